@@ -0,0 +1,225 @@
+//! Derive macros for [oai_rs](https://docs.rs/oai_rs/), enable them with the `derive` feature
+//! of `oai_rs` rather than depending on this crate directly.
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::meta::ParseNestedMeta;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Expr, Fields, Lit, LitStr, Meta};
+
+/// Implements `oai_rs::tools::Tool` for a struct with named fields
+///
+/// The function name is the struct name in snake case, the description is the struct's doc
+/// comment and every field becomes a parameter described by its own doc comment. Fields that
+/// are an `Option` are left out of `required`. The struct must also implement `Deserialize`,
+/// parameters are named like its fields are deserialized, following `#[serde(rename)]` and
+/// `#[serde(rename_all)]`, and fields skipped with `#[serde(skip)]` are left out.
+#[proc_macro_derive(OaiTool)]
+pub fn derive_tool(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return error(&input, "OaiTool can only be derived for structs with named fields"),
+        },
+        _ => return error(&input, "OaiTool can only be derived for structs"),
+    };
+
+    let ident = &input.ident;
+    let name = snake_case(&ident.to_string());
+    let description = match doc_comment(&input.attrs) {
+        Some(doc) => quote!(::std::option::Option::Some(#doc)),
+        None => quote!(::std::option::Option::None),
+    };
+
+    let container = match serde_attrs(&input.attrs) {
+        Ok(attrs) => attrs,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let mut properties = Vec::new();
+    for field in fields.iter() {
+        let attrs = match serde_attrs(&field.attrs) {
+            Ok(attrs) => attrs,
+            Err(e) => return e.to_compile_error().into(),
+        };
+        if attrs.skip {
+            continue;
+        }
+        let field_name = match attrs.rename {
+            Some(rename) => rename,
+            None => {
+                let ident = field.ident.as_ref().map(|i| i.to_string()).unwrap_or_default();
+                let ident = ident.trim_start_matches("r#");
+                match &container.rename_all {
+                    Some(rule) => match rename_field(ident, &rule.value()) {
+                        Some(renamed) => renamed,
+                        None => {
+                            let message = format!("unknown rename_all rule \"{}\"", rule.value());
+                            return syn::Error::new_spanned(rule, message).to_compile_error().into();
+                        }
+                    },
+                    None => ident.to_string(),
+                }
+            }
+        };
+        let ty = &field.ty;
+        let describe = doc_comment(&field.attrs).map(|doc| quote! {
+            schema["description"] = ::oai_rs::tools::__private::Value::from(#doc);
+        });
+        properties.push(quote! {
+            let mut schema = <#ty as ::oai_rs::tools::ToolParameter>::schema();
+            #describe
+            properties.insert(::std::string::String::from(#field_name), schema);
+            if <#ty as ::oai_rs::tools::ToolParameter>::REQUIRED {
+                required.push(#field_name);
+            }
+        });
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let expanded = quote! {
+        impl #impl_generics ::oai_rs::tools::Tool for #ident #ty_generics #where_clause {
+            const NAME: &'static str = #name;
+
+            fn description() -> ::std::option::Option<&'static str> {
+                #description
+            }
+
+            fn parameters() -> ::oai_rs::tools::__private::Value {
+                let mut properties = ::oai_rs::tools::__private::Map::new();
+                let mut required: ::std::vec::Vec<&str> = ::std::vec::Vec::new();
+                #(#properties)*
+                ::oai_rs::tools::__private::json!({
+                    "type": "object",
+                    "properties": properties,
+                    "required": required,
+                })
+            }
+        }
+    };
+    expanded.into()
+}
+
+fn error(input: &DeriveInput, message: &str) -> TokenStream {
+    syn::Error::new_spanned(&input.ident, message).to_compile_error().into()
+}
+
+/// The `#[serde(...)]` attributes that change how fields are deserialized
+#[derive(Default)]
+struct SerdeAttrs {
+    rename: Option<String>,
+    rename_all: Option<LitStr>,
+    skip: bool,
+}
+
+fn serde_attrs(attrs: &[Attribute]) -> syn::Result<SerdeAttrs> {
+    let mut serde = SerdeAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                serde.rename = deserialize_name(&meta)?.map(|name| name.value());
+            } else if meta.path.is_ident("rename_all") {
+                serde.rename_all = deserialize_name(&meta)?;
+            } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                serde.skip = true;
+            } else if meta.path.is_ident("flatten") {
+                return Err(meta.error("OaiTool doesn't support #[serde(flatten)]"));
+            } else {
+                skip_value(&meta)?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(serde)
+}
+
+/// The name of `rename = "..."`, or of `deserialize` in `rename(serialize = "...", deserialize = "...")`
+fn deserialize_name(meta: &ParseNestedMeta) -> syn::Result<Option<LitStr>> {
+    if meta.input.peek(syn::Token![=]) {
+        return Ok(Some(meta.value()?.parse()?));
+    }
+    let mut name = None;
+    meta.parse_nested_meta(|inner| {
+        if inner.path.is_ident("deserialize") {
+            name = Some(inner.value()?.parse()?);
+        } else {
+            skip_value(&inner)?;
+        }
+        Ok(())
+    })?;
+    Ok(name)
+}
+
+/// Consume the value of an attribute that doesn't affect the schema, e.g. `default = "..."`
+fn skip_value(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(syn::Token![=]) {
+        meta.value()?.parse::<Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(|inner| skip_value(&inner))?;
+    }
+    Ok(())
+}
+
+/// The name serde gives a snake case field under a `rename_all` rule, `None` for unknown rules
+fn rename_field(field: &str, rule: &str) -> Option<String> {
+    let pascal = || field.split('_').map(|word| {
+        let mut chars = word.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+            None => String::new(),
+        }
+    }).collect::<String>();
+    let renamed = match rule {
+        "lowercase" | "snake_case" => field.to_string(),
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => field.to_ascii_uppercase(),
+        "PascalCase" => pascal(),
+        "camelCase" => {
+            let pascal = pascal();
+            let mut chars = pascal.chars();
+            match chars.next() {
+                Some(first) => first.to_lowercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+        "kebab-case" => field.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field.to_ascii_uppercase().replace('_', "-"),
+        _ => return None,
+    };
+    Some(renamed)
+}
+
+/// The `///` lines of an item, trimmed and joined with spaces
+fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs.iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(expr) => match &expr.lit {
+                    Lit::Str(s) => Some(s.value().trim().to_string()),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        })
+        .filter(|line| !line.is_empty())
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+    Some(lines.join(" "))
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in name.char_indices() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
@@ -0,0 +1,152 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::models::ChatModels;
+use crate::requester::{self, Query, RequestOptions};
+use crate::schema::{ResponseFormat, Schema};
+use crate::tools::ToolDefinition;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Assistant {
+    pub id: String,
+    pub object: String,
+    pub created_at: u64,
+    pub model: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub instructions: Option<String>,
+    #[serde(default)]
+    pub tools: Vec<ToolDefinition>,
+    /// `auto`, or the JSON or structured output format the assistant answers in
+    #[serde(default)]
+    pub response_format: Option<Value>,
+}
+
+/// Available parameters that can be sent with an assistant request
+pub struct Parameters<'a> {
+    options: RequestOptions,
+    model: ChatModels,
+    query: Query<'a>,
+}
+
+/// Function to create an assistant request
+///
+/// Call it using [`build`] and add valid [`Parameters`] to the request to build an
+/// assistant and close with `create()`.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{assistants, models};
+///
+/// async {
+///     let assistant = assistants::build(models::ChatModels::GPT_4O_MINI)
+///         .name("Librarian")
+///         .instructions("Answer questions about the attached books.")
+///         .create()
+///         .await
+///         .expect("Error Getting Response");
+///
+///     println!("{:?}", assistant.id);
+/// };
+/// ```
+pub fn build<'a, M: Into<ChatModels>>(model: M) -> Parameters<'a> {
+    Parameters {
+        options: RequestOptions::default(),
+        model: model.into(),
+        query: Query::new(),
+    }
+}
+
+impl<'a> Parameters<'a> {
+    /// Send the request using the given client instead of one loaded from the environment
+    pub fn client(mut self, client: &Client) -> Self {
+        self.options.client = Some(client.clone());
+        self
+    }
+
+    /// The name of the assistant, at most 256 characters.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/assistants/createAssistant#assistants-createassistant-name)
+    pub fn name(mut self, input: &'a str) -> Self {
+        self.query.push(("name", json!(input)));
+        self
+    }
+
+    /// The description of the assistant, at most 512 characters.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/assistants/createAssistant#assistants-createassistant-description)
+    pub fn description(mut self, input: &'a str) -> Self {
+        self.query.push(("description", json!(input)));
+        self
+    }
+
+    /// The system instructions that the assistant uses.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/assistants/createAssistant#assistants-createassistant-instructions)
+    pub fn instructions(mut self, input: &'a str) -> Self {
+        self.query.push(("instructions", json!(input)));
+        self
+    }
+
+    /// Tools enabled on the assistant, e.g. [`ToolDefinition::file_search`].
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/assistants/createAssistant#assistants-createassistant-tools)
+    pub fn tools(mut self, input: &'a [ToolDefinition]) -> Self {
+        self.query.push(("tools", json!(input)));
+        self
+    }
+
+    /// The format the assistant answers in, see [`ResponseFormat`]
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/assistants/createAssistant#assistants-createassistant-response_format)
+    pub fn response_format(mut self, input: ResponseFormat) -> Self {
+        self.query.push(("response_format", json!(input)));
+        self
+    }
+
+    /// Have the assistant answer with a JSON object.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/assistants/createAssistant#assistants-createassistant-response_format)
+    pub fn json_object(self) -> Self {
+        self.response_format(ResponseFormat::JsonObject)
+    }
+
+    /// Have the assistant answer with JSON matching `schema`, using structured outputs.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/assistants/createAssistant#assistants-createassistant-response_format)
+    pub fn json_schema(self, name: &'a str, schema: &'a Schema) -> Self {
+        self.response_format(ResponseFormat::json_schema(name, schema.clone()))
+    }
+
+    /// Mark the request as safe to retry even when the client's retry policy
+    /// is restricted to idempotent requests, see [`RetryScope::IdempotentOnly`](crate::retry::RetryScope::IdempotentOnly)
+    pub fn idempotent(mut self) -> Self {
+        self.options.idempotent = true;
+        self
+    }
+
+    /// Set a parameter this crate doesn't model yet, sent as is in the request body
+    ///
+    /// Replaces the value set for `key` by earlier setters, and is replaced by later ones.
+    pub fn extra_param(mut self, key: &'a str, value: Value) -> Self {
+        self.query.push((key, value));
+        self
+    }
+
+    /// Complete the request and send
+    pub async fn create(mut self) -> Result<Assistant, Error> {
+        self.query.push(("model", json!(self.model.as_str())));
+
+        let response: Result<Assistant, Error> = requester::assistants(&self.options, &self.query).await;
+
+        match response {
+            Ok(t) => Ok(t),
+            Err(e) => Err(e),
+        }
+    }
+}
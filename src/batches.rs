@@ -0,0 +1,101 @@
+use crate::client::Client;
+use crate::error::{ApiError, Error};
+use crate::files;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Why a request in a batch failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchFailure {
+    /// The request was run and the API responded with an error
+    Api(ApiError),
+    /// The request was not run, e.g. because the batch expired first
+    NotRun {
+        code: Option<String>,
+        message: String,
+    },
+}
+
+/// A request from a batch's input file that failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedRequest {
+    /// The `custom_id` the request was given in the input file
+    pub custom_id: String,
+    /// The `x-request-id` of the failed response, if the request was run
+    pub request_id: Option<String>,
+    pub failure: BatchFailure,
+}
+
+#[derive(Deserialize)]
+struct ErrorLine {
+    custom_id: String,
+    response: Option<ErrorResponse>,
+    error: Option<LineError>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    status_code: u16,
+    request_id: Option<String>,
+    #[serde(default)]
+    body: Value,
+}
+
+#[derive(Deserialize)]
+struct LineError {
+    code: Option<String>,
+    #[serde(default)]
+    message: String,
+}
+
+/// Parse the content of a batch's error file into the failed requests, in file order
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::batches::{self, BatchFailure};
+///
+/// let content = concat!(
+///     r#"{"id": "batch_req_1", "custom_id": "request-1", "response": {"status_code": 400, "request_id": "req_abc", "body": {"error": {"message": "Invalid model", "type": "invalid_request_error", "param": "model", "code": null}}}, "error": null}"#, "\n",
+///     r#"{"id": "batch_req_2", "custom_id": "request-2", "response": null, "error": {"code": "batch_expired", "message": "This request could not be executed before the completion window expired."}}"#, "\n",
+/// );
+///
+/// let failed = batches::parse_errors(content).unwrap();
+/// assert_eq!(failed[0].custom_id, "request-1");
+/// assert!(matches!(&failed[0].failure, BatchFailure::Api(e) if e.param.as_deref() == Some("model")));
+/// assert!(matches!(&failed[1].failure, BatchFailure::NotRun { code: Some(code), .. } if code == "batch_expired"));
+/// ```
+pub fn parse_errors(content: &str) -> Result<Vec<FailedRequest>, Error> {
+    content.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_error_line)
+        .collect()
+}
+
+fn parse_error_line(line: &str) -> Result<FailedRequest, Error> {
+    let parsed: ErrorLine = serde_json::from_str(line).map_err(|e| Error::deserialize(e, line))?;
+    let (request_id, failure) = match (parsed.response, parsed.error) {
+        (Some(response), _) => {
+            let status = StatusCode::from_u16(response.status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            (response.request_id, BatchFailure::Api(ApiError::from_body(status, &response.body.to_string())))
+        }
+        (None, Some(error)) => (None, BatchFailure::NotRun { code: error.code, message: error.message }),
+        (None, None) => (None, BatchFailure::NotRun { code: None, message: String::new() }),
+    };
+    Ok(FailedRequest {
+        custom_id: parsed.custom_id,
+        request_id,
+        failure,
+    })
+}
+
+/// Download a batch's error file and parse it into the failed requests, see [`parse_errors`]
+pub async fn errors(error_file_id: &str) -> Result<Vec<FailedRequest>, Error> {
+    errors_with(None, error_file_id).await
+}
+
+pub(crate) async fn errors_with(client: Option<&Client>, error_file_id: &str) -> Result<Vec<FailedRequest>, Error> {
+    let content = files::content_with(client, error_file_id).await?;
+    parse_errors(&String::from_utf8_lossy(&content))
+}
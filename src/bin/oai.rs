@@ -0,0 +1,179 @@
+//! A small command line client built on the library
+//!
+//! Install it with `cargo install oai_rs --features cli`, the API key is read from
+//! `OPENAI_API_KEY` like [`Client::from_env`] does.
+//!
+//! ```text
+//! oai chat [--model <model>] [--system <instructions>] <prompt>
+//! oai complete [--model <model>] [--max-tokens <n>] <prompt>
+//! oai image [--size <size>] [--n <n>] [--out <dir>] <prompt>
+//! oai models
+//! ```
+use oai_rs::chat::{self, ChatMessage};
+use oai_rs::stream::Flush;
+use oai_rs::{completions, images, Client, Error};
+use std::collections::HashMap;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: oai <command> [options] <prompt>
+
+Commands:
+  chat      Answer a prompt with a chat model
+            --model <model>          defaults to gpt-4o-mini
+            --system <instructions>  system message sent before the prompt
+  complete  Complete a prompt, streaming the text as it's generated
+            --model <model>          defaults to gpt-3.5-turbo-instruct
+            --max-tokens <n>         defaults to 256
+  image     Generate images and save them as PNG files
+            --size <size>            e.g. 1024x1024
+            --n <n>                  number of images, defaults to 1
+            --out <dir>              defaults to the current directory
+  models    List the models available to the API key";
+
+/// The options and prompt of a command
+struct Args {
+    options: HashMap<String, String>,
+    prompt: String,
+}
+
+impl Args {
+    /// Split `--name value` pairs from the words of the prompt
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Args, String> {
+        let mut options = HashMap::new();
+        let mut words = Vec::new();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    let value = args.next().ok_or_else(|| format!("missing value for --{}", name))?;
+                    options.insert(name.to_string(), value);
+                }
+                None => words.push(arg),
+            }
+        }
+        Ok(Args { options, prompt: words.join(" ") })
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    fn number<T: std::str::FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        match self.option(name) {
+            Some(value) => value.parse().map_err(|_| format!("--{} must be a number, got {}", name, value)),
+            None => Ok(default),
+        }
+    }
+
+    fn require_prompt(&self) -> Result<&str, String> {
+        if self.prompt.is_empty() {
+            return Err(String::from("missing prompt"));
+        }
+        Ok(&self.prompt)
+    }
+}
+
+async fn chat(client: &Client, args: &Args) -> Result<(), Error> {
+    let mut messages = Vec::new();
+    if let Some(system) = args.option("system") {
+        messages.push(ChatMessage::system(system));
+    }
+    messages.push(ChatMessage::user(args.prompt.as_str()));
+
+    let completion = chat::build(args.option("model").unwrap_or("gpt-4o-mini"))
+        .client(client)
+        .messages(&messages)
+        .complete()
+        .await?;
+    for choice in completion.choices.iter() {
+        println!("{}", choice.message.text());
+    }
+    Ok(())
+}
+
+async fn complete(client: &Client, args: &Args, max_tokens: u16) -> Result<(), Error> {
+    let mut stdout = tokio::io::stdout();
+    completions::build(args.option("model").unwrap_or("gpt-3.5-turbo-instruct"))
+        .client(client)
+        .prompt(&args.prompt)
+        .max_tokens(max_tokens)
+        .write_to(&mut stdout, Flush::EveryChunk)
+        .await?;
+    println!();
+    Ok(())
+}
+
+async fn image(client: &Client, args: &Args, n: u8) -> Result<(), Error> {
+    let mut request = images::build()
+        .client(client)
+        .generate(args.prompt.clone())
+        .n(&n)
+        .response_format("b64_json");
+    if let Some(size) = args.option("size") {
+        request = request.size(size);
+    }
+    let images = request.done().await?;
+    for path in images.download_all(args.option("out").unwrap_or("."), 4).await? {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+async fn models(client: &Client) -> Result<(), Error> {
+    let mut models = client.models().await?;
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    for model in models.iter() {
+        println!("{}", model.id);
+    }
+    Ok(())
+}
+
+async fn run(command: &str, args: Args) -> Result<(), String> {
+    let client = Client::from_env().map_err(|e| e.to_string())?;
+    let result = match command {
+        "chat" => {
+            args.require_prompt()?;
+            chat(&client, &args).await
+        }
+        "complete" => {
+            args.require_prompt()?;
+            let max_tokens = args.number("max-tokens", 256)?;
+            complete(&client, &args, max_tokens).await
+        }
+        "image" => {
+            args.require_prompt()?;
+            let n = args.number("n", 1)?;
+            image(&client, &args, n).await
+        }
+        "models" => models(&client).await,
+        _ => return Err(format!("unknown command {}\n\n{}", command, USAGE)),
+    };
+    result.map_err(|e| e.to_string())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let command = match args.next() {
+        Some(command) if command != "--help" && command != "-h" => command,
+        _ => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+    };
+    let args = match Args::parse(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("oai: {}\n\n{}", e, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(&command, args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("oai: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
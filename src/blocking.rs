@@ -0,0 +1,61 @@
+//! Blocking variants of the download helpers, for synchronous programs
+//!
+//! Each function runs its async counterpart to completion on a small runtime owned by this
+//! module, so callers don't need to set up an async runtime of their own. Calling them from
+//! within an async runtime panics, use the async functions there instead.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use oai_rs::{blocking, Client};
+//!
+//! fn save_report(client: &Client) -> Result<(), oai_rs::Error> {
+//!     blocking::download_file(client, "file-abc123", "./report.jsonl")
+//! }
+//! ```
+use crate::client::Client;
+use crate::error::Error;
+use crate::files;
+use crate::images::{ImageData, Images};
+use bytes::Bytes;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tokio::runtime::Runtime;
+
+/// The runtime every blocking call runs on, kept for the lifetime of the program so pooled
+/// connections stay usable between calls
+fn block_on<F: Future>(future: F) -> Result<F::Output, Error> {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    let runtime = match RUNTIME.get() {
+        Some(runtime) => runtime,
+        None => {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(Error::Io)?;
+            RUNTIME.get_or_init(|| runtime)
+        }
+    };
+    Ok(runtime.block_on(future))
+}
+
+/// The content of the file with the given identifier, see [`files::content`]
+pub fn file_content(client: &Client, file_id: &str) -> Result<Bytes, Error> {
+    block_on(files::content_with(Some(client), file_id))?
+}
+
+/// Download the content of a file into `path`, see [`files::download`]
+pub fn download_file<P: AsRef<Path>>(client: &Client, file_id: &str, path: P) -> Result<(), Error> {
+    block_on(files::download_with(Some(client), file_id, path.as_ref()))?
+}
+
+/// The PNG bytes of a generated image, see [`ImageData::bytes`]
+pub fn image_bytes(image: &ImageData) -> Result<Bytes, Error> {
+    block_on(image.bytes())?
+}
+
+/// Download every generated image into `dir`, see [`Images::download_all`]
+pub fn download_images<P: AsRef<Path>>(images: &Images, dir: P, concurrency: usize) -> Result<Vec<PathBuf>, Error> {
+    block_on(images.download_all(dir, concurrency))?
+}
@@ -0,0 +1,258 @@
+use crate::error::Error;
+use crate::usage::Usage;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Called with the amount spent in the current window when a [`Budget`] is exceeded
+pub type BudgetCallback = Arc<dyn Fn(&Spent) + Send + Sync>;
+
+/// Called with the model's name when a dollar [`Budget`] can't estimate what it spent
+pub type UnpricedCallback = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// What a [`Budget`] is limited by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Tokens(u64),
+    /// Estimated from the token usage with the prices set with [`Budget::prices`], or the
+    /// list prices of [`Usage::estimated_cost`] unless set, see [`Budget::dollars`]
+    Dollars(f64),
+}
+
+/// Tokens and estimated dollars spent in the current window
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Spent {
+    pub tokens: u64,
+    pub dollars: f64,
+    /// Tokens spent on models without a price, not included in `dollars`
+    pub unpriced_tokens: u64,
+}
+
+/// Limits how much a client can spend in a window of time
+///
+/// Usage is recorded from every response that reports it, including the last chunk of a
+/// stream sent with `include_usage`. Once the limit is reached the
+/// remaining requests in the window fail immediately with
+/// [`Error::BudgetExceeded`](crate::Error::BudgetExceeded), or are let through after calling
+/// the callback set with [`on_exceeded`](Budget::on_exceeded). A request is only rejected
+/// after the limit was reached, so the last request let through may go over the limit.
+///
+/// The budget is shared between all clones of the [`Client`](crate::Client) it is set on.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{Client, budget::Budget};
+/// use std::time::Duration;
+///
+/// // At most $5 an hour, at list prices
+/// let client = Client::new(String::from("sk-..."))
+///     .budget(Budget::dollars(5.0, Duration::from_secs(60 * 60)));
+/// ```
+#[derive(Clone)]
+pub struct Budget {
+    limit: Limit,
+    window: Duration,
+    prices: Option<(f64, f64)>,
+    on_exceeded: Option<BudgetCallback>,
+    on_unpriced: Option<UnpricedCallback>,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug)]
+struct State {
+    window_start: Instant,
+    spent: Spent,
+    /// Models spent on whose cost can't be estimated, reported once per window
+    unpriced: HashSet<String>,
+}
+
+impl Budget {
+    fn new(limit: Limit, window: Duration) -> Self {
+        Budget {
+            limit,
+            window,
+            prices: None,
+            on_exceeded: None,
+            on_unpriced: None,
+            state: Arc::new(Mutex::new(State {
+                window_start: Instant::now(),
+                spent: Spent::default(),
+                unpriced: HashSet::new(),
+            })),
+        }
+    }
+
+    /// Allow up to `max_tokens` total tokens in every `window`
+    pub fn tokens(max_tokens: u64, window: Duration) -> Self {
+        Budget::new(Limit::Tokens(max_tokens), window)
+    }
+
+    /// Allow up to `max_dollars` of estimated spend in every `window`
+    ///
+    /// Spend is estimated with the list prices of [`Usage::estimated_cost`] unless
+    /// [`prices`](Budget::prices) are set. Spend on a model without a list price, e.g. an
+    /// audio model or one newer than this release, can't be estimated. It doesn't count
+    /// towards the limit, only towards [`Spent::unpriced_tokens`], and is reported to the
+    /// callback set with [`on_unpriced`](Budget::on_unpriced). Set [`prices`](Budget::prices)
+    /// to budget for those models.
+    pub fn dollars(max_dollars: f64, window: Duration) -> Self {
+        Budget::new(Limit::Dollars(max_dollars), window)
+    }
+
+    /// Prices in dollars per million prompt and completion tokens, used to estimate spend
+    /// for every model instead of the list prices
+    pub fn prices(mut self, prompt: f64, completion: f64) -> Self {
+        self.prices = Some((prompt, completion));
+        self
+    }
+
+    /// Call `callback` and send the request anyway instead of failing once the budget is exceeded
+    pub fn on_exceeded<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Spent) + Send + Sync + 'static
+    {
+        self.on_exceeded = Some(Arc::new(callback));
+        self
+    }
+
+    /// Call `callback` with the model's name the first time in a window that a dollar budget
+    /// can't estimate the spend on it, e.g. to log it or alert on it
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{Client, budget::Budget};
+    /// use std::time::Duration;
+    ///
+    /// let client = Client::new(String::from("sk-..."))
+    ///     .budget(Budget::dollars(5.0, Duration::from_secs(60 * 60))
+    ///         .on_unpriced(|model| eprintln!("no price for {}, not budgeted", model)));
+    /// ```
+    pub fn on_unpriced<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static
+    {
+        self.on_unpriced = Some(Arc::new(callback));
+        self
+    }
+
+    /// Tokens and estimated dollars spent in the current window
+    pub fn spent(&self) -> Spent {
+        self.current().spent
+    }
+
+    fn current(&self) -> std::sync::MutexGuard<'_, State> {
+        let mut state = self.state.lock().unwrap();
+        if state.window_start.elapsed() >= self.window {
+            state.window_start = Instant::now();
+            state.spent = Spent::default();
+            state.unpriced.clear();
+        }
+        state
+    }
+
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        let spent = self.spent();
+        let exceeded = match self.limit {
+            Limit::Tokens(max) => spent.tokens >= max,
+            Limit::Dollars(max) => spent.dollars >= max,
+        };
+        if !exceeded {
+            return Ok(());
+        }
+        match &self.on_exceeded {
+            Some(callback) => {
+                callback(&spent);
+                Ok(())
+            }
+            None => Err(Error::BudgetExceeded),
+        }
+    }
+
+    pub(crate) fn record(&self, model: Option<&str>, usage: &Usage) {
+        let dollars = match self.prices {
+            Some((prompt, completion)) => Some((usage.prompt_tokens.max(0) as f64 * prompt
+                + usage.completion_tokens.max(0) as f64 * completion) / 1_000_000.0),
+            None => model.and_then(|model| usage.estimated_cost(model)),
+        };
+        let tokens = usage.total_tokens.max(0) as u64;
+        let mut state = self.current();
+        state.spent.tokens += tokens;
+        let unpriced = match dollars {
+            Some(dollars) => {
+                state.spent.dollars += dollars;
+                None
+            }
+            None => {
+                state.spent.unpriced_tokens += tokens;
+                model.filter(|model| tokens > 0 && state.unpriced.insert(model.to_string()))
+            }
+        };
+        drop(state);
+        if let (Some(model), Some(callback), Limit::Dollars(_)) = (unpriced, &self.on_unpriced, self.limit) {
+            callback(model);
+        }
+    }
+}
+
+impl fmt::Debug for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Budget")
+            .field("limit", &self.limit)
+            .field("window", &self.window)
+            .field("prices", &self.prices)
+            .field("spent", &self.spent())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Budget;
+    use crate::error::Error;
+    use crate::usage::Usage;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    fn usage(tokens: i32) -> Usage {
+        Usage { prompt_tokens: tokens, total_tokens: tokens, ..Usage::default() }
+    }
+
+    #[test]
+    fn unpriced_models_are_reported_without_exceeding_a_dollar_budget() {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let budget = Budget::dollars(5.0, Duration::from_secs(60)).on_unpriced({
+            let reported = reported.clone();
+            move |model| reported.lock().unwrap().push(model.to_string())
+        });
+        budget.record(Some("gpt-4o-mini"), &usage(1000));
+        budget.record(Some("gpt-4o-audio-preview"), &usage(10));
+        budget.record(Some("gpt-4o-audio-preview"), &usage(10));
+        assert!(budget.check().is_ok());
+        assert_eq!(*reported.lock().unwrap(), ["gpt-4o-audio-preview"]);
+        assert_eq!(budget.spent().tokens, 1020);
+        assert_eq!(budget.spent().unpriced_tokens, 20);
+
+        budget.record(Some("gpt-4o-mini"), &usage(40_000_000));
+        assert!(matches!(budget.check(), Err(Error::BudgetExceeded)));
+    }
+
+    #[test]
+    fn set_prices_cover_every_model() {
+        let budget = Budget::dollars(5.0, Duration::from_secs(60)).prices(1.0, 1.0);
+        budget.record(Some("o3"), &usage(1000));
+        budget.record(None, &usage(1000));
+        assert!(budget.check().is_ok());
+        assert_eq!(budget.spent().dollars, 0.002);
+    }
+
+    #[test]
+    fn unpriced_models_count_towards_a_token_budget() {
+        let budget = Budget::tokens(100, Duration::from_secs(60));
+        budget.record(Some("o3"), &usage(10));
+        assert!(budget.check().is_ok());
+        assert_eq!(budget.spent().tokens, 10);
+    }
+}
@@ -0,0 +1,453 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Somewhere response bodies can be cached, keyed by request
+///
+/// Keys are the endpoint path followed by the hex encoded SHA-256 checksum of the request
+/// body and the client's API key, organization, project and base URL, e.g.
+/// `embeddings/3b1f…`, so every entry for an endpoint shares the endpoint's path as a
+/// prefix and clients of different accounts can share a cache. Implementations are shared between requests and have to handle
+/// concurrent use.
+pub trait CacheBackend: Send + Sync {
+    /// The cached response body for `key`, `None` if it isn't cached or has expired
+    fn get(&self, key: &str) -> Option<String>;
+
+    /// Cache the response body for `key`, replacing any body cached for it before
+    fn put(&self, key: &str, body: &str);
+
+    /// Remove every entry whose key starts with `prefix`, returning how many were removed
+    fn invalidate_prefix(&self, prefix: &str) -> usize;
+}
+
+/// Caches responses in memory, with optional expiry and size limits
+///
+/// Entries expire `ttl` after they were cached. Once the cache holds more than
+/// `max_entries` entries or `max_bytes` bytes of response bodies, the least recently used
+/// entries are evicted until it fits again. Without limits the cache grows for as long as
+/// the program runs.
+///
+/// The cache is shared between all of its clones, so a clone can be kept to invalidate
+/// entries after it's set on a [`Client`](crate::Client).
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{cache::{CacheBackend, MemoryCache}, Client};
+/// use std::time::Duration;
+///
+/// let cache = MemoryCache::new()
+///     .ttl(Duration::from_secs(60 * 60))
+///     .max_entries(10_000)
+///     .max_bytes(64 * 1024 * 1024);
+/// let client = Client::new(String::from("sk-...")).response_cache(cache.clone());
+///
+/// // Drop every cached embedding, e.g. after switching embedding models
+/// cache.invalidate_prefix("embeddings/");
+/// ```
+#[derive(Clone, Default)]
+pub struct MemoryCache {
+    ttl: Option<Duration>,
+    max_entries: Option<usize>,
+    max_bytes: Option<usize>,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<String, Entry>,
+    /// Keys by when they were last used, least recently used first
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+    bytes: usize,
+}
+
+struct Entry {
+    body: String,
+    expires_at: Option<Instant>,
+    used: u64,
+}
+
+impl State {
+    fn remove(&mut self, key: &str) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+        self.recency.remove(&entry.used);
+        self.bytes -= entry.body.len();
+        Some(entry)
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+impl MemoryCache {
+    /// An empty cache without expiry or size limits
+    pub fn new() -> Self {
+        MemoryCache::default()
+    }
+
+    /// Expire entries `ttl` after they were cached
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Keep at most `max_entries` entries, evicting the least recently used
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Keep at most `max_bytes` bytes of response bodies, evicting the least recently used
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// The number of entries cached, including any that expired but weren't looked up since
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Whether nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove every entry
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.clear();
+        state.recency.clear();
+        state.bytes = 0;
+    }
+
+    /// Evict the least recently used entries until the cache is within its limits
+    fn evict(&self, state: &mut State) {
+        let over = |state: &State| {
+            self.max_entries.is_some_and(|max| state.entries.len() > max)
+                || self.max_bytes.is_some_and(|max| state.bytes > max)
+        };
+        while over(state) {
+            let Some((_, key)) = state.recency.pop_first() else {
+                return;
+            };
+            if let Some(entry) = state.entries.remove(&key) {
+                state.bytes -= entry.body.len();
+            }
+        }
+    }
+}
+
+impl CacheBackend for MemoryCache {
+    fn get(&self, key: &str) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        let expired = state.entries.get(key)?.expires_at.is_some_and(|at| at <= Instant::now());
+        if expired {
+            state.remove(key);
+            return None;
+        }
+        let tick = state.next_tick();
+        let entry = state.entries.get_mut(key)?;
+        let previous = std::mem::replace(&mut entry.used, tick);
+        let body = entry.body.clone();
+        state.recency.remove(&previous);
+        state.recency.insert(tick, key.to_string());
+        Some(body)
+    }
+
+    fn put(&self, key: &str, body: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.remove(key);
+        let tick = state.next_tick();
+        state.entries.insert(key.to_string(), Entry {
+            body: body.to_string(),
+            expires_at: self.ttl.map(|ttl| Instant::now() + ttl),
+            used: tick,
+        });
+        state.recency.insert(tick, key.to_string());
+        state.bytes += body.len();
+        self.evict(&mut state);
+    }
+
+    fn invalidate_prefix(&self, prefix: &str) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let keys: Vec<String> = state.entries.keys().filter(|k| k.starts_with(prefix)).cloned().collect();
+        for key in keys.iter() {
+            state.remove(key);
+        }
+        keys.len()
+    }
+}
+
+impl fmt::Debug for MemoryCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryCache")
+            .field("ttl", &self.ttl)
+            .field("max_entries", &self.max_entries)
+            .field("max_bytes", &self.max_bytes)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Caches responses as files in a directory, so they survive restarts
+///
+/// Each entry is stored at its key below the directory, e.g.
+/// `{dir}/embeddings/3b1f…`, written to a temporary file first so an interrupted write
+/// never leaves a partial entry. Only files named by a checksum are treated as entries, so
+/// invalidating or clearing the cache leaves other files in the directory alone. Entries expire `ttl` after they were written, going by the
+/// file's modification time. Files are read and written synchronously, which suits
+/// development runs and batch jobs better than latency sensitive services.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{cache::DiskCache, models, Client};
+///
+/// async {
+///     let cache = DiskCache::new("./.oai-cache").expect("Error Creating Cache");
+///     let client = Client::from_env().expect("Missing configuration").response_cache(cache);
+///
+///     // Only the first run of this sends the request, later runs read the cached answer
+///     let embeddings = client.embeddings(models::EmbeddingModels::TEXT_EMBEDDING_3_SMALL)
+///         .input("Ice cream or cookies?")
+///         .embed()
+///         .await;
+///
+///     println!("{:?}", embeddings);
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+    ttl: Option<Duration>,
+}
+
+impl DiskCache {
+    /// A cache in `dir`, created if it doesn't exist, keeping any entries already in it
+    pub fn new<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        Ok(DiskCache { dir, ttl: None })
+    }
+
+    /// Expire entries `ttl` after they were written
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Remove every entry, along with the directories that are left empty
+    pub fn clear(&self) -> io::Result<()> {
+        let mut entries = Vec::new();
+        self.entries(&self.dir, &mut entries);
+        for (_, path) in entries.iter() {
+            match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        for (_, path) in entries.iter() {
+            let mut dir = path.parent();
+            while let Some(parent) = dir.filter(|d| *d != self.dir) {
+                if fs::remove_dir(parent).is_err() {
+                    break;
+                }
+                dir = parent.parent();
+            }
+        }
+        Ok(())
+    }
+
+    /// The file an entry is stored in, `None` for keys that would escape the directory
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        if key.is_empty() || key.split('/').any(|part| part.is_empty() || part == "." || part == "..") {
+            return None;
+        }
+        Some(self.dir.join(key))
+    }
+
+    fn expired(&self, path: &Path) -> bool {
+        let Some(ttl) = self.ttl else {
+            return false;
+        };
+        let written = fs::metadata(path).and_then(|m| m.modified());
+        match written.map(|at| SystemTime::now().duration_since(at)) {
+            Ok(Ok(age)) => age >= ttl,
+            Ok(Err(_)) => false,
+            Err(_) => true,
+        }
+    }
+
+    /// Every entry file below `dir` along with its key, skipping files not named by a checksum
+    fn entries(&self, dir: &Path, entries: &mut Vec<(String, PathBuf)>) {
+        let Ok(read) = fs::read_dir(dir) else {
+            return;
+        };
+        let checksum = |name: &str| name.len() == 64 && name.bytes().all(|b| b.is_ascii_hexdigit());
+        for entry in read.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                self.entries(&path, entries);
+                continue;
+            }
+            if !path.file_name().and_then(|n| n.to_str()).is_some_and(checksum) {
+                continue;
+            }
+            if let Ok(relative) = path.strip_prefix(&self.dir) {
+                let key = relative.components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                entries.push((key, path));
+            }
+        }
+    }
+}
+
+impl CacheBackend for DiskCache {
+    fn get(&self, key: &str) -> Option<String> {
+        let path = self.path(key)?;
+        if self.expired(&path) {
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+        fs::read_to_string(&path).ok()
+    }
+
+    fn put(&self, key: &str, body: &str) {
+        let Some(path) = self.path(key) else {
+            return;
+        };
+        let Some(parent) = path.parent() else {
+            return;
+        };
+        let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+        let written = fs::create_dir_all(parent)
+            .and_then(|_| fs::write(&temporary, body))
+            .and_then(|_| fs::rename(&temporary, &path));
+        if written.is_err() {
+            let _ = fs::remove_file(&temporary);
+        }
+    }
+
+    fn invalidate_prefix(&self, prefix: &str) -> usize {
+        let mut entries = Vec::new();
+        self.entries(&self.dir, &mut entries);
+        entries.into_iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .filter(|(_, path)| fs::remove_file(path).is_ok())
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CacheBackend, DiskCache, MemoryCache};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    const KEY: &str = "embeddings/3b1f00000000000000000000000000000000000000000000000000000000abcd";
+
+    #[test]
+    fn memory_cache_evicts_least_recently_used() {
+        let cache = MemoryCache::new().max_entries(2);
+        cache.put("a", "1");
+        cache.put("b", "2");
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+        cache.put("c", "3");
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+        assert_eq!(cache.get("c").as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn memory_cache_stays_within_max_bytes() {
+        let cache = MemoryCache::new().max_bytes(10);
+        cache.put("a", "12345");
+        cache.put("b", "12345");
+        cache.put("c", "123");
+
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.get("b").as_deref(), Some("12345"));
+        assert_eq!(cache.get("c").as_deref(), Some("123"));
+
+        // Replacing an entry frees the bytes of the old body
+        cache.put("b", "1");
+        cache.put("d", "123456");
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn memory_cache_expires_entries() {
+        let cache = MemoryCache::new().ttl(Duration::from_millis(20));
+        cache.put("a", "1");
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+
+        std::thread::sleep(Duration::from_millis(40));
+        assert_eq!(cache.get("a"), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn memory_cache_invalidates_prefix() {
+        let cache = MemoryCache::new();
+        cache.put("embeddings/a", "1");
+        cache.put("embeddings/b", "2");
+        cache.put("chat/completions/a", "3");
+
+        assert_eq!(cache.invalidate_prefix("embeddings/"), 2);
+        assert_eq!(cache.get("embeddings/a"), None);
+        assert_eq!(cache.get("chat/completions/a").as_deref(), Some("3"));
+        assert_eq!(cache.invalidate_prefix("embeddings/"), 0);
+    }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("oai-rs-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn disk_cache_clear_keeps_other_files() {
+        let dir = scratch_dir("clear");
+        let cache = DiskCache::new(&dir).unwrap();
+        fs::write(dir.join("notes.txt"), "keep me").unwrap();
+        fs::create_dir_all(dir.join("embeddings")).unwrap();
+        fs::write(dir.join("embeddings/README"), "keep me too").unwrap();
+        cache.put(KEY, "{}");
+        cache.put("chat/completions/0000000000000000000000000000000000000000000000000000000000000000", "{}");
+        assert_eq!(cache.get(KEY).as_deref(), Some("{}"));
+
+        cache.clear().unwrap();
+
+        assert_eq!(cache.get(KEY), None);
+        assert!(dir.join("notes.txt").exists());
+        assert!(dir.join("embeddings/README").exists());
+        assert!(!dir.join("chat").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn disk_cache_invalidates_only_entries() {
+        let dir = scratch_dir("invalidate");
+        let cache = DiskCache::new(&dir).unwrap();
+        fs::create_dir_all(dir.join("embeddings")).unwrap();
+        fs::write(dir.join("embeddings/README"), "keep me").unwrap();
+        cache.put(KEY, "{}");
+
+        assert_eq!(cache.invalidate_prefix("embeddings/"), 1);
+        assert!(dir.join("embeddings/README").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,2170 @@
+use crate::choices::{self, Choice, PostProcess};
+use crate::client::{Client, Timing};
+use crate::error::Error;
+use crate::files;
+use crate::models::ChatModels;
+use crate::moderations;
+use crate::requester::{self, Query, RequestOptions};
+use crate::schema::{ResponseFormat, Schema};
+use crate::stream::{self, EventStream, Flush, Resume, ResumableStream};
+#[cfg(feature = "tokenizer")]
+use crate::tokenizer::Tokenizer;
+use crate::tools::{FunctionCall, FunctionDefinition, ToolCall, ToolChoice, ToolDefinition};
+use crate::usage::{OutputBudget, Usage};
+use base64::Engine;
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::time::Instant;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::Sender;
+
+/// The largest image accepted in a message, 20 MB
+const MAX_IMAGE_SIZE: u64 = 20 * 1024 * 1024;
+
+/// The largest audio file accepted in a message, 25 MB
+const MAX_AUDIO_SIZE: u64 = 25 * 1024 * 1024;
+
+/// The author of a chat message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    /// Instructions taking the place of system messages for newer models, also used in the
+    /// input of stored responses
+    Developer,
+    User,
+    Assistant,
+    Tool,
+    /// The result of a legacy `function_call`, superseded by [`Role::Tool`]
+    Function,
+}
+
+/// How much detail the model should look at an image in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageDetail {
+    Auto,
+    Low,
+    High,
+}
+
+/// Encoding of input audio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    Wav,
+    Mp3,
+}
+
+/// A kind of output a model can generate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Modality {
+    Text,
+    Audio,
+}
+
+/// Encoding of audio generated by the model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputAudioFormat {
+    Wav,
+    Mp3,
+    Flac,
+    Opus,
+    Pcm16,
+}
+
+/// Audio generated by the model as an answer
+///
+/// Only the `id` is sent when the message is passed back in a later request, which is how
+/// the model is given its earlier audio answers.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MessageAudio {
+    pub id: String,
+    /// The base64 encoded audio, in the format requested with [`audio`](Parameters::audio)
+    #[serde(default)]
+    pub data: String,
+    /// What was said in the audio
+    #[serde(default)]
+    pub transcript: String,
+    /// Unix timestamp after which the audio can no longer be referred to in later requests
+    #[serde(default)]
+    pub expires_at: u64,
+}
+
+impl MessageAudio {
+    /// The audio, decoded from `data`
+    pub fn bytes(&self) -> Result<Vec<u8>, Error> {
+        base64::engine::general_purpose::STANDARD.decode(&self.data).map_err(|e| Error::Deserialize {
+            reason: format!("invalid base64 audio data: {}", e),
+            body: String::new(),
+        })
+    }
+}
+
+impl Serialize for MessageAudio {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        json!({ "id": self.id }).serialize(serializer)
+    }
+}
+
+/// The processing tier a request is served with, trading cost against latency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceTier {
+    /// The scale tier if the project has scale tier credits left, the default tier otherwise
+    Auto,
+    /// The default tier
+    Default,
+    /// Cheaper but slower processing, and requests may fail when capacity runs out
+    Flex,
+}
+
+/// A part of a multimodal message
+///
+/// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-messages)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "WirePart", from = "WirePart")]
+pub enum ContentPart {
+    Text(String),
+    /// An image by URL, or as a base64 encoded data URL
+    ImageUrl {
+        url: String,
+        detail: Option<ImageDetail>,
+    },
+    /// Base64 encoded audio
+    InputAudio {
+        data: String,
+        format: AudioFormat,
+    },
+}
+
+impl ContentPart {
+    /// An image by URL, leaving the level of detail to the model
+    pub fn image_url<S: Into<String>>(url: S) -> Self {
+        ContentPart::ImageUrl {
+            url: url.into(),
+            detail: None,
+        }
+    }
+
+    /// A PNG, JPEG, GIF or WebP image read from `path`, sent as a base64 encoded data URL
+    ///
+    /// Fails without reading the file if it isn't one of those formats or is larger than 20 MB.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::chat::{ChatMessage, ContentPart};
+    ///
+    /// async {
+    ///     let image = ContentPart::image_from_path("./cookie.png").await.expect("Error Reading Image");
+    ///     let message = ChatMessage::user(vec![ContentPart::from("What is in this image?"), image]);
+    /// };
+    /// ```
+    pub async fn image_from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let mime = match files::mime_type(path) {
+            Some(mime) if mime.starts_with("image/") => mime,
+            _ => return Err(Error::InvalidParameter(format!("{} is not a PNG, JPEG, GIF or WebP image", path.display()))),
+        };
+        let data = files::read_limited(path, MAX_IMAGE_SIZE).await?;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&data);
+        Ok(ContentPart::image_url(format!("data:{};base64,{}", mime, encoded)))
+    }
+
+    /// A WAV or MP3 file read from `path`, sent base64 encoded
+    ///
+    /// Fails without reading the file if it isn't one of those formats or is larger than 25 MB.
+    pub async fn audio_from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let format = match files::mime_type(path) {
+            Some("audio/wav") => AudioFormat::Wav,
+            Some("audio/mpeg") => AudioFormat::Mp3,
+            _ => return Err(Error::InvalidParameter(format!("{} is not a WAV or MP3 file", path.display()))),
+        };
+        let data = files::read_limited(path, MAX_AUDIO_SIZE).await?;
+        Ok(ContentPart::InputAudio {
+            data: base64::engine::general_purpose::STANDARD.encode(&data),
+            format,
+        })
+    }
+}
+
+impl From<&str> for ContentPart {
+    fn from(text: &str) -> Self {
+        ContentPart::Text(text.to_string())
+    }
+}
+
+impl From<String> for ContentPart {
+    fn from(text: String) -> Self {
+        ContentPart::Text(text)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireImageUrl {
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<ImageDetail>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireInputAudio {
+    data: String,
+    format: AudioFormat,
+}
+
+/// How a [`ContentPart`] is laid out on the wire
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WirePart {
+    Text { text: String },
+    ImageUrl { image_url: WireImageUrl },
+    InputAudio { input_audio: WireInputAudio },
+}
+
+impl From<ContentPart> for WirePart {
+    fn from(part: ContentPart) -> Self {
+        match part {
+            ContentPart::Text(text) => WirePart::Text { text },
+            ContentPart::ImageUrl { url, detail } => WirePart::ImageUrl { image_url: WireImageUrl { url, detail } },
+            ContentPart::InputAudio { data, format } => WirePart::InputAudio { input_audio: WireInputAudio { data, format } },
+        }
+    }
+}
+
+impl From<WirePart> for ContentPart {
+    fn from(part: WirePart) -> Self {
+        match part {
+            WirePart::Text { text } => ContentPart::Text(text),
+            WirePart::ImageUrl { image_url } => ContentPart::ImageUrl { url: image_url.url, detail: image_url.detail },
+            WirePart::InputAudio { input_audio } => ContentPart::InputAudio { data: input_audio.data, format: input_audio.format },
+        }
+    }
+}
+
+/// The content of a message, either plain text or a list of parts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+impl MessageContent {
+    /// The text of the content, with the text of each part joined together
+    pub fn text(&self) -> String {
+        match self {
+            MessageContent::Text(text) => text.clone(),
+            MessageContent::Parts(parts) => parts.iter()
+                .filter_map(|part| match part {
+                    ContentPart::Text(text) => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&str> for MessageContent {
+    fn from(text: &str) -> Self {
+        MessageContent::Text(text.to_string())
+    }
+}
+
+impl From<String> for MessageContent {
+    fn from(text: String) -> Self {
+        MessageContent::Text(text)
+    }
+}
+
+impl From<Vec<ContentPart>> for MessageContent {
+    fn from(parts: Vec<ContentPart>) -> Self {
+        MessageContent::Parts(parts)
+    }
+}
+
+/// A single message in a chat conversation
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::chat::{ChatMessage, ContentPart};
+///
+/// let messages = vec![
+///     ChatMessage::system("You are a helpful assistant."),
+///     ChatMessage::user("Ice cream or cookies?").name("alice"),
+///     ChatMessage::user(vec![
+///         ContentPart::from("What is in this image?"),
+///         ContentPart::image_url("https://example.com/dessert.png"),
+///     ]),
+/// ];
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub content: Option<MessageContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    /// Calls the assistant made to the tools it was given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    /// The function the assistant called, when it was given legacy `functions` instead of tools
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function_call: Option<FunctionCall>,
+    /// The audio the assistant answered with, when audio output was requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<MessageAudio>,
+}
+
+impl ChatMessage {
+    fn new(role: Role, content: Option<MessageContent>) -> Self {
+        ChatMessage {
+            role,
+            content,
+            name: None,
+            tool_call_id: None,
+            tool_calls: None,
+            function_call: None,
+            audio: None,
+        }
+    }
+
+    /// A message setting the behaviour of the assistant
+    pub fn system<C: Into<MessageContent>>(content: C) -> Self {
+        ChatMessage::new(Role::System, Some(content.into()))
+    }
+
+    /// A message from the end user
+    pub fn user<C: Into<MessageContent>>(content: C) -> Self {
+        ChatMessage::new(Role::User, Some(content.into()))
+    }
+
+    /// A previous response from the assistant
+    pub fn assistant<C: Into<MessageContent>>(content: C) -> Self {
+        ChatMessage::new(Role::Assistant, Some(content.into()))
+    }
+
+    /// The result of the tool call with the given ID
+    pub fn tool<S: Into<String>, C: Into<MessageContent>>(tool_call_id: S, content: C) -> Self {
+        let mut message = ChatMessage::new(Role::Tool, Some(content.into()));
+        message.tool_call_id = Some(tool_call_id.into());
+        message
+    }
+
+    /// The result of a legacy `function_call` to the function `name`
+    pub fn function<S: Into<String>, C: Into<MessageContent>>(name: S, content: C) -> Self {
+        ChatMessage::new(Role::Function, Some(content.into())).name(name)
+    }
+
+    /// The calls the assistant made, whether it made them as tool calls or as a legacy
+    /// `function_call`
+    ///
+    /// A legacy function call is given the ID `call_{name}`, as it was sent without one.
+    pub fn calls(&self) -> Vec<ToolCall> {
+        match (&self.tool_calls, &self.function_call) {
+            (Some(calls), _) => calls.clone(),
+            (None, Some(call)) => vec![ToolCall::from(call.clone())],
+            (None, None) => Vec::new(),
+        }
+    }
+
+    /// This message in the current `tools` shape, turning a legacy `function_call` into a
+    /// tool call and a `function` result into a `tool` result
+    ///
+    /// Use it to continue a conversation held with `functions` on a model only accepting tools.
+    pub fn into_tools_shape(mut self) -> Self {
+        if let Some(call) = self.function_call.take() {
+            self.tool_calls.get_or_insert_with(Vec::new).push(ToolCall::from(call));
+        }
+        if self.role == Role::Function {
+            self.role = Role::Tool;
+            self.tool_call_id = self.name.take().map(|name| format!("call_{}", name));
+        }
+        self
+    }
+
+    /// An optional name for the participant, to tell apart participants with the same role
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// The text of the message, the transcript of its audio if it only has audio, empty if
+    /// it has neither
+    pub fn text(&self) -> String {
+        match (&self.content, &self.audio) {
+            (Some(content), _) => content.text(),
+            (None, Some(audio)) => audio.transcript.clone(),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopLogprob {
+    pub token: String,
+    pub logprob: f64,
+    pub bytes: Option<Vec<u8>>,
+}
+
+/// Log probability of a generated token, along with the most likely alternatives
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenLogprob {
+    pub token: String,
+    pub logprob: f64,
+    pub bytes: Option<Vec<u8>>,
+    #[serde(default)]
+    pub top_logprobs: Vec<TopLogprob>,
+}
+
+/// Log probabilities of a choice, returned when `logprobs` is enabled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatLogprobs {
+    pub content: Option<Vec<TokenLogprob>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatChoice {
+    pub index: i32,
+    pub message: ChatMessage,
+    pub finish_reason: Option<String>,
+    #[serde(default)]
+    pub logprobs: Option<ChatLogprobs>,
+}
+
+impl Choice for ChatChoice {
+    fn finish_reason(&self) -> Option<&str> {
+        self.finish_reason.as_deref()
+    }
+
+    fn token_logprobs(&self) -> Vec<(&str, f64)> {
+        self.logprobs.iter()
+            .flat_map(|l| l.content.iter().flatten())
+            .map(|t| (t.token.as_str(), t.logprob))
+            .collect()
+    }
+}
+
+/// Key-value pairs attached to a stored chat completion, to filter by in the dashboard and
+/// when listing stored completions
+///
+/// Holds at most [`MAX_PAIRS`](Metadata::MAX_PAIRS) pairs, with keys of at most
+/// [`MAX_KEY_LEN`](Metadata::MAX_KEY_LEN) and values of at most
+/// [`MAX_VALUE_LEN`](Metadata::MAX_VALUE_LEN) characters.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::chat::Metadata;
+///
+/// let mut metadata = Metadata::new();
+/// metadata.insert("user_id", "42").unwrap();
+/// metadata.insert("feature", "support-bot").unwrap();
+/// assert!(metadata.insert("x".repeat(65), "too long").is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Metadata(BTreeMap<String, String>);
+
+impl Metadata {
+    /// The most pairs metadata can hold
+    pub const MAX_PAIRS: usize = 16;
+    /// The longest a key can be, in characters
+    pub const MAX_KEY_LEN: usize = 64;
+    /// The longest a value can be, in characters
+    pub const MAX_VALUE_LEN: usize = 512;
+
+    /// Empty metadata
+    pub fn new() -> Self {
+        Metadata::default()
+    }
+
+    /// Set `key` to `value`, returning the value it had before
+    ///
+    /// Fails without changing anything if the key or value is too long, or if the key is new
+    /// and the metadata already holds [`MAX_PAIRS`](Metadata::MAX_PAIRS) pairs.
+    pub fn insert<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) -> Result<Option<String>, Error> {
+        let (key, value) = (key.into(), value.into());
+        if key.chars().count() > Metadata::MAX_KEY_LEN {
+            return Err(Error::InvalidParameter(format!("metadata keys must be at most {} characters, {} is too long", Metadata::MAX_KEY_LEN, key)));
+        }
+        if value.chars().count() > Metadata::MAX_VALUE_LEN {
+            return Err(Error::InvalidParameter(format!("metadata values must be at most {} characters, the value of {} is too long", Metadata::MAX_VALUE_LEN, key)));
+        }
+        if !self.0.contains_key(&key) && self.0.len() >= Metadata::MAX_PAIRS {
+            return Err(Error::InvalidParameter(format!("metadata can hold at most {} pairs", Metadata::MAX_PAIRS)));
+        }
+        Ok(self.0.insert(key, value))
+    }
+
+    /// The value of `key`
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    /// Remove `key`, returning its value
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.0.remove(key)
+    }
+
+    /// The pairs, ordered by key
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// The number of pairs
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether there are no pairs
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatCompletion {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub object: String,
+    #[serde(default, alias = "created_at")]
+    pub created: u64,
+    /// Older deployments and some gateways send this as `engine`
+    #[serde(alias = "engine")]
+    pub model: String,
+    #[serde(default)]
+    pub system_fingerprint: Option<String>,
+    /// The tier the request was served with, e.g. `scale` or `default`
+    #[serde(default)]
+    pub service_tier: Option<String>,
+    pub choices: Vec<ChatChoice>,
+    pub usage: Option<Usage>,
+    /// The metadata the completion was stored with, only set on stored completions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Metadata>,
+    /// How long the request took, not part of the API's response
+    #[serde(skip)]
+    pub timing: Option<Timing>,
+}
+
+impl ChatCompletion {
+    /// The usage reported by the API, zero if it wasn't reported
+    pub fn usage_or_zero(&self) -> Usage {
+        self.usage.clone().unwrap_or_default()
+    }
+
+    /// The first choice that finished for `reason`, e.g. `stop` to skip truncated choices
+    pub fn choice_by_finish_reason(&self, reason: &str) -> Option<&ChatChoice> {
+        choices::by_finish_reason(&self.choices, reason)
+    }
+
+    /// The choice with the highest average logprob, requires [`logprobs`](Parameters::logprobs)
+    /// to be enabled on the request
+    pub fn choice_by_logprob(&self) -> Option<&ChatChoice> {
+        choices::by_average_logprob(&self.choices)
+    }
+
+    /// The choice with the highest score given by `score`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{chat::{self, ChatMessage}, models};
+    ///
+    /// async {
+    ///     let messages = vec![ChatMessage::user("Name a dessert.")];
+    ///     let completion = chat::build(models::ChatModels::GPT_4O_MINI)
+    ///         .messages(&messages)
+    ///         .n(&3)
+    ///         .complete()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     let shortest = completion.choice_by(|c| -(c.message.text().len() as f64));
+    ///     println!("{:?}", shortest);
+    /// };
+    /// ```
+    pub fn choice_by<F>(&self, score: F) -> Option<&ChatChoice>
+    where
+        F: FnMut(&ChatChoice) -> f64
+    {
+        choices::by_score(&self.choices, score)
+    }
+}
+
+/// The part of a function call streamed in a chunk
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FunctionCallDelta {
+    /// Only sent on the first chunk of the call
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The next piece of the JSON encoded arguments
+    #[serde(default)]
+    pub arguments: Option<String>,
+}
+
+/// The part of a tool call streamed in a chunk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallDelta {
+    /// Position of the call among the message's tool calls, the pieces of a call share it
+    pub index: usize,
+    /// Only sent on the first chunk of the call
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(rename = "type", default)]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub function: Option<FunctionCallDelta>,
+}
+
+/// The part of a message streamed in a chunk, every field is only set when it changed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatDelta {
+    /// Only sent on the first chunk of a choice
+    #[serde(default)]
+    pub role: Option<Role>,
+    /// The next piece of the text answer
+    #[serde(default)]
+    pub content: Option<String>,
+    /// The next piece of the refusal, when the model refused to answer
+    #[serde(default)]
+    pub refusal: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Option<Vec<ToolCallDelta>>,
+    /// The next piece of a legacy `function_call`
+    #[serde(default)]
+    pub function_call: Option<FunctionCallDelta>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatChunkChoice {
+    pub index: i32,
+    #[serde(default)]
+    pub delta: ChatDelta,
+    pub finish_reason: Option<String>,
+    #[serde(default)]
+    pub logprobs: Option<ChatLogprobs>,
+}
+
+/// A partial chat completion received while streaming
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCompletionChunk {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub object: String,
+    #[serde(default, alias = "created_at")]
+    pub created: u64,
+    /// Older deployments and some gateways send this as `engine`
+    #[serde(alias = "engine")]
+    pub model: String,
+    #[serde(default)]
+    pub system_fingerprint: Option<String>,
+    #[serde(default)]
+    pub service_tier: Option<String>,
+    pub choices: Vec<ChatChunkChoice>,
+    /// Only sent on the last chunk, when requested with [`include_usage`](Parameters::include_usage)
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+/// Stream of partial chat completions returned by [`Parameters::stream`]
+pub type ChatStream = EventStream<ChatCompletionChunk>;
+
+impl Resume for ChatCompletionChunk {
+    const RESUMED: &'static str = "messages";
+
+    fn push_text(&self, text: &mut String) {
+        for content in self.choices.iter().filter_map(|c| c.delta.content.as_deref()) {
+            text.push_str(content);
+        }
+    }
+
+    fn resumed(original: &Value, received: &str) -> Value {
+        let mut messages = original.as_array().cloned().unwrap_or_default();
+        messages.push(json!(ChatMessage::assistant(received)));
+        messages.push(json!(ChatMessage::user(CONTINUE_PROMPT)));
+        json!(messages)
+    }
+
+    fn stream<'f>(options: &'f RequestOptions, query: &'f Query<'_>) -> impl Future<Output = Result<ChatStream, Error>> + Send + 'f {
+        requester::chat_stream(options, query)
+    }
+}
+
+/// Assembles the chunks of a [`ChatStream`] into a [`ChatCompletion`]
+///
+/// The text, tool calls and logprobs of each choice are appended as their pieces arrive,
+/// tool calls at the position their chunks give. Refusals are only sent in the chunks. The
+/// answer so far can be parsed as JSON with [`partial`](ChatAccumulator::partial), to render
+/// a structured output or JSON mode answer before the whole of it has arrived.
+///
+/// # Examples
+///
+/// ```rust
+/// use futures_util::StreamExt;
+/// use oai_rs::chat::{self, ChatAccumulator, ChatMessage};
+/// use oai_rs::{models, schema::ResponseFormat};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Recipe {
+///     title: Option<String>,
+///     #[serde(default)]
+///     steps: Vec<String>,
+/// }
+///
+/// async {
+///     let messages = vec![ChatMessage::user("Give me a cookie recipe as JSON with a title and steps.")];
+///     let mut stream = chat::build(models::ChatModels::GPT_4O_MINI)
+///         .messages(&messages)
+///         .response_format(ResponseFormat::JsonObject)
+///         .stream()
+///         .await
+///         .expect("Error Getting Response");
+///
+///     let mut answer = ChatAccumulator::new();
+///     while let Some(chunk) = stream.next().await {
+///         answer.push(chunk.expect("Error Getting Chunk"));
+///         if let Some(recipe) = answer.partial::<Recipe>() {
+///             println!("{:?}", recipe);
+///         }
+///     }
+///     let completion = answer.finish().expect("No Chunks Received");
+///     println!("{}", completion.choices[0].message.text());
+/// };
+/// ```
+#[derive(Debug, Default)]
+pub struct ChatAccumulator {
+    completion: Option<ChatCompletion>,
+}
+
+impl ChatAccumulator {
+    /// An accumulator that has received no chunks yet
+    pub fn new() -> Self {
+        ChatAccumulator::default()
+    }
+
+    /// Append the next chunk of the stream
+    pub fn push(&mut self, chunk: ChatCompletionChunk) {
+        let completion = self.completion.get_or_insert_with(|| ChatCompletion {
+            id: chunk.id.clone(),
+            object: String::from("chat.completion"),
+            created: chunk.created,
+            model: chunk.model.clone(),
+            system_fingerprint: chunk.system_fingerprint.clone(),
+            service_tier: chunk.service_tier.clone(),
+            choices: Vec::new(),
+            usage: None,
+            metadata: None,
+            timing: None,
+        });
+        if chunk.usage.is_some() {
+            completion.usage = chunk.usage;
+        }
+        for part in chunk.choices {
+            let choice = match completion.choices.iter().position(|c| c.index == part.index) {
+                Some(i) => &mut completion.choices[i],
+                None => {
+                    completion.choices.push(ChatChoice {
+                        index: part.index,
+                        message: ChatMessage::new(Role::Assistant, None),
+                        finish_reason: None,
+                        logprobs: None,
+                    });
+                    completion.choices.last_mut().unwrap()
+                }
+            };
+            merge_delta(&mut choice.message, part.delta);
+            if let Some(logprobs) = part.logprobs {
+                let content = choice.logprobs.get_or_insert(ChatLogprobs { content: None }).content.get_or_insert_with(Vec::new);
+                content.extend(logprobs.content.into_iter().flatten());
+            }
+            if part.finish_reason.is_some() {
+                choice.finish_reason = part.finish_reason;
+            }
+        }
+    }
+
+    /// The text of the first choice so far
+    pub fn text(&self) -> String {
+        self.completion.as_ref()
+            .and_then(|c| c.choices.iter().find(|choice| choice.index == 0))
+            .map(|choice| choice.message.text())
+            .unwrap_or_default()
+    }
+
+    /// The text of the first choice so far completed into JSON, see [`PartialJson`](stream::PartialJson)
+    pub fn partial_value(&self) -> Option<Value> {
+        stream::partial_value(&self.text())
+    }
+
+    /// The text of the first choice so far completed into JSON and deserialized as `T`,
+    /// `None` if it can't be yet
+    ///
+    /// Fields that haven't arrived are missing, so `T` should make them optional or default.
+    pub fn partial<T: DeserializeOwned>(&self) -> Option<T> {
+        serde_json::from_value(self.partial_value()?).ok()
+    }
+
+    /// The completion assembled so far, `None` before the first chunk
+    pub fn completion(&self) -> Option<&ChatCompletion> {
+        self.completion.as_ref()
+    }
+
+    /// The assembled completion, `None` if no chunk was received
+    pub fn finish(self) -> Option<ChatCompletion> {
+        self.completion
+    }
+}
+
+/// Append the pieces of a streamed delta onto the message assembled so far
+fn merge_delta(message: &mut ChatMessage, delta: ChatDelta) {
+    if let Some(role) = delta.role {
+        message.role = role;
+    }
+    if let Some(content) = delta.content {
+        match &mut message.content {
+            Some(MessageContent::Text(text)) => text.push_str(&content),
+            _ => message.content = Some(MessageContent::Text(content)),
+        }
+    }
+    for part in delta.tool_calls.into_iter().flatten() {
+        let calls = message.tool_calls.get_or_insert_with(Vec::new);
+        // Pieces of later calls may arrive first, so calls are kept at the index they were given
+        while calls.len() <= part.index {
+            calls.push(ToolCall {
+                id: String::new(),
+                kind: String::from("function"),
+                function: FunctionCall { name: String::new(), arguments: String::new() },
+            });
+        }
+        let call = &mut calls[part.index];
+        if let Some(id) = part.id {
+            call.id = id;
+        }
+        if let Some(kind) = part.kind {
+            call.kind = kind;
+        }
+        if let Some(function) = part.function {
+            merge_function(&mut call.function, function);
+        }
+    }
+    if let Some(function) = delta.function_call {
+        let call = message.function_call.get_or_insert_with(|| FunctionCall { name: String::new(), arguments: String::new() });
+        merge_function(call, function);
+    }
+}
+
+fn merge_function(call: &mut FunctionCall, delta: FunctionCallDelta) {
+    if let Some(name) = delta.name {
+        call.name.push_str(&name);
+    }
+    if let Some(arguments) = delta.arguments {
+        call.arguments.push_str(&arguments);
+    }
+}
+
+/// Available parameters that can be sent with a chat completion request
+pub struct Parameters<'a> {
+    options: RequestOptions,
+    model: ChatModels,
+    max_rounds: u32,
+    output_budget: Option<u32>,
+    resume_attempts: u32,
+    post_process: Option<PostProcess>,
+    query: Query<'a>,
+}
+
+/// The message asking the model to go on after an answer was cut off
+const CONTINUE_PROMPT: &str = "Continue exactly where you left off, without repeating anything.";
+
+/// Function to create a chat completion request
+///
+/// Call it using [`build`] and add valid [`Parameters`] to the request to build a
+/// chat completions request and close with `complete()`.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{chat::{self, ChatMessage}, models};
+///
+/// async {
+///     let messages = vec![
+///         ChatMessage::system("You are a helpful assistant."),
+///         ChatMessage::user("Ice cream or cookies?"),
+///     ];
+///     let completion = chat::build(models::ChatModels::GPT_4O_MINI)
+///         .messages(&messages)
+///         .max_tokens(32)
+///         .complete()
+///         .await
+///         .expect("Error Getting Response");
+///
+///     println!("{:?}", completion);
+/// };
+/// ```
+pub fn build<'a, M: Into<ChatModels>>(model: M) -> Parameters<'a> {
+    Parameters {
+        options: RequestOptions::default(),
+        model: model.into(),
+        max_rounds: 0,
+        output_budget: None,
+        resume_attempts: 0,
+        post_process: None,
+        query: Query::new(),
+    }
+}
+
+impl<'a> Parameters<'a> {
+    /// Send the request using the given client instead of one loaded from the environment
+    pub fn client(mut self, client: &Client) -> Self {
+        self.options.client = Some(client.clone());
+        self
+    }
+
+    /// Mark the request as safe to retry even when the client's retry policy
+    /// is restricted to idempotent requests, see [`RetryScope::IdempotentOnly`](crate::retry::RetryScope::IdempotentOnly)
+    pub fn idempotent(mut self) -> Self {
+        self.options.idempotent = true;
+        self
+    }
+
+    /// Set a parameter this crate doesn't model yet, sent as is in the request body
+    ///
+    /// Replaces the value set for `key` by earlier setters, and is replaced by later ones.
+    /// `model` and `stream` are set when the request is sent, as are `messages` and the output
+    /// limit in the rounds of an [`output_budget`](Parameters::output_budget), so extra values
+    /// for them are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{chat::{self, ChatMessage}, models};
+    /// use serde_json::json;
+    ///
+    /// async {
+    ///     let messages = vec![ChatMessage::user("Ice cream or cookies?")];
+    ///     let completion = chat::build(models::ChatModels::GPT_4O_MINI)
+    ///         .messages(&messages)
+    ///         .extra_param("reasoning_effort", json!("low"))
+    ///         .complete()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    /// };
+    /// ```
+    pub fn extra_param(mut self, key: &'a str, value: Value) -> Self {
+        self.query.push((key, value));
+        self
+    }
+
+    /// The messages of the conversation so far.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-messages)
+    pub fn messages(mut self, input: &'a [ChatMessage]) -> Self {
+        self.query.push(("messages", json!(input)));
+        self
+    }
+
+    /// What sampling temperature to use, between 0 and 2. Higher values
+    /// like 0.8 will make the output more random, while lower values
+    /// like 0.2 will make it more focused and deterministic.
+    ///
+    /// We generally recommend altering this or top_p but not both.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-temperature)
+    pub fn temperature(mut self, input: &'a f32) -> Self {
+        self.query.push(("temperature", json!(input)));
+        self
+    }
+
+    /// An alternative to sampling with `temperature`, called
+    /// nucleus sampling, where the model considers the results
+    /// of the tokens with `top_p` probability mass. So 0.1
+    /// means only the tokens comprising the top 10% probability
+    /// mass are considered.
+    ///
+    /// We generally recommend altering this or `temperature` but not both.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-top_p)
+    pub fn top_p(mut self, input: &'a f32) -> Self {
+        self.query.push(("top_p", json!(input)));
+        self
+    }
+
+    /// How many chat completion choices to generate for each input message.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-n)
+    pub fn n(mut self, input: &'a u32) -> Self {
+        self.query.push(("n", json!(input)));
+        self
+    }
+
+    /// Modify the likelihood of specified tokens appearing in the completion, maps
+    /// token IDs to a bias value from -100 to 100.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-logit_bias)
+    pub fn logit_bias(mut self, input: &'a HashMap<u32, i32>) -> Self {
+        let bias = input.iter().map(|(token, bias)| (token.to_string(), json!(bias))).collect();
+        self.query.merge("logit_bias", bias);
+        self
+    }
+
+    /// Bias whole words instead of token IDs, each word is tokenized for the request's
+    /// model and its bias applied to every one of its tokens, see [`Tokenizer::logit_bias`].
+    ///
+    /// Fails with [`Error::UnknownTokenizer`] if no tokenizer is known for the model.
+    #[cfg(feature = "tokenizer")]
+    pub fn logit_bias_words(mut self, words: &[(&str, i32)]) -> Result<Self, Error> {
+        let tokenizer = Tokenizer::for_model(self.model.as_str())?;
+        let bias = tokenizer.logit_bias(words).into_iter().map(|(token, bias)| (token.to_string(), json!(bias))).collect();
+        self.query.merge("logit_bias", bias);
+        Ok(self)
+    }
+
+    /// Whether to return the log probabilities of the generated tokens.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-logprobs)
+    pub fn logprobs(mut self, input: &'a bool) -> Self {
+        self.query.push(("logprobs", json!(input)));
+        self
+    }
+
+    /// How many of the most likely tokens to return at each position, between 0 and 20.
+    /// Requires [`logprobs`](Parameters::logprobs) to be enabled.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-top_logprobs)
+    pub fn top_logprobs(mut self, input: &'a u8) -> Self {
+        self.query.push(("top_logprobs", json!(input)));
+        self
+    }
+
+    /// One sequence where the API will stop generating further tokens.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-stop)
+    pub fn stop(mut self, input: &'a str) -> Self {
+        self.query.push(("stop", json!(input)));
+        self
+    }
+
+    /// Up to 4 sequences where the API will stop generating further tokens.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-stop)
+    pub fn stops(mut self, input: &'a Vec<&str>) -> Self {
+        self.query.push(("stop", json!(input)));
+        self
+    }
+
+    /// The maximum number of tokens to generate in the chat completion.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-max_tokens)
+    pub fn max_tokens(mut self, input: u16) -> Self {
+        self.query.push(("max_tokens", json!(input)));
+        self
+    }
+
+    /// The maximum number of tokens to generate including reasoning tokens, required by
+    /// reasoning models which reject `max_tokens`
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-max_completion_tokens)
+    pub fn max_completion_tokens(mut self, input: u32) -> Self {
+        self.query.push(("max_completion_tokens", json!(input)));
+        self
+    }
+
+    /// Number between -2.0 and 2.0. Positive values penalize new tokens
+    /// based on whether they appear in the text so far,
+    /// increasing the model's likelihood to talk about new topics.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-presence_penalty)
+    pub fn presence_penalty(mut self, input: &'a f32) -> Self {
+        self.query.push(("presence_penalty", json!(input)));
+        self
+    }
+
+    /// Number between -2.0 and 2.0. Positive values penalize new tokens based
+    /// on their existing frequency in the text so far, decreasing the model's
+    /// likelihood to repeat the same line verbatim.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-frequency_penalty)
+    pub fn frequency_penalty(mut self, input: &'a f32) -> Self {
+        self.query.push(("frequency_penalty", json!(input)));
+        self
+    }
+
+    /// The format the model answers in, see [`ResponseFormat`]
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-response_format)
+    pub fn response_format(mut self, input: ResponseFormat) -> Self {
+        self.query.push(("response_format", json!(input)));
+        self
+    }
+
+    /// Have the model answer with JSON matching `schema`, using structured outputs.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-response_format)
+    pub fn json_schema(self, name: &'a str, schema: &'a Schema) -> Self {
+        self.response_format(ResponseFormat::json_schema(name, schema.clone()))
+    }
+
+    /// Tools the model may call, see [`Tool::definition`](crate::tools::Tool::definition).
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-tools)
+    pub fn tools(mut self, input: &'a [ToolDefinition]) -> Self {
+        self.query.push(("tools", json!(input)));
+        self
+    }
+
+    /// Whether and which of the [`tools`](Parameters::tools) the model calls, e.g.
+    /// [`ToolChoice::tool`] to force a call to a specific tool
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-tool_choice)
+    pub fn tool_choice(mut self, input: ToolChoice) -> Self {
+        self.query.push(("tool_choice", json!(input)));
+        self
+    }
+
+    /// Whether the model may call several tools in one answer, on by default. Turn it off
+    /// when the tools have to be called one after another, e.g. when a call depends on the
+    /// result of the one before it.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-parallel_tool_calls)
+    pub fn parallel_tool_calls(mut self, input: bool) -> Self {
+        self.query.push(("parallel_tool_calls", json!(input)));
+        self
+    }
+
+    /// Functions the model may call, the legacy shape of [`tools`](Parameters::tools) still
+    /// expected by some deployments and proxies. Answers call them with
+    /// [`function_call`](ChatMessage::function_call) instead of tool calls.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-functions)
+    pub fn functions(mut self, input: &'a [FunctionDefinition]) -> Self {
+        self.query.push(("functions", json!(input)));
+        self
+    }
+
+    /// Whether and which of the [`functions`](Parameters::functions) the model calls,
+    /// `none`, `auto`, or the name of the function it has to call
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-function_call)
+    pub fn function_call(mut self, input: &'a str) -> Self {
+        let value = match input {
+            "none" | "auto" => json!(input),
+            name => json!({ "name": name }),
+        };
+        self.query.push(("function_call", value));
+        self
+    }
+
+    /// The kinds of output to generate, `[Modality::Text, Modality::Audio]` to have an audio
+    /// model answer with speech as well as text, set along with [`audio`](Parameters::audio)
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-modalities)
+    pub fn modalities(mut self, input: &'a [Modality]) -> Self {
+        self.query.push(("modalities", json!(input)));
+        self
+    }
+
+    /// The voice and format of audio answers, e.g. `alloy` and [`OutputAudioFormat::Wav`].
+    /// The audio is returned in the answer's [`audio`](ChatMessage::audio).
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-audio)
+    pub fn audio(mut self, voice: &'a str, format: OutputAudioFormat) -> Self {
+        self.query.push(("audio", json!({ "voice": voice, "format": format })));
+        self
+    }
+
+    /// Content most of the answer is expected to match, e.g. the current version of a file the
+    /// model is asked to make a small change to. Matching parts of the answer are generated
+    /// much faster, how much of the prediction was used is reported by
+    /// [`Usage::accepted_prediction_tokens`] and [`Usage::rejected_prediction_tokens`].
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-prediction)
+    pub fn prediction<C: Into<MessageContent>>(mut self, content: C) -> Self {
+        self.query.push(("prediction", json!({ "type": "content", "content": content.into() })));
+        self
+    }
+
+    /// Store the completion for 30 days, to look at in the dashboard and use in evals and
+    /// distillation
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-store)
+    pub fn store(mut self, input: bool) -> Self {
+        self.query.push(("store", json!(input)));
+        self
+    }
+
+    /// Metadata to attach to a [stored](Parameters::store) completion
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-metadata)
+    pub fn metadata(mut self, input: &'a Metadata) -> Self {
+        self.query.push(("metadata", json!(input)));
+        self
+    }
+
+    /// The tier to serve the request with, the tier used is returned in
+    /// [`service_tier`](ChatCompletion::service_tier)
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-service_tier)
+    pub fn service_tier(mut self, input: ServiceTier) -> Self {
+        self.query.push(("service_tier", json!(input)));
+        self
+    }
+
+    /// Sample deterministically, as far as possible, so repeated requests with the same seed
+    /// and parameters return the same result, see [`ChatCompletion::system_fingerprint`]
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-seed)
+    pub fn seed(mut self, input: i64) -> Self {
+        self.query.push(("seed", json!(input)));
+        self
+    }
+
+    /// A unique identifier representing your end-user, which can help
+    /// OpenAI to monitor and detect abuse.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-user)
+    pub fn user(mut self, input: &'a str) -> Self {
+        self.query.push(("user", json!(input)));
+        self
+    }
+
+    /// Send the usage for the whole request on an extra last chunk that has no choices,
+    /// only valid when streaming.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/create#chat-create-stream_options)
+    pub fn include_usage(mut self) -> Self {
+        self.query.push(("stream_options", json!({ "include_usage": true })));
+        self
+    }
+
+    /// Continue the answer up to `max_rounds` times when it's cut off by `max_tokens`
+    ///
+    /// While the first choice finishes with `length`, the answer so far is sent back as an
+    /// assistant message followed by a user message asking to continue, and the continuation
+    /// is appended to the choice. The returned completion holds the combined answer and the
+    /// usage of every round.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{chat::{self, ChatMessage}, models};
+    ///
+    /// async {
+    ///     let messages = vec![ChatMessage::user("Write a long story about cookies.")];
+    ///     let completion = chat::build(models::ChatModels::GPT_4O_MINI)
+    ///         .messages(&messages)
+    ///         .max_tokens(1024)
+    ///         .auto_continue(3)
+    ///         .complete()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     println!("{}", completion.choices[0].message.text());
+    /// };
+    /// ```
+    pub fn auto_continue(mut self, max_rounds: u32) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    /// Generate up to `total` tokens, continuing the answer over as many rounds as it takes
+    ///
+    /// Each round asks for the tokens left in the budget, at most `max_completion_tokens` or
+    /// `max_tokens` if set, so the total can exceed what the model generates in a single
+    /// response. The limit of each round is sent as `max_completion_tokens` if that was set,
+    /// which reasoning models require, and as `max_tokens` otherwise. Rounds stop once
+    /// the budget is spent or the answer finishes, and at most after the rounds set with
+    /// [`auto_continue`](Parameters::auto_continue) if set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{chat::{self, ChatMessage}, models};
+    ///
+    /// async {
+    ///     let messages = vec![ChatMessage::user("Write a 10,000 word story about cookies.")];
+    ///     let completion = chat::build(models::ChatModels::GPT_4O_MINI)
+    ///         .messages(&messages)
+    ///         .max_tokens(4096)
+    ///         .output_budget(10_000)
+    ///         .complete()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     println!("{}", completion.choices[0].message.text());
+    /// };
+    /// ```
+    pub fn output_budget(mut self, total: u32) -> Self {
+        self.output_budget = Some(total);
+        self
+    }
+
+    /// Resume the answer up to `max_attempts` times when the stream drops part way through,
+    /// e.g. on a flaky network. Off unless set.
+    ///
+    /// When the connection drops mid-stream ([`Error::Interrupted`]), times out or the stream
+    /// idles out, the request is sent again with the answer streamed so far as an assistant
+    /// message followed by a user message asking to continue, and streaming carries on from
+    /// there. With the `tokenizer` feature the output limit is lowered by the tokens already
+    /// received; without it the limit is sent unchanged, so the whole answer can run longer.
+    /// Each resumed request is billed for the longer conversation, and streamed usage only
+    /// covers the last request. Only applies to [`complete_with`](Parameters::complete_with),
+    /// [`stream_to`](Parameters::stream_to) and [`write_to`](Parameters::write_to) with a
+    /// single choice.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{chat::{self, ChatMessage}, models, stream::Flush};
+    ///
+    /// async {
+    ///     let messages = vec![ChatMessage::user("Write a long story about cookies.")];
+    ///     let completion = chat::build(models::ChatModels::GPT_4O_MINI)
+    ///         .messages(&messages)
+    ///         .max_tokens(2048)
+    ///         .resume_on_disconnect(3)
+    ///         .write_to(&mut tokio::io::sink(), Flush::OnFinish)
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     println!("{}", completion.choices[0].message.text());
+    /// };
+    /// ```
+    pub fn resume_on_disconnect(mut self, max_attempts: u32) -> Self {
+        self.resume_attempts = max_attempts;
+        self
+    }
+
+    /// Clean up the text answer of every choice once the completion has arrived, e.g. strip
+    /// a trailing stop sequence or normalize line endings, see [`PostProcess`]
+    ///
+    /// Applies to the completions returned by `complete()`, `complete_with()`, `stream_to()`
+    /// and `write_to()`, not to the partial completions streamed on the way.
+    pub fn post_process(mut self, post_process: PostProcess) -> Self {
+        self.post_process = Some(post_process);
+        self
+    }
+
+    /// Applies the [`post_process`](Parameters::post_process) to the text answers of a
+    /// completion for the stop sequences of the request as it is now
+    fn finisher(&self) -> impl Fn(&mut ChatCompletion) {
+        let post_process = self.post_process;
+        let stops: Vec<String> = match self.query.get("stop") {
+            Some(Value::String(stop)) => vec![stop.clone()],
+            Some(Value::Array(stops)) => stops.iter().filter_map(Value::as_str).map(String::from).collect(),
+            _ => Vec::new(),
+        };
+        move |completion| {
+            if let Some(post_process) = &post_process {
+                for choice in completion.choices.iter_mut() {
+                    if let Some(MessageContent::Text(text)) = &mut choice.message.content {
+                        *text = post_process.apply(text, None, &stops);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Complete the request and send
+    pub async fn complete(mut self) -> Result<ChatCompletion, Error> {
+        let started = Instant::now();
+        let finish = self.finisher();
+        moderations::preflight(&self.options, user_content(&self.query).as_ref()).await?;
+        self.query.push(("model", json!(self.model.as_str())));
+
+        let max_rounds = match self.output_budget {
+            Some(_) if self.max_rounds == 0 => u32::MAX,
+            _ => self.max_rounds,
+        };
+        // Reasoning models only accept `max_completion_tokens`, so cap rounds with whichever key was set
+        let limit_key = match self.query.get("max_completion_tokens") {
+            Some(_) => "max_completion_tokens",
+            None => "max_tokens",
+        };
+        let per_round = self.query.get(limit_key).and_then(Value::as_u64);
+        let mut budget = self.output_budget.map(|total| OutputBudget::new(total, per_round));
+        if let Some(budget) = budget.as_mut() {
+            self.query.push((limit_key, json!(budget.next_round())));
+        }
+
+        let response: Result<ChatCompletion, Error> = requester::chat(&self.options, &self.query).await;
+        let mut completion = response?;
+        if let Some(budget) = budget.as_mut() {
+            budget.spend(completion.usage.as_ref());
+        }
+
+        let messages = match self.query.get("messages") {
+            Some(Value::Array(messages)) => messages.clone(),
+            _ => {
+                finish(&mut completion);
+                completion.timing = Some(Timing::since(started));
+                return Ok(completion);
+            }
+        };
+        let mut rounds = 0;
+        while rounds < max_rounds
+            && completion.choices.first().is_some_and(|c| c.finish_reason.as_deref() == Some("length"))
+            && budget.as_ref().is_none_or(OutputBudget::has_remaining)
+        {
+            if let Some(budget) = budget.as_mut() {
+                self.query.push((limit_key, json!(budget.next_round())));
+            }
+            let mut continued = messages.clone();
+            continued.push(json!(ChatMessage::assistant(completion.choices[0].message.text())));
+            continued.push(json!(ChatMessage::user(CONTINUE_PROMPT)));
+            self.query.push(("messages", json!(continued)));
+
+            let next: ChatCompletion = requester::chat(&self.options, &self.query).await?;
+            if let Some(budget) = budget.as_mut() {
+                budget.spend(next.usage.as_ref());
+            }
+            merge_continuation(&mut completion, next);
+            rounds += 1;
+        }
+        finish(&mut completion);
+        completion.timing = Some(Timing::since(started));
+        Ok(completion)
+    }
+
+    /// Complete the request and stream back partial completions as they are generated
+    ///
+    /// [`auto_continue`](Parameters::auto_continue) and [`output_budget`](Parameters::output_budget)
+    /// only apply to [`complete`](Parameters::complete), and [`post_process`](Parameters::post_process)
+    /// only to the completions assembled by it and [`complete_with`](Parameters::complete_with),
+    /// [`stream_to`](Parameters::stream_to) and [`write_to`](Parameters::write_to).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_util::StreamExt;
+    /// use oai_rs::{chat::{self, ChatMessage}, models};
+    ///
+    /// async {
+    ///     let messages = vec![ChatMessage::user("Ice cream or cookies?")];
+    ///     let mut stream = chat::build(models::ChatModels::GPT_4O_MINI)
+    ///         .messages(&messages)
+    ///         .stream()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     while let Some(chunk) = stream.next().await {
+    ///         let chunk = chunk.expect("Error Getting Chunk");
+    ///         if let Some(text) = chunk.choices.first().and_then(|c| c.delta.content.as_deref()) {
+    ///             print!("{}", text);
+    ///         }
+    ///     }
+    /// };
+    /// ```
+    pub async fn stream(mut self) -> Result<ChatStream, Error> {
+        moderations::preflight(&self.options, user_content(&self.query).as_ref()).await?;
+        self.query.push(("model", json!(self.model.as_str())));
+        self.query.push(("stream", json!(true)));
+
+        requester::chat_stream(&self.options, &self.query).await
+    }
+
+    /// Start streaming, resuming the stream if set with [`resume_on_disconnect`](Parameters::resume_on_disconnect)
+    async fn resumable_stream(self) -> Result<ResumableStream<'a, ChatCompletionChunk>, Error> {
+        let started = Instant::now();
+        let single_choice = self.query.get("n").and_then(Value::as_u64).unwrap_or(1) == 1;
+        let resume = match self.query.get("messages") {
+            Some(messages @ Value::Array(_)) if single_choice && self.resume_attempts > 0 => Some((messages.clone(), self.resume_attempts)),
+            _ => None,
+        };
+        let limit_key = match self.query.get("max_completion_tokens") {
+            Some(_) => "max_completion_tokens",
+            None => "max_tokens",
+        };
+        let limit = self.query.get(limit_key).and_then(Value::as_u64).map(|limit| (limit_key, limit));
+        let model = self.model.as_str().to_string();
+        let options = self.options.clone();
+        let mut query = self.query.clone();
+        query.push(("model", json!(self.model.as_str())));
+        query.push(("stream", json!(true)));
+        let inner = self.stream().await?;
+
+        Ok(ResumableStream::new(inner, options, query, resume, limit, &model, started))
+    }
+
+    /// Complete the request, calling `on_chunk` with each partial completion as it is
+    /// streamed back, and return the completion assembled with a [`ChatAccumulator`] once
+    /// the stream ends
+    ///
+    /// The completion's `usage` is only set if the request asked for it with
+    /// [`include_usage`](Parameters::include_usage).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{chat::{self, ChatMessage}, models};
+    ///
+    /// async {
+    ///     let messages = vec![ChatMessage::user("Ice cream or cookies?")];
+    ///     let completion = chat::build(models::ChatModels::GPT_4O_MINI)
+    ///         .messages(&messages)
+    ///         .complete_with(|chunk| {
+    ///             if let Some(text) = chunk.choices.first().and_then(|c| c.delta.content.as_deref()) {
+    ///                 print!("{}", text);
+    ///             }
+    ///         })
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     println!("{:?}", completion);
+    /// };
+    /// ```
+    pub async fn complete_with<F>(self, mut on_chunk: F) -> Result<ChatCompletion, Error>
+    where
+        F: FnMut(&ChatCompletionChunk)
+    {
+        let finish = self.finisher();
+        let mut stream = self.resumable_stream().await?;
+        let mut answer = ChatAccumulator::new();
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            on_chunk(&chunk);
+
+            answer.push(chunk);
+        }
+
+        let mut completion = answer.finish().ok_or(Error::EmptyStream)?;
+        finish(&mut completion);
+        completion.timing = Some(stream.timing());
+        Ok(completion)
+    }
+
+    /// Complete the request, forwarding each partial completion into `sender` as it is
+    /// streamed back, and return the assembled completion once the stream ends
+    ///
+    /// Sending waits for room in the channel, so a slow receiver slows down reading the
+    /// stream. If the receiver is dropped the stream is cancelled and the completion
+    /// assembled so far is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{chat::{self, ChatCompletionChunk, ChatMessage}, models};
+    ///
+    /// async {
+    ///     let (sender, mut receiver) = tokio::sync::mpsc::channel(16);
+    ///     let printer = tokio::spawn(async move {
+    ///         while let Some(chunk) = receiver.recv().await {
+    ///             let chunk: ChatCompletionChunk = chunk;
+    ///             if let Some(text) = chunk.choices.first().and_then(|c| c.delta.content.as_deref()) {
+    ///                 print!("{}", text);
+    ///             }
+    ///         }
+    ///     });
+    ///
+    ///     let messages = vec![ChatMessage::user("Ice cream or cookies?")];
+    ///     let completion = chat::build(models::ChatModels::GPT_4O_MINI)
+    ///         .messages(&messages)
+    ///         .stream_to(sender)
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     printer.await.unwrap();
+    ///     println!("{:?}", completion);
+    /// };
+    /// ```
+    pub async fn stream_to(self, sender: Sender<ChatCompletionChunk>) -> Result<ChatCompletion, Error> {
+        let finish = self.finisher();
+        let mut stream = self.resumable_stream().await?;
+        let mut answer = ChatAccumulator::new();
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            if sender.send(chunk.clone()).await.is_err() {
+                stream.cancel();
+                break;
+            }
+
+            answer.push(chunk);
+        }
+
+        let mut completion = answer.finish().ok_or(Error::EmptyStream)?;
+        finish(&mut completion);
+        completion.timing = Some(stream.timing());
+        Ok(completion)
+    }
+
+    /// Complete the request, writing the text answer of each partial completion to `writer`
+    /// as it is streamed back, and return the assembled completion once the stream ends
+    ///
+    /// The text of every choice is written as it arrives, so this is intended for requests
+    /// generating a single choice. Refusals and tool calls aren't written, only assembled
+    /// into the completion. The writer is always flushed once the stream ends.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{chat::{self, ChatMessage}, models, stream::Flush};
+    ///
+    /// async {
+    ///     let mut output: Vec<u8> = Vec::new();
+    ///     let messages = vec![ChatMessage::user("Ice cream or cookies?")];
+    ///     let completion = chat::build(models::ChatModels::GPT_4O_MINI)
+    ///         .messages(&messages)
+    ///         .write_to(&mut output, Flush::OnFinish)
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     println!("{:?}", completion);
+    /// };
+    /// ```
+    pub async fn write_to<W>(self, writer: &mut W, flush: Flush) -> Result<ChatCompletion, Error>
+    where
+        W: AsyncWrite + Unpin
+    {
+        let finish = self.finisher();
+        let mut stream = self.resumable_stream().await?;
+        let mut answer = ChatAccumulator::new();
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            for text in chunk.choices.iter().filter_map(|c| c.delta.content.as_deref()) {
+                writer.write_all(text.as_bytes()).await.map_err(Error::Io)?;
+            }
+            if flush == Flush::EveryChunk {
+                writer.flush().await.map_err(Error::Io)?;
+            }
+            answer.push(chunk);
+        }
+        writer.flush().await.map_err(Error::Io)?;
+
+        let mut completion = answer.finish().ok_or(Error::EmptyStream)?;
+        finish(&mut completion);
+        completion.timing = Some(stream.timing());
+        Ok(completion)
+    }
+
+    /// Complete the request and parse the answer of the first choice as JSON into `T`
+    ///
+    /// Uses JSON mode unless a schema was set with [`json_schema`](Parameters::json_schema),
+    /// JSON mode requires the messages to ask for JSON. Fails with
+    /// [`Error::UnparsableOutput`] holding the answer if it isn't valid for `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{chat::{self, ChatMessage}, models};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Dessert {
+    ///     name: String,
+    ///     calories: u32,
+    /// }
+    ///
+    /// async {
+    ///     let messages = vec![ChatMessage::user("Suggest a dessert as JSON with a name and calories.")];
+    ///     let dessert: Dessert = chat::build(models::ChatModels::GPT_4O_MINI)
+    ///         .messages(&messages)
+    ///         .complete_as()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     println!("{} ({} kcal)", dessert.name, dessert.calories);
+    /// };
+    /// ```
+    pub async fn complete_as<T: DeserializeOwned>(mut self) -> Result<T, Error> {
+        if self.query.get("response_format").is_none() {
+            self.query.push(("response_format", json!(ResponseFormat::JsonObject)));
+        }
+        let completion = self.complete().await?;
+        let text = completion.choices.first().map(|c| c.message.text()).unwrap_or_default();
+        serde_json::from_str(&text).map_err(|e| Error::UnparsableOutput {
+            reason: e.to_string(),
+            text,
+        })
+    }
+}
+
+/// A page of a list of stored completions or their messages, or of a stored response's
+/// [input items](crate::responses::input_items)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub data: Vec<T>,
+    #[serde(default)]
+    pub first_id: Option<String>,
+    /// The ID to pass to `after` to get the next page
+    #[serde(default)]
+    pub last_id: Option<String>,
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+/// A message of a stored completion's conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredMessage {
+    pub id: String,
+    #[serde(flatten)]
+    pub message: ChatMessage,
+}
+
+/// The confirmation that a stored completion was deleted
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeletedCompletion {
+    pub id: String,
+    #[serde(default)]
+    pub object: String,
+    pub deleted: bool,
+}
+
+/// Get a completion made with [`store`](Parameters::store)
+///
+/// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/get)
+pub async fn stored(completion_id: &str) -> Result<ChatCompletion, Error> {
+    stored_with(None, completion_id).await
+}
+
+pub(crate) async fn stored_with(client: Option<&Client>, completion_id: &str) -> Result<ChatCompletion, Error> {
+    let response: Result<ChatCompletion, Error> = requester::chat_completion(&options(client), Method::GET, completion_id).await;
+
+    match response {
+        Ok(t) => Ok(t),
+        Err(e) => Err(e),
+    }
+}
+
+/// Delete a completion made with [`store`](Parameters::store)
+///
+/// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/delete)
+pub async fn delete_stored(completion_id: &str) -> Result<DeletedCompletion, Error> {
+    delete_stored_with(None, completion_id).await
+}
+
+pub(crate) async fn delete_stored_with(client: Option<&Client>, completion_id: &str) -> Result<DeletedCompletion, Error> {
+    let response: Result<DeletedCompletion, Error> = requester::chat_completion(&options(client), Method::DELETE, completion_id).await;
+
+    match response {
+        Ok(t) => Ok(t),
+        Err(e) => Err(e),
+    }
+}
+
+fn options(client: Option<&Client>) -> RequestOptions {
+    RequestOptions {
+        client: client.cloned(),
+        ..RequestOptions::default()
+    }
+}
+
+/// List the completions made with [`store`](Parameters::store), newest first unless ordered
+/// otherwise
+///
+/// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/list)
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::chat::{self, Metadata};
+///
+/// async {
+///     let mut metadata = Metadata::new();
+///     metadata.insert("feature", "support-bot").unwrap();
+///
+///     let page = chat::list_stored()
+///         .metadata(&metadata)
+///         .limit(50)
+///         .list()
+///         .await
+///         .expect("Error Listing Completions");
+///
+///     for completion in page.data {
+///         let messages = chat::stored_messages(&completion.id).list().await;
+///         println!("{:?}", messages);
+///     }
+/// };
+/// ```
+pub fn list_stored() -> ListParameters<ChatCompletion> {
+    ListParameters {
+        options: RequestOptions::default(),
+        completion_id: None,
+        query: Vec::new(),
+        item: PhantomData,
+    }
+}
+
+/// List the messages of the conversation a stored completion answered
+///
+/// [OpenAI Reference](https://platform.openai.com/docs/api-reference/chat/getMessages)
+pub fn stored_messages(completion_id: &str) -> ListParameters<StoredMessage> {
+    ListParameters {
+        options: RequestOptions::default(),
+        completion_id: Some(completion_id.to_string()),
+        query: Vec::new(),
+        item: PhantomData,
+    }
+}
+
+/// Parameters of a list of stored completions, or of a stored completion's messages
+pub struct ListParameters<T> {
+    options: RequestOptions,
+    completion_id: Option<String>,
+    query: Vec<(String, String)>,
+    item: PhantomData<T>,
+}
+
+impl<T: DeserializeOwned> ListParameters<T> {
+    /// Send the request with `client` instead of the client loaded from the environment
+    pub fn client(mut self, client: &Client) -> Self {
+        self.options.client = Some(client.clone());
+        self
+    }
+
+    /// Only list completions made by `model`, ignored when listing messages
+    pub fn model(mut self, model: &str) -> Self {
+        self.query.push((String::from("model"), model.to_string()));
+        self
+    }
+
+    /// Only list completions stored with all of these pairs of metadata, ignored when listing
+    /// messages
+    pub fn metadata(mut self, metadata: &Metadata) -> Self {
+        for (key, value) in metadata.iter() {
+            self.query.push((format!("metadata[{}]", key), value.to_string()));
+        }
+        self
+    }
+
+    /// Start the page after the ID, the [`last_id`](Page::last_id) of the previous page
+    pub fn after(mut self, id: &str) -> Self {
+        self.query.push((String::from("after"), id.to_string()));
+        self
+    }
+
+    /// The most entries to return, between 1 and 100
+    pub fn limit(mut self, limit: u8) -> Self {
+        self.query.push((String::from("limit"), limit.to_string()));
+        self
+    }
+
+    /// `asc` or `desc`, by when the completions were made or by message order
+    pub fn order(mut self, order: &str) -> Self {
+        self.query.push((String::from("order"), order.to_string()));
+        self
+    }
+
+    /// Get the page of stored completions or messages
+    pub async fn list(self) -> Result<Page<T>, Error> {
+        let response: Result<Page<T>, Error> = match &self.completion_id {
+            Some(id) => requester::chat_completion_messages(&self.options, id, &self.query).await,
+            None => requester::chat_completions_list(&self.options, &self.query).await,
+        };
+
+        match response {
+            Ok(t) => Ok(t),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Append the first choice of a continuation round onto the first choice of the completion
+fn merge_continuation(completion: &mut ChatCompletion, next: ChatCompletion) {
+    if let Some(usage) = &next.usage {
+        completion.usage.get_or_insert_with(Usage::default).add(usage);
+    }
+    let (Some(choice), Some(part)) = (completion.choices.first_mut(), next.choices.into_iter().next()) else {
+        return;
+    };
+    let text = choice.message.text() + &part.message.text();
+    choice.message.content = Some(MessageContent::Text(text));
+    if let (Some(logprobs), Some(more)) = (choice.logprobs.as_mut(), part.logprobs) {
+        logprobs.content.get_or_insert_with(Vec::new).extend(more.content.into_iter().flatten());
+    }
+    choice.finish_reason = part.finish_reason;
+}
+
+/// The content of every user message, used as the input for moderation
+fn user_content(query: &Query<'_>) -> Option<Value> {
+    let messages = query.get("messages")?.as_array()?;
+    let content: Vec<Value> = messages.iter()
+        .filter(|m| m["role"] == "user")
+        .filter_map(|m| serde_json::from_value::<MessageContent>(m["content"].clone()).ok())
+        .map(|c| json!(c.text()))
+        .filter(|c| c != "")
+        .collect();
+    if content.is_empty() {
+        return None;
+    }
+    Some(json!(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build, ChatAccumulator, ChatCompletionChunk, ChatMessage, Metadata, Modality, OutputAudioFormat, ServiceTier};
+    use crate::choices::PostProcess;
+    use crate::models::ChatModels;
+    use crate::schema::ResponseFormat;
+    use crate::stream::Flush;
+    use crate::tools::ToolChoice;
+    use crate::testing::{assert_snapshot, golden, CannedResponse, Matcher, TestClient};
+    use futures_util::StreamExt;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn parameters_use_wire_keys() {
+        let test = TestClient::new();
+        let messages = [ChatMessage::user("Hello")];
+        let bias = HashMap::from([(50256, -100)]);
+        let mut metadata = Metadata::new();
+        metadata.insert("team", "search").unwrap();
+        let modalities = [Modality::Text, Modality::Audio];
+        let _ = build(ChatModels::GPT_4O)
+            .client(&test.client())
+            .messages(&messages)
+            .temperature(&0.5)
+            .top_p(&0.25)
+            .n(&2)
+            .logit_bias(&bias)
+            .logprobs(&true)
+            .top_logprobs(&3)
+            .stop("\n")
+            .max_completion_tokens(32)
+            .presence_penalty(&0.5)
+            .frequency_penalty(&-0.5)
+            .response_format(ResponseFormat::JsonObject)
+            .tool_choice(ToolChoice::Required)
+            .parallel_tool_calls(false)
+            .modalities(&modalities)
+            .audio("alloy", OutputAudioFormat::Wav)
+            .prediction("fn main() {}")
+            .store(true)
+            .metadata(&metadata)
+            .service_tier(ServiceTier::Flex)
+            .seed(7)
+            .user("user-1")
+            .extra_param("verbosity", json!("low"))
+            .complete()
+            .await;
+
+        assert_snapshot(golden("chat"), &test.last_request());
+    }
+
+    #[tokio::test]
+    async fn max_tokens_uses_its_own_key() {
+        let test = TestClient::new();
+        let messages = [ChatMessage::user("Hello")];
+        let _ = build(ChatModels::GPT_4O)
+            .client(&test.client())
+            .messages(&messages)
+            .max_tokens(16)
+            .complete()
+            .await;
+
+        assert_snapshot(golden("chat_max_tokens"), &test.last_request());
+    }
+
+    #[tokio::test]
+    async fn client_defaults_fill_unset_parameters() {
+        let test = TestClient::new();
+        let client = test.client()
+            .default_param("temperature", json!(0.2))
+            .default_param("store", json!(true));
+        let messages = [ChatMessage::user("Hello")];
+        let _ = build(ChatModels::GPT_4O)
+            .client(&client)
+            .messages(&messages)
+            .temperature(&0.5)
+            .complete()
+            .await;
+
+        let body = test.last_request().unwrap().body.unwrap();
+        assert_eq!(body["temperature"], json!(0.5));
+        assert_eq!(body["store"], json!(true));
+    }
+
+    fn chunk(choices: serde_json::Value) -> ChatCompletionChunk {
+        serde_json::from_value(json!({ "id": "chatcmpl-1", "model": "gpt-4o", "choices": choices })).unwrap()
+    }
+
+    #[test]
+    fn accumulator_parses_partial_json() {
+        #[derive(serde::Deserialize)]
+        struct Recipe {
+            title: Option<String>,
+            #[serde(default)]
+            steps: Vec<String>,
+        }
+
+        let mut answer = ChatAccumulator::new();
+        assert!(answer.partial_value().is_none());
+        let pieces = [r#"{"ti"#, r#"tle": "Cook"#, r#"ies", "steps": ["Pre"#, r#"heat", "Mix"]}"#];
+        let mut titles = Vec::new();
+        for piece in pieces {
+            answer.push(chunk(json!([{ "index": 0, "delta": { "content": piece }, "finish_reason": null }])));
+            let recipe: Recipe = answer.partial().unwrap();
+            titles.push((recipe.title, recipe.steps.len()));
+        }
+
+        assert_eq!(titles, [(None, 0), (Some(String::from("Cook")), 0), (Some(String::from("Cookies")), 1), (Some(String::from("Cookies")), 2)]);
+        assert_eq!(answer.partial_value(), Some(json!({ "title": "Cookies", "steps": ["Preheat", "Mix"] })));
+    }
+
+    #[test]
+    fn accumulator_assembles_choices_and_tool_calls() {
+        let mut answer = ChatAccumulator::new();
+        answer.push(chunk(json!([
+            { "index": 1, "delta": { "role": "assistant", "content": "Ice" }, "finish_reason": null },
+            { "index": 0, "delta": { "role": "assistant", "tool_calls": [
+                { "index": 1, "id": "call_b", "type": "function", "function": { "name": "get_price", "arguments": "{\"item\"" } },
+            ] }, "finish_reason": null },
+        ])));
+        answer.push(chunk(json!([
+            { "index": 0, "delta": { "tool_calls": [
+                { "index": 0, "id": "call_a", "type": "function", "function": { "name": "get_weather", "arguments": "" } },
+                { "index": 1, "function": { "arguments": ": \"cookie\"}" } },
+            ] }, "finish_reason": null },
+            { "index": 1, "delta": { "content": " cream" }, "finish_reason": "stop" },
+        ])));
+        answer.push(chunk(json!([
+            { "index": 0, "delta": { "tool_calls": [{ "index": 0, "function": { "arguments": "{}" } }] }, "finish_reason": "tool_calls" },
+        ])));
+
+        let completion = answer.finish().unwrap();
+        let calls = completion.choices[1].message.tool_calls.as_ref().unwrap();
+        assert_eq!(completion.choices[1].index, 0);
+        assert_eq!(completion.choices[1].finish_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(calls.iter().map(|c| (c.id.as_str(), c.function.name.as_str(), c.function.arguments.as_str())).collect::<Vec<_>>(), [
+            ("call_a", "get_weather", "{}"),
+            ("call_b", "get_price", "{\"item\": \"cookie\"}"),
+        ]);
+        assert_eq!(completion.choices[0].message.text(), "Ice cream");
+        assert_eq!(completion.choices[0].finish_reason.as_deref(), Some("stop"));
+    }
+
+    #[tokio::test]
+    async fn streams_chunks() {
+        let test = TestClient::new().on(Matcher::post("chat/completions"), CannedResponse::stream(&[
+            json!({ "model": "gpt-4o", "choices": [{ "index": 0, "delta": { "role": "assistant", "content": "" }, "finish_reason": null }] }),
+            json!({ "model": "gpt-4o", "choices": [{ "index": 0, "delta": { "content": "Cookies" }, "finish_reason": null }] }),
+            json!({ "model": "gpt-4o", "choices": [{ "index": 0, "delta": {}, "finish_reason": "stop" }] }),
+            json!({ "model": "gpt-4o", "choices": [], "usage": { "prompt_tokens": 5, "completion_tokens": 1, "total_tokens": 6 } }),
+        ]));
+        let messages = [ChatMessage::user("Ice cream or cookies?")];
+
+        let chunks: Vec<_> = build(ChatModels::GPT_4O)
+            .client(&test.client())
+            .messages(&messages)
+            .include_usage()
+            .stream()
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        let text: String = chunks.iter().flat_map(|c| &c.choices).filter_map(|c| c.delta.content.as_deref()).collect();
+        assert_eq!(text, "Cookies");
+        assert_eq!(chunks[2].choices[0].finish_reason.as_deref(), Some("stop"));
+        assert_eq!(chunks[3].usage.as_ref().map(|u| u.total_tokens), Some(6));
+        let body = test.last_request().unwrap().body.unwrap();
+        assert_eq!((&body["stream"], &body["stream_options"]), (&json!(true), &json!({ "include_usage": true })));
+    }
+
+    fn answer_chunks() -> CannedResponse {
+        CannedResponse::stream(&[
+            json!({ "model": "gpt-4o", "choices": [{ "index": 0, "delta": { "role": "assistant", "content": " Cook" }, "finish_reason": null }] }),
+            json!({ "model": "gpt-4o", "choices": [{ "index": 0, "delta": { "content": "ies" }, "finish_reason": "stop" }] }),
+            json!({ "model": "gpt-4o", "choices": [], "usage": { "prompt_tokens": 5, "completion_tokens": 2, "total_tokens": 7 } }),
+        ])
+    }
+
+    #[tokio::test]
+    async fn write_to_writes_text_and_assembles_completion() {
+        let test = TestClient::new().on(Matcher::post("chat/completions"), answer_chunks());
+        let messages = [ChatMessage::user("Ice cream or cookies?")];
+
+        let mut output = Vec::new();
+        let completion = build(ChatModels::GPT_4O)
+            .client(&test.client())
+            .messages(&messages)
+            .include_usage()
+            .post_process(PostProcess::new().trim_leading_whitespace(true))
+            .write_to(&mut output, Flush::EveryChunk)
+            .await
+            .unwrap();
+
+        assert_eq!(output, b" Cookies");
+        assert_eq!(completion.choices[0].message.text(), "Cookies");
+        assert_eq!(completion.choices[0].finish_reason.as_deref(), Some("stop"));
+        assert_eq!(completion.usage.map(|u| u.total_tokens), Some(7));
+        assert!(completion.timing.is_some());
+    }
+
+    #[tokio::test]
+    async fn stream_to_stops_once_the_receiver_is_dropped() {
+        let test = TestClient::new().on(Matcher::post("chat/completions"), answer_chunks());
+        let messages = [ChatMessage::user("Ice cream or cookies?")];
+
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(1);
+        let first = tokio::spawn(async move { receiver.recv().await });
+        let completion = build(ChatModels::GPT_4O)
+            .client(&test.client())
+            .messages(&messages)
+            .stream_to(sender)
+            .await
+            .unwrap();
+
+        let first: ChatCompletionChunk = first.await.unwrap().unwrap();
+        assert_eq!(first.choices[0].delta.content.as_deref(), Some(" Cook"));
+        assert_eq!(completion.choices[0].message.text(), " Cook");
+        assert_eq!(completion.choices[0].finish_reason, None);
+    }
+
+    #[tokio::test]
+    async fn dropped_stream_resumes_with_received_answer() {
+        let test = TestClient::new()
+            .on(
+                Matcher::post("chat/completions").body("/messages/1/content", json!(" Cook")),
+                CannedResponse::stream(&[json!({ "model": "gpt-4o", "choices": [{ "index": 0, "delta": { "role": "assistant", "content": "ies" }, "finish_reason": "stop" }] })]),
+            )
+            .on(
+                Matcher::post("chat/completions"),
+                CannedResponse::interrupted_stream(&[json!({ "model": "gpt-4o", "choices": [{ "index": 0, "delta": { "role": "assistant", "content": " Cook" }, "finish_reason": null }] })]),
+            );
+        let messages = [ChatMessage::user("Ice cream or cookies?")];
+
+        let completion = build(ChatModels::GPT_4O)
+            .client(&test.client())
+            .messages(&messages)
+            .max_completion_tokens(16)
+            .resume_on_disconnect(1)
+            .complete_with(|_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(completion.choices[0].message.text(), " Cookies");
+        assert_eq!(completion.choices[0].finish_reason.as_deref(), Some("stop"));
+        let requests = test.requests();
+        assert_eq!(requests.len(), 2);
+        let resumed = requests[1].body.as_ref().unwrap();
+        assert_eq!(resumed["messages"].as_array().map(Vec::len), Some(3));
+        assert_eq!(resumed["messages"][1]["role"], json!("assistant"));
+        assert_eq!(resumed["messages"][2]["content"], json!(super::CONTINUE_PROMPT));
+        #[cfg(feature = "tokenizer")]
+        let received = crate::tokenizer::Tokenizer::for_model("gpt-4o").unwrap().count(" Cook") as u64;
+        #[cfg(not(feature = "tokenizer"))]
+        let received = 0;
+        assert_eq!(resumed["max_completion_tokens"], json!(16 - received));
+    }
+}
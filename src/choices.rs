@@ -0,0 +1,308 @@
+use std::cmp::Ordering;
+
+/// A choice from a response, generated for each of the `n` samples requested
+pub trait Choice {
+    /// Why the model stopped generating the choice, e.g. `stop` or `length`
+    fn finish_reason(&self) -> Option<&str>;
+
+    /// The generated tokens with their log probabilities, empty if the request didn't ask
+    /// for logprobs
+    fn token_logprobs(&self) -> Vec<(&str, f64)>;
+
+    /// Average log probability of the generated tokens, if the request asked for logprobs
+    fn average_logprob(&self) -> Option<f64> {
+        average(self.token_logprobs().into_iter().map(|(_, logprob)| logprob))
+    }
+
+    /// Perplexity of the generated tokens, `1.0` when the model was certain of every token
+    /// and higher the less confident it was
+    fn perplexity(&self) -> Option<f64> {
+        self.average_logprob().map(|logprob| (-logprob).exp())
+    }
+
+    /// The generated tokens with their probabilities, from `0.0` to `1.0`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{chat::{self, ChatMessage}, choices::Choice, models};
+    ///
+    /// async {
+    ///     let messages = vec![ChatMessage::user("Is a tomato a fruit? Answer yes or no.")];
+    ///     let completion = chat::build(models::ChatModels::GPT_4O_MINI)
+    ///         .messages(&messages)
+    ///         .logprobs(&true)
+    ///         .complete()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     let choice = &completion.choices[0];
+    ///     if choice.perplexity().is_some_and(|p| p < 1.1) {
+    ///         println!("{:?}", choice.token_probabilities());
+    ///     }
+    /// };
+    /// ```
+    fn token_probabilities(&self) -> Vec<(&str, f64)> {
+        self.token_logprobs().into_iter().map(|(token, logprob)| (token, logprob.exp())).collect()
+    }
+}
+
+/// The first choice that finished for `reason`
+pub fn by_finish_reason<'c, C: Choice>(choices: &'c [C], reason: &str) -> Option<&'c C> {
+    choices.iter().find(|c| c.finish_reason() == Some(reason))
+}
+
+/// The choice the model was most confident in, ignoring choices without logprobs, the first
+/// on ties
+pub fn by_average_logprob<C: Choice>(choices: &[C]) -> Option<&C> {
+    // `max_by` keeps the last of equal elements, so walk the choices backwards
+    choices.iter().rev()
+        .filter_map(|c| c.average_logprob().map(|logprob| (c, logprob)))
+        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        .map(|(c, _)| c)
+}
+
+/// The choice with the highest score given by `score`, the first on ties
+pub fn by_score<C, F>(choices: &[C], mut score: F) -> Option<&C>
+where
+    F: FnMut(&C) -> f64
+{
+    let mut best: Option<(&C, f64)> = None;
+    for choice in choices {
+        let s = score(choice);
+        if best.is_none_or(|(_, b)| s > b) {
+            best = Some((choice, s));
+        }
+    }
+    best.map(|(c, _)| c)
+}
+
+/// Clean-up applied to the text of every choice once a response has arrived, set on a
+/// request with e.g. [`completions::Parameters::post_process`](crate::completions::Parameters::post_process)
+///
+/// Nothing is changed unless turned on. Steps run in order: strip the echoed prompt, strip a
+/// trailing stop sequence, normalize line endings, then trim leading whitespace.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::choices::PostProcess;
+///
+/// let post_process = PostProcess::new()
+///     .strip_echo(true)
+///     .strip_stop(true)
+///     .normalize_line_endings(true)
+///     .trim_leading_whitespace(true);
+///
+/// let text = post_process.apply("Q: Ice cream?\r\nA: Cookies.\r\nQ:", Some("Q: Ice cream?"), &["Q:"]);
+/// assert_eq!(text, "A: Cookies.\n");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PostProcess {
+    strip_echo: bool,
+    strip_stop: bool,
+    normalize_line_endings: bool,
+    trim_leading_whitespace: bool,
+}
+
+impl PostProcess {
+    /// A post-process that leaves the text as it is
+    pub fn new() -> Self {
+        PostProcess::default()
+    }
+
+    /// Remove the prompt from the start of the text, sent back when `echo` is set
+    pub fn strip_echo(mut self, strip_echo: bool) -> Self {
+        self.strip_echo = strip_echo;
+        self
+    }
+
+    /// Remove a stop sequence the text ends with, some gateways and self-hosted models
+    /// return it even though the API doesn't
+    pub fn strip_stop(mut self, strip_stop: bool) -> Self {
+        self.strip_stop = strip_stop;
+        self
+    }
+
+    /// Turn `\r\n` and lone `\r` line endings into `\n`
+    pub fn normalize_line_endings(mut self, normalize_line_endings: bool) -> Self {
+        self.normalize_line_endings = normalize_line_endings;
+        self
+    }
+
+    /// Remove whitespace from the start of the text, e.g. the space or newline completion
+    /// models tend to begin with
+    pub fn trim_leading_whitespace(mut self, trim_leading_whitespace: bool) -> Self {
+        self.trim_leading_whitespace = trim_leading_whitespace;
+        self
+    }
+
+    /// Apply the post-process to `text`, generated for `prompt` with the stop sequences `stops`
+    pub fn apply<S: AsRef<str>>(&self, text: &str, prompt: Option<&str>, stops: &[S]) -> String {
+        let mut text = text;
+        if self.strip_echo {
+            if let Some(rest) = prompt.and_then(|prompt| text.strip_prefix(prompt)) {
+                text = rest;
+            }
+        }
+        if self.strip_stop {
+            if let Some(rest) = stops.iter().find_map(|stop| text.strip_suffix(stop.as_ref())) {
+                text = rest;
+            }
+        }
+        let mut text = match self.normalize_line_endings {
+            true => text.replace("\r\n", "\n").replace('\r', "\n"),
+            false => text.to_string(),
+        };
+        if self.trim_leading_whitespace {
+            text = text.trim_start().to_string();
+        }
+        text
+    }
+}
+
+/// Average of the given log probabilities, `None` if there are none
+fn average<I: IntoIterator<Item = f64>>(logprobs: I) -> Option<f64> {
+    let (sum, count) = logprobs.into_iter().fold((0.0, 0usize), |(sum, count), l| (sum + l, count + 1));
+    if count == 0 {
+        return None;
+    }
+    Some(sum / count as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{by_average_logprob, by_finish_reason, by_score, Choice, PostProcess};
+
+    /// A choice with the given finish reason and token logprobs
+    struct Sample(Option<&'static str>, Vec<f64>);
+
+    impl Choice for Sample {
+        fn finish_reason(&self) -> Option<&str> {
+            self.0
+        }
+
+        fn token_logprobs(&self) -> Vec<(&str, f64)> {
+            self.1.iter().map(|logprob| ("token", *logprob)).collect()
+        }
+    }
+
+    fn position<C>(choices: &[C], choice: Option<&C>) -> Option<usize> {
+        choice.map(|c| choices.iter().position(|other| std::ptr::eq(other, c)).unwrap())
+    }
+
+    #[test]
+    fn choices_by_finish_reason() {
+        let choices = [
+            Sample(Some("length"), vec![]),
+            Sample(None, vec![]),
+            Sample(Some("stop"), vec![]),
+            Sample(Some("stop"), vec![]),
+        ];
+        let cases = [("stop", Some(2)), ("length", Some(0)), ("content_filter", None)];
+        for (reason, expected) in cases {
+            assert_eq!(position(&choices, by_finish_reason(&choices, reason)), expected, "{}", reason);
+        }
+        assert!(by_finish_reason::<Sample>(&[], "stop").is_none());
+    }
+
+    #[test]
+    fn choices_by_average_logprob() {
+        let cases: [(Vec<Sample>, Option<usize>); 5] = [
+            (vec![Sample(None, vec![-0.5, -1.5]), Sample(None, vec![-0.2, -0.4]), Sample(None, vec![-3.0])], Some(1)),
+            // Choices without logprobs are ignored rather than counted as certain
+            (vec![Sample(None, vec![]), Sample(None, vec![-0.9])], Some(1)),
+            (vec![Sample(None, vec![]), Sample(None, vec![])], None),
+            (vec![Sample(None, vec![-0.5]), Sample(None, vec![-0.25, -0.75])], Some(0)),
+            (vec![], None),
+        ];
+        for (i, (choices, expected)) in cases.iter().enumerate() {
+            assert_eq!(position(choices, by_average_logprob(choices)), *expected, "case {}", i);
+        }
+    }
+
+    #[test]
+    fn choices_by_score_keep_the_first_on_ties() {
+        let choices = [
+            Sample(Some("stop"), vec![-1.0]),
+            Sample(Some("stop"), vec![-1.0, -3.0]),
+            Sample(Some("length"), vec![-1.0, -3.0]),
+        ];
+        let longest = by_score(&choices, |c| c.token_logprobs().len() as f64);
+        assert_eq!(position(&choices, longest), Some(1));
+        assert_eq!(position(&choices, by_score(&choices, |_| 0.0)), Some(0));
+        assert_eq!(position(&choices, by_score(&choices, |_| f64::NAN)), Some(0));
+        assert!(by_score::<Sample, _>(&[], |_| 1.0).is_none());
+    }
+
+    #[test]
+    fn perplexity_of_token_logprobs() {
+        let cases: [(Vec<f64>, Option<f64>); 4] = [
+            (vec![], None),
+            (vec![0.0, 0.0], Some(1.0)),
+            (vec![-1.0, -3.0], Some(2f64.exp())),
+            (vec![f64::NEG_INFINITY], Some(f64::INFINITY)),
+        ];
+        for (logprobs, expected) in cases {
+            let choice = Sample(None, logprobs.clone());
+            match (choice.perplexity(), expected) {
+                (Some(perplexity), Some(expected)) => assert!(
+                    perplexity == expected || (perplexity - expected).abs() < 1e-9,
+                    "{:?}: {}",
+                    logprobs,
+                    perplexity
+                ),
+                (perplexity, expected) => assert_eq!(perplexity, expected, "{:?}", logprobs),
+            }
+        }
+        assert_eq!(Sample(None, vec![]).average_logprob(), None);
+        assert_eq!(Sample(None, vec![-1.0, -3.0]).average_logprob(), Some(-2.0));
+    }
+
+    #[test]
+    fn token_probabilities_of_token_logprobs() {
+        assert!(Sample(None, vec![]).token_probabilities().is_empty());
+        let choice = Sample(None, vec![0.0, 0.5f64.ln()]);
+        let probabilities = choice.token_probabilities();
+        assert_eq!(probabilities.len(), 2);
+        assert_eq!(probabilities[0], ("token", 1.0));
+        assert!((probabilities[1].1 - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn post_process_strips_echo_and_stop() {
+        let all = PostProcess::new().strip_echo(true).strip_stop(true);
+        let stops = ["\nQ:", "END"];
+        let cases = [
+            // Nothing is changed unless turned on
+            (PostProcess::new(), "Q: Hi\nA: Hello\nQ:", Some("Q: Hi"), "Q: Hi\nA: Hello\nQ:"),
+            (PostProcess::new().strip_echo(true), "Q: Hi\nA: Hello", Some("Q: Hi"), "\nA: Hello"),
+            (PostProcess::new().strip_stop(true), "A: Hello\nQ:", None, "A: Hello"),
+            (all, "Q: Hi\nA: Hello\nQ:", Some("Q: Hi"), "\nA: Hello"),
+            // The prompt is only stripped from the start and a stop only from the end
+            (all, "A: Q: Hi", Some("Q: Hi"), "A: Q: Hi"),
+            (all, "END of the line", None, "END of the line"),
+            // Only one stop sequence is stripped
+            (all, "Hello ENDEND", None, "Hello END"),
+            (all, "Q: Hi", None, "Q: Hi"),
+            (all, "Q: Hi", Some("Q: Hi"), ""),
+        ];
+        for (post_process, text, prompt, expected) in cases {
+            assert_eq!(post_process.apply(text, prompt, &stops), expected, "{:?} {:?}", text, prompt);
+        }
+    }
+
+    #[test]
+    fn post_process_steps_run_in_order() {
+        let all = PostProcess::new()
+            .strip_echo(true)
+            .strip_stop(true)
+            .normalize_line_endings(true)
+            .trim_leading_whitespace(true);
+        assert_eq!(all.apply("Say hi\r\n Hi\rthere\r\n", Some("Say hi"), &["\r\n"]), "Hi\nthere");
+        // The prompt is matched before line endings are normalized
+        assert_eq!(all.apply("Say\r\nhi Hi", Some("Say\nhi"), &[] as &[&str]), "Say\nhi Hi");
+        let trim = PostProcess::new().trim_leading_whitespace(true);
+        assert_eq!(trim.apply(" \n Hi \n", None, &[] as &[&str]), "Hi \n");
+    }
+}
@@ -0,0 +1,148 @@
+use crate::error::Error;
+use crate::tokenizer::Tokenizer;
+
+/// Split text into chunks of up to `max_tokens` tokens, each overlapping the previous chunk
+/// by `overlap` tokens, available with the `tokenizer` feature
+///
+/// Chunks are slices of the original text. Chunk boundaries are moved back to the nearest
+/// character boundary when a token ends part way through a character. `overlap` is capped
+/// at `max_tokens - 1` so that every chunk makes progress.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{chunking, tokenizer::Tokenizer};
+///
+/// let tokenizer = Tokenizer::for_model("text-embedding-ada-002").expect("Unknown model");
+/// let document = "A very long document. ".repeat(100);
+///
+/// let chunks = chunking::chunk_by_tokens(&document, &tokenizer, 64, 16);
+/// assert!(chunks.iter().all(|chunk| tokenizer.count(chunk) <= 64));
+/// ```
+pub fn chunk_by_tokens<'a>(text: &'a str, tokenizer: &Tokenizer, max_tokens: usize, overlap: usize) -> Vec<&'a str> {
+    let max_tokens = max_tokens.max(1);
+    let step = max_tokens - overlap.min(max_tokens - 1);
+
+    let tokens = tokenizer.encode(text);
+    let mut offsets = Vec::with_capacity(tokens.len() + 1);
+    let mut offset = 0;
+    offsets.push(offset);
+    for token in tokens.iter() {
+        offset += tokenizer.decode_bytes(std::slice::from_ref(token)).len();
+        offsets.push(offset.min(text.len()));
+    }
+
+    let mut chunks = Vec::new();
+    let mut start_token = 0;
+    while start_token < tokens.len() {
+        let end_token = (start_token + max_tokens).min(tokens.len());
+        let start = floor_char_boundary(text, offsets[start_token]);
+        let mut end = floor_char_boundary(text, offsets[end_token]);
+        if end <= start {
+            end = ceil_char_boundary(text, offsets[end_token]);
+        }
+        chunks.push(&text[start..end]);
+        if end_token == tokens.len() {
+            break;
+        }
+        start_token += step;
+    }
+    chunks
+}
+
+/// Split text into overlapping chunks using the tokenizer of the given model,
+/// see [`chunk_by_tokens`]
+pub fn chunk_for_model<'a>(text: &'a str, model: &str, max_tokens: usize, overlap: usize) -> Result<Vec<&'a str>, Error> {
+    let tokenizer = Tokenizer::for_model(model)?;
+    Ok(chunk_by_tokens(text, &tokenizer, max_tokens, overlap))
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::chunk_by_tokens;
+    use crate::tokenizer::Tokenizer;
+
+    fn tokenizer() -> Tokenizer {
+        Tokenizer::for_model("text-embedding-ada-002").unwrap()
+    }
+
+    /// Byte offset of `chunk` within `text`, which it is a slice of
+    fn offset(text: &str, chunk: &str) -> usize {
+        chunk.as_ptr() as usize - text.as_ptr() as usize
+    }
+
+    #[test]
+    fn chunks_cover_multi_byte_text() {
+        let tokenizer = tokenizer();
+        let cases = [
+            "Crème brûlée, smörgåsbord and jalapeño. ".repeat(20),
+            "東京は日本の首都です。大阪は食べ物で有名です。".repeat(10),
+            "🦀🚀👩‍👩‍👧‍👦🇯🇵 rust 🦀".repeat(15),
+        ];
+        for text in cases.iter() {
+            for max_tokens in [1, 2, 3, 7, 64] {
+                let chunks = chunk_by_tokens(text, &tokenizer, max_tokens, 0);
+                assert!(!chunks.is_empty());
+                assert_eq!(offset(text, chunks[0]), 0);
+                let last = chunks[chunks.len() - 1];
+                assert_eq!(offset(text, last) + last.len(), text.len());
+                // Each chunk starts at or before where the previous one ended, so nothing is lost
+                for pair in chunks.windows(2) {
+                    assert!(offset(text, pair[1]) <= offset(text, pair[0]) + pair[0].len(), "{:?}", pair);
+                    assert!(offset(text, pair[1]) >= offset(text, pair[0]), "{:?}", pair);
+                }
+                assert!(chunks.iter().all(|chunk| !chunk.is_empty()));
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_of_ascii_text_join_back_without_overlap() {
+        let tokenizer = tokenizer();
+        let text = "A very long document. ".repeat(50);
+        let chunks = chunk_by_tokens(&text, &tokenizer, 16, 0);
+        assert_eq!(chunks.concat(), text);
+        assert!(chunks.iter().all(|chunk| tokenizer.count(chunk) <= 16));
+    }
+
+    #[test]
+    fn overlap_is_capped_below_max_tokens() {
+        let tokenizer = tokenizer();
+        let text = "one two three four five six seven eight nine ten";
+        let tokens = tokenizer.count(text);
+        for overlap in [3, 4, 100] {
+            let chunks = chunk_by_tokens(text, &tokenizer, 4, overlap);
+            // Every chunk moves on by one token
+            assert_eq!(chunks.len(), tokens - 3, "overlap {}", overlap);
+            assert_eq!(chunks[0], "one two three four");
+            assert_eq!(chunks[1], " two three four five");
+            assert!(chunks[chunks.len() - 1].ends_with("ten"));
+        }
+        let chunks = chunk_by_tokens(text, &tokenizer, 4, 2);
+        assert_eq!(chunks[1], " three four five six");
+    }
+
+    #[test]
+    fn zero_max_tokens_is_one_token() {
+        let tokenizer = tokenizer();
+        let text = "one two three";
+        assert_eq!(chunk_by_tokens(text, &tokenizer, 0, 0), ["one", " two", " three"]);
+        assert_eq!(chunk_by_tokens(text, &tokenizer, 0, 5), ["one", " two", " three"]);
+        assert!(chunk_by_tokens("", &tokenizer, 0, 0).is_empty());
+        assert!(chunk_by_tokens("", &tokenizer, 8, 2).is_empty());
+    }
+}
@@ -0,0 +1,178 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Fails requests fast while the API is repeatedly failing
+///
+/// After `failure_threshold` consecutive `5xx` responses, timeouts or connection failures
+/// the circuit opens and every request fails immediately with
+/// [`Error::CircuitOpen`](crate::Error::CircuitOpen) until `cool_down` has passed. The circuit is then half-open: the next request is let
+/// through as a probe while the others keep failing fast, if the probe succeeds the circuit
+/// closes again, if it fails the circuit opens for another `cool_down`. A probe that hasn't
+/// finished after `cool_down`, e.g. because it was cancelled, is replaced by the next request.
+///
+/// The breaker state is shared between all clones of the [`Client`](crate::Client) it is set on.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{Client, circuit_breaker::CircuitBreaker};
+/// use std::time::Duration;
+///
+/// let client = Client::new(String::from("sk-..."))
+///     .circuit_breaker(CircuitBreaker::new(5, Duration::from_secs(30)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cool_down: Duration,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// When the probe sent while half-open was let through
+    probe_started: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Open the circuit after `failure_threshold` consecutive failures, for `cool_down`
+    pub fn new(failure_threshold: u32, cool_down: Duration) -> Self {
+        CircuitBreaker {
+            failure_threshold,
+            cool_down,
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+
+    /// Whether requests are currently being rejected, while cooling down or while a probe is
+    /// in flight
+    pub fn is_open(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        match state.opened_at {
+            Some(opened_at) => opened_at.elapsed() < self.cool_down || self.probing(&state),
+            None => false,
+        }
+    }
+
+    /// Whether a request may be sent, letting a single probe through once the circuit is half-open
+    pub(crate) fn admit(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.opened_at {
+            None => true,
+            Some(opened_at) if opened_at.elapsed() < self.cool_down => false,
+            Some(_) if self.probing(&state) => false,
+            Some(_) => {
+                state.probe_started = Some(Instant::now());
+                true
+            }
+        }
+    }
+
+    fn probing(&self, state: &State) -> bool {
+        state.probe_started.is_some_and(|started| started.elapsed() < self.cool_down)
+    }
+
+    pub(crate) fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = 0;
+        state.opened_at = None;
+        state.probe_started = None;
+    }
+
+    /// Let the next request probe in place of one that ended without reaching the API
+    pub(crate) fn release(&self) {
+        self.state.lock().unwrap().probe_started = None;
+    }
+
+    pub(crate) fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        state.probe_started = None;
+        if state.consecutive_failures >= self.failure_threshold {
+            state.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CircuitBreaker;
+    use crate::error::Error;
+    use crate::models::EmbeddingModels;
+    use crate::testing::{CannedResponse, Fault, FaultInjector, Matcher, TestClient};
+    use crate::{embeddings, Client};
+    use serde_json::json;
+    use std::time::Duration;
+
+    async fn embed(client: &Client) -> Result<embeddings::Embeddings, Error> {
+        embeddings::build(EmbeddingModels::TEXT_EMBEDDING_3_SMALL)
+            .client(client)
+            .input("The food was delicious")
+            .embed()
+            .await
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        breaker.record_failure();
+        assert!(breaker.admit());
+        breaker.record_failure();
+        assert!(breaker.is_open());
+        assert!(!breaker.admit());
+    }
+
+    #[test]
+    fn half_open_admits_a_single_probe() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(20));
+        breaker.record_failure();
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert!(breaker.admit());
+        assert!(!breaker.admit());
+        assert!(breaker.is_open());
+
+        breaker.record_success();
+        assert!(!breaker.is_open());
+        assert!(breaker.admit());
+        assert!(breaker.admit());
+    }
+
+    #[test]
+    fn failed_probe_opens_the_circuit_again() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(20));
+        breaker.record_failure();
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert!(breaker.admit());
+        breaker.record_failure();
+        assert!(!breaker.admit());
+    }
+
+    #[tokio::test]
+    async fn connection_failures_open_the_circuit() {
+        let answering = TestClient::new().on(Matcher::post("embeddings"), CannedResponse::json(&json!({
+            "object": "list",
+            "data": [{ "object": "embedding", "embedding": [0.5], "index": 0 }],
+            "model": "text-embedding-3-small",
+            "usage": null
+        })));
+        let faults = FaultInjector::new(answering.clone()).rate(Fault::Connect, 1.0);
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(20));
+        let client = answering.client().transport(faults.clone()).circuit_breaker(breaker.clone());
+
+        assert!(matches!(embed(&client).await, Err(Error::Connect)));
+        assert!(matches!(embed(&client).await, Err(Error::Connect)));
+        assert!(matches!(embed(&client).await, Err(Error::CircuitOpen)));
+        assert_eq!(faults.injected(Fault::Connect), 2);
+
+        // The probe can't connect either, so the circuit opens again
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(matches!(embed(&client).await, Err(Error::Connect)));
+        assert!(breaker.is_open());
+        assert!(matches!(embed(&client).await, Err(Error::CircuitOpen)));
+        assert!(answering.requests().is_empty());
+    }
+}
@@ -0,0 +1,1165 @@
+use crate::batches::FailedRequest;
+use crate::budget::Budget;
+use crate::cache::CacheBackend;
+use crate::circuit_breaker::CircuitBreaker;
+use crate::error::Error;
+use crate::files::FileObject;
+use crate::fine_tuning::FineTuningJob;
+use crate::key_pool::KeyPool;
+use crate::models::{ChatModels, CompletionModels, EditModels, EmbeddingModels, Model};
+use crate::requester::{self, RequestOptions, API_BASE_URL, API_VERSION};
+use crate::retry::RetryPolicy;
+use crate::scheduler::Scheduler;
+use crate::transport::Transport;
+use crate::usage::UsageTracker;
+use crate::{assistants, batches, chat, completions, edits, embeddings, files, fine_tuning, images, models, moderations, responses, threads, uploads, vector_stores};
+use bytes::Bytes;
+use dotenv::dotenv;
+use reqwest::header::HeaderValue;
+use reqwest::{Certificate, Identity, StatusCode, Url};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+use std::env;
+
+/// Result of a health check made with [`ping`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ping {
+    /// Time until the response was received, including any retries
+    pub latency: Duration,
+    /// Whether the API key was accepted
+    pub authenticated: bool,
+}
+
+/// How long a request took, attached to responses as their `timing`
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{chat::{self, ChatMessage}, models};
+///
+/// async {
+///     let messages = vec![ChatMessage::user("Ice cream or cookies?")];
+///     let completion = chat::build(models::ChatModels::GPT_4O_MINI)
+///         .messages(&messages)
+///         .complete()
+///         .await
+///         .expect("Error Getting Response");
+///
+///     if let Some(timing) = completion.timing {
+///         println!("answered in {:?}", timing.elapsed);
+///     }
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    /// Wall-clock time from starting the request until the whole response was read,
+    /// including any retries and continuation rounds
+    pub elapsed: Duration,
+    /// Time until the first chunk of a streamed response arrived, `None` unless streamed
+    pub time_to_first_token: Option<Duration>,
+}
+
+impl Timing {
+    pub(crate) fn since(started: Instant) -> Self {
+        Timing {
+            elapsed: started.elapsed(),
+            time_to_first_token: None,
+        }
+    }
+}
+
+/// Check the API can be reached with the client loaded from the environment
+///
+/// Lists the models, which is authenticated but costs nothing, and reports how long that
+/// took and whether the API key was accepted. Fails if the API couldn't be reached or
+/// responded with an error other than `401 Unauthorized`, useful for readiness probes.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::client;
+///
+/// async {
+///     let ping = client::ping().await.expect("API unreachable");
+///     println!("authenticated: {} in {:?}", ping.authenticated, ping.latency);
+/// };
+/// ```
+pub async fn ping() -> Result<Ping, Error> {
+    RequestOptions::default().client()?.ping().await
+}
+
+/// Client used by requests built without one, set with [`configure`] or loaded from the
+/// environment by the first such request
+static GLOBAL_CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Set the client used by requests built without one, e.g. by [`chat::build`] unless
+/// [`client`](chat::Parameters::client) is called, instead of loading it from the environment
+///
+/// Call it once at startup, before any such request is made. Returns
+/// [`Error::AlreadyConfigured`] if the global client was already set, by an earlier call or by
+/// an earlier request loading it from the environment. Requests given a client explicitly are
+/// unaffected.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{chat::{self, ChatMessage}, models, Client};
+/// use std::time::Duration;
+///
+/// async {
+///     let client = Client::builder()
+///         .timeout(Duration::from_secs(60))
+///         .build()
+///         .expect("Invalid configuration");
+///     oai_rs::configure(client).expect("Already configured");
+///
+///     let messages = vec![ChatMessage::user("Ice cream or cookies?")];
+///     let completion = chat::build(models::ChatModels::GPT_4O_MINI)
+///         .messages(&messages)
+///         .complete()
+///         .await;
+///
+///     println!("{:?}", completion);
+/// };
+/// ```
+pub fn configure(client: Client) -> Result<(), Error> {
+    GLOBAL_CLIENT.set(client).map_err(|_| Error::AlreadyConfigured)
+}
+
+/// The global client, loading it from the environment if it wasn't configured
+pub(crate) fn global() -> Result<&'static Client, Error> {
+    if let Some(client) = GLOBAL_CLIENT.get() {
+        return Ok(client);
+    }
+    let client = Client::from_env()?;
+    Ok(GLOBAL_CLIENT.get_or_init(|| client))
+}
+
+/// Connection pool shared by every client
+static HTTP_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+pub(crate) fn shared_http() -> &'static reqwest::Client {
+    HTTP_CLIENT.get_or_init(reqwest::Client::new)
+}
+
+/// Holds the configuration used to talk to the API
+///
+/// A client can be created from the environment using [`Client::from_env`], or
+/// explicitly with [`Client::new`] or [`Client::builder`]. Requests built without a client
+/// use the global client, set with [`configure`] or loaded from the environment when the
+/// first such request is sent.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{models, Client};
+///
+/// async {
+///     let client = Client::from_env().expect("Missing configuration");
+///     let completions = client.completions(models::CompletionModels::TEXT_DAVINCI_003)
+///         .prompt("Ice cream or cookies?")
+///         .max_tokens(32)
+///         .complete()
+///         .await;
+///
+///     println!("{:?}", completions);
+/// };
+/// ```
+#[derive(Clone)]
+pub struct Client {
+    pub(crate) api_key: Arc<str>,
+    pub(crate) organization: Option<Arc<str>>,
+    pub(crate) project: Option<Arc<str>>,
+    pub(crate) base_url: Arc<str>,
+    failover_base_urls: Arc<[Arc<str>]>,
+    endpoint_paths: Arc<Vec<(String, String)>>,
+    pub(crate) http: reqwest::Client,
+    urls: Arc<RwLock<HashMap<&'static str, Url>>>,
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) circuit_breaker: Option<CircuitBreaker>,
+    pub(crate) budget: Option<Budget>,
+    pub(crate) key_pool: Option<KeyPool>,
+    pub(crate) scheduler: Option<Scheduler>,
+    pub(crate) usage_tracker: Option<UsageTracker>,
+    pub(crate) response_cache: Option<Arc<dyn CacheBackend>>,
+    pub(crate) transport: Option<Arc<dyn Transport>>,
+    #[cfg(feature = "opentelemetry")]
+    pub(crate) metrics: Option<crate::telemetry::Metrics>,
+    pub(crate) label: Option<Arc<str>>,
+    pub(crate) dump_requests: bool,
+    pub(crate) stream_idle_timeout: Option<Duration>,
+    pub(crate) moderate_inputs: bool,
+    pub(crate) default_params: Arc<Map<String, Value>>,
+    end_user: Option<Arc<str>>,
+    user_tagger: Option<UserTagger>,
+}
+
+/// Derives the `user` sent with requests from the caller's end user ID
+pub type UserTagger = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+impl Client {
+    /// Create a client for the default API base URL using the given API key
+    pub fn new(api_key: String) -> Client {
+        Client {
+            api_key: api_key.into(),
+            organization: None,
+            project: None,
+            base_url: format!("{}/{}", API_BASE_URL, API_VERSION).into(),
+            failover_base_urls: Arc::new([]),
+            endpoint_paths: Arc::default(),
+            http: shared_http().clone(),
+            urls: Arc::default(),
+            retry_policy: RetryPolicy::default(),
+            circuit_breaker: None,
+            budget: None,
+            key_pool: None,
+            scheduler: None,
+            usage_tracker: None,
+            response_cache: None,
+            transport: None,
+            #[cfg(feature = "opentelemetry")]
+            metrics: None,
+            label: None,
+            dump_requests: false,
+            stream_idle_timeout: None,
+            moderate_inputs: false,
+            default_params: Arc::default(),
+            end_user: None,
+            user_tagger: None,
+        }
+    }
+
+    /// Configure a client step by step, see [`ClientBuilder`]
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    /// Create a client from the environment, a `.env` file is also loaded if present.
+    ///
+    /// - `OPENAI_API_KEY` - required, the API key used to authenticate requests
+    /// - `OPENAI_ORG_ID` - optional, sent as the `OpenAI-Organization` header
+    /// - `OPENAI_PROJECT_ID` - optional, sent as the `OpenAI-Project` header
+    /// - `OPENAI_BASE_URL` - optional, defaults to `https://api.openai.com/v1`
+    ///
+    /// Returns [`Error::MissingApiKey`] if `OPENAI_API_KEY` is not set.
+    pub fn from_env() -> Result<Client, Error> {
+        dotenv().ok();
+
+        let api_key = env::var("OPENAI_API_KEY").map_err(|_| Error::MissingApiKey)?;
+        let mut client = Client::new(api_key);
+        client.organization = env::var("OPENAI_ORG_ID").ok().map(Arc::from);
+        client.project = env::var("OPENAI_PROJECT_ID").ok().map(Arc::from);
+        if let Ok(base_url) = env::var("OPENAI_BASE_URL") {
+            client = client.base_url(base_url);
+        }
+        Ok(client)
+    }
+
+    /// Set the organization the requests are made on behalf of
+    pub fn organization(mut self, organization: String) -> Self {
+        self.organization = Some(organization.into());
+        self
+    }
+
+    /// Set the project the requests are made on behalf of
+    pub fn project(mut self, project: String) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    /// Set the base URL, including the API version, that requests are sent to
+    pub fn base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url.trim_end_matches('/').into();
+        self.urls = Arc::default();
+        self
+    }
+
+    /// Base URLs to fail over to, in order, when a request can't connect to the base URL
+    ///
+    /// Each request starts at the base URL and moves on to the next URL in the list whenever
+    /// it fails to connect, failing over doesn't count towards the client's [`RetryPolicy`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::Client;
+    ///
+    /// let client = Client::new(String::from("sk-..."))
+    ///     .base_url(String::from("https://gateway.internal/v1"))
+    ///     .failover_base_urls(vec![String::from("https://api.openai.com/v1")]);
+    /// ```
+    pub fn failover_base_urls(mut self, base_urls: Vec<String>) -> Self {
+        self.failover_base_urls = base_urls.iter().map(|url| Arc::from(url.trim_end_matches('/'))).collect();
+        self
+    }
+
+    /// Send requests for `endpoint`, e.g. `chat/completions`, to `path` instead, for gateways
+    /// that expose endpoints at their own paths
+    ///
+    /// The override also applies below `endpoint`, an override of `responses` applies to
+    /// `responses/{id}/cancel` as well, and the longest matching endpoint wins. `path` is
+    /// appended to the base URL, unless it starts with `/` and replaces the path of the base
+    /// URL, or is a full URL.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::Client;
+    ///
+    /// let client = Client::new(String::from("sk-..."))
+    ///     .base_url(String::from("https://gateway.internal/v1"))
+    ///     .endpoint_path("chat/completions", "/openai/deployments/gpt-4o/chat/completions")
+    ///     .endpoint_path("embeddings", "/openai/deployments/embed/embeddings");
+    /// ```
+    pub fn endpoint_path(mut self, endpoint: &str, path: &str) -> Self {
+        let endpoint = endpoint.trim_matches('/').to_string();
+        let path = path.trim_end_matches('/').to_string();
+        let endpoint_paths = Arc::make_mut(&mut self.endpoint_paths);
+        endpoint_paths.retain(|(e, _)| *e != endpoint);
+        endpoint_paths.push((endpoint, path));
+        self.urls = Arc::default();
+        self
+    }
+
+    /// Set how failed requests are retried, see [`RetryPolicy`]
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Fail requests fast after repeated upstream failures, see [`CircuitBreaker`]
+    pub fn circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// Limit how much can be spent in a window of time, see [`Budget`]
+    pub fn budget(mut self, budget: Budget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Spread requests over several API keys instead of the client's own key, see [`KeyPool`]
+    pub fn key_pool(mut self, key_pool: KeyPool) -> Self {
+        self.key_pool = Some(key_pool);
+        self
+    }
+
+    /// Dispatch requests in submission order with a limit on how many are in flight, see [`Scheduler`]
+    pub fn scheduler(mut self, scheduler: Scheduler) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    /// Record the usage reported by responses, see [`UsageTracker`]
+    pub fn usage_tracker(mut self, usage_tracker: UsageTracker) -> Self {
+        self.usage_tracker = Some(usage_tracker);
+        self
+    }
+
+    /// Answer completion, chat, edit, embedding and moderation requests from `cache` when
+    /// the same request was sent before, see [`MemoryCache`](crate::cache::MemoryCache) and
+    /// [`DiskCache`](crate::cache::DiskCache)
+    ///
+    /// Cached answers don't count towards the client's budget or usage tracker, as nothing
+    /// is spent on them.
+    pub fn response_cache<C: CacheBackend + 'static>(mut self, cache: C) -> Self {
+        self.response_cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Send requests with `transport` instead of the client's HTTP client, see [`Transport`]
+    pub fn transport<T: Transport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Record requests as OpenTelemetry metrics on instruments created from `meter`
+    ///
+    /// - `oai_rs.requests` - counter of requests, by `endpoint`
+    /// - `oai_rs.errors` - counter of failed requests, by `endpoint` and `error.class`, e.g.
+    ///   `rate_limited`, `server`, `client` or `transport`
+    /// - `oai_rs.request.duration` - histogram of request durations in seconds, by `endpoint`
+    /// - `oai_rs.tokens` - counter of tokens used, by `model` and `token.type`: `prompt`,
+    ///   `cached` (prompt tokens read from the prompt cache) or `completion`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "opentelemetry")] {
+    /// use oai_rs::Client;
+    ///
+    /// let meter = opentelemetry::global::meter("my-service");
+    /// let client = Client::new(String::from("sk-...")).meter(&meter);
+    /// # }
+    /// ```
+    #[cfg(feature = "opentelemetry")]
+    pub fn meter(mut self, meter: &opentelemetry::metrics::Meter) -> Self {
+        self.metrics = Some(crate::telemetry::Metrics::new(meter));
+        self
+    }
+
+    /// Print each request's method, URL and headers along with the response status,
+    /// useful when debugging. The `Authorization` header is never printed.
+    pub fn dump_requests(mut self, dump_requests: bool) -> Self {
+        self.dump_requests = dump_requests;
+        self
+    }
+
+    /// Abort streams with [`Error::StreamIdleTimeout`] if no data is received for `timeout`,
+    /// otherwise a stalled connection can leave a stream waiting indefinitely
+    pub fn stream_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.stream_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Run prompts through the moderations endpoint before sending them to a completion,
+    /// chat, response or edit model, returning [`Error::ContentFlagged`] instead if the
+    /// prompt is flagged
+    pub fn moderate_inputs(mut self, moderate_inputs: bool) -> Self {
+        self.moderate_inputs = moderate_inputs;
+        self
+    }
+
+    /// Send `value` as the parameter `key` with every completion, chat and response request
+    /// that doesn't set it itself, e.g. a default `temperature` or `store`
+    ///
+    /// Other endpoints, e.g. embeddings or assistants, reject parameters they don't know, so
+    /// defaults are never sent to them.
+    pub fn default_param(mut self, key: &str, value: Value) -> Self {
+        Arc::make_mut(&mut self.default_params).insert(key.to_string(), value);
+        self
+    }
+
+    /// A copy of the client that tags every request with the given end user
+    ///
+    /// The `user` sent with completion, chat, response, embedding and image requests is
+    /// derived from `user_id`, by default as its SHA-256 hex digest so the raw ID is never
+    /// sent, unless a request sets `user` itself. This helps OpenAI monitor and detect abuse
+    /// per end user in multi-tenant applications.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{models, Client};
+    ///
+    /// async {
+    ///     let client = Client::from_env().expect("Missing configuration");
+    ///     let completions = client.for_user("customer-42")
+    ///         .completions(models::CompletionModels::TEXT_DAVINCI_003)
+    ///         .prompt("Ice cream or cookies?")
+    ///         .complete()
+    ///         .await;
+    ///
+    ///     println!("{:?}", completions);
+    /// };
+    /// ```
+    pub fn for_user(&self, user_id: &str) -> Client {
+        let mut client = self.clone();
+        client.end_user = Some(user_id.into());
+        client
+    }
+
+    /// A copy of this client whose usage is recorded under `label` by the client's
+    /// [`UsageTracker`], e.g. a feature name or tenant ID. The label is never sent to the API.
+    pub fn with_label(&self, label: &str) -> Client {
+        let mut client = self.clone();
+        client.label = Some(label.into());
+        client
+    }
+
+    /// Set how the `user` sent with requests is derived from the ID given to [`Client::for_user`]
+    pub fn user_tagger<F>(mut self, tagger: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static
+    {
+        self.user_tagger = Some(Arc::new(tagger));
+        self
+    }
+
+    /// Create a completion request sent using this client, see [`completions::build`]
+    pub fn completions<'a, M: Into<CompletionModels>>(&self, model: M) -> completions::Parameters<'a> {
+        completions::build(model).client(self)
+    }
+
+    /// Create an edit request sent using this client, see [`edits::build`]
+    pub fn edits<'a, M: Into<EditModels>>(&self, model: M, instruction: String) -> edits::Parameters<'a> {
+        edits::build(model, instruction).client(self)
+    }
+
+    /// Create a chat completion request sent using this client, see [`chat::build`]
+    pub fn chat<'a, M: Into<ChatModels>>(&self, model: M) -> chat::Parameters<'a> {
+        chat::build(model).client(self)
+    }
+
+    /// Create a Responses API request sent using this client, see [`responses::build`]
+    pub fn responses<'a, M: Into<ChatModels>>(&self, model: M) -> responses::Parameters<'a> {
+        responses::build(model).client(self)
+    }
+
+    /// Create an embeddings request sent using this client, see [`embeddings::build`]
+    pub fn embeddings<'a, M: Into<EmbeddingModels>>(&self, model: M) -> embeddings::Parameters<'a> {
+        embeddings::build(model).client(self)
+    }
+
+    /// Create a moderation request sent using this client, see [`moderations::build`]
+    pub fn moderations<'a>(&self) -> moderations::Parameters<'a> {
+        moderations::build().client(self)
+    }
+
+    /// Create an assistant request sent using this client, see [`assistants::build`]
+    pub fn assistants<'a, M: Into<ChatModels>>(&self, model: M) -> assistants::Parameters<'a> {
+        assistants::build(model).client(self)
+    }
+
+    /// Create a threads request sent using this client, see [`threads::build`]
+    pub fn threads(&self) -> threads::Parameters {
+        threads::build().client(self)
+    }
+
+    /// Create a vector stores request sent using this client, see [`vector_stores::build`]
+    pub fn vector_stores(&self) -> vector_stores::Parameters {
+        vector_stores::build().client(self)
+    }
+
+    /// Create an upload request sent using this client, see [`uploads::build`]
+    pub fn uploads(&self) -> uploads::Parameters {
+        uploads::build().client(self)
+    }
+
+    /// Create an images request sent using this client, see [`images::build`]
+    pub fn images(&self) -> images::Parameters {
+        images::build().client(self)
+    }
+
+    /// Upload the file at `path` for `purpose`, see [`files::upload`]
+    pub async fn upload_file<P: AsRef<Path>>(&self, path: P, purpose: &str) -> Result<FileObject, Error> {
+        files::upload_with(Some(self), path.as_ref(), purpose).await
+    }
+
+    /// Download the content of a file, see [`files::content`]
+    pub async fn file_content(&self, file_id: &str) -> Result<Bytes, Error> {
+        files::content_with(Some(self), file_id).await
+    }
+
+    /// Download the content of a file into `path`, see [`files::download`]
+    pub async fn download_file<P: AsRef<Path>>(&self, file_id: &str, path: P) -> Result<(), Error> {
+        files::download_with(Some(self), file_id, path.as_ref()).await
+    }
+
+    /// Get a stored chat completion, see [`chat::stored`]
+    pub async fn stored_chat_completion(&self, completion_id: &str) -> Result<chat::ChatCompletion, Error> {
+        chat::stored_with(Some(self), completion_id).await
+    }
+
+    /// List stored chat completions, see [`chat::list_stored`]
+    pub fn stored_chat_completions(&self) -> chat::ListParameters<chat::ChatCompletion> {
+        chat::list_stored().client(self)
+    }
+
+    /// Delete a stored chat completion, see [`chat::delete_stored`]
+    pub async fn delete_stored_chat_completion(&self, completion_id: &str) -> Result<chat::DeletedCompletion, Error> {
+        chat::delete_stored_with(Some(self), completion_id).await
+    }
+
+    /// Get a stored response, see [`responses::get`]
+    pub async fn response(&self, response_id: &str) -> Result<responses::Response, Error> {
+        responses::get_with(Some(self), response_id).await
+    }
+
+    /// List the input items of a stored response, see [`responses::input_items`]
+    pub fn response_input_items(&self, response_id: &str) -> responses::InputItemsParameters {
+        responses::input_items(response_id).client(self)
+    }
+
+    /// Cancel a background response, see [`responses::cancel`]
+    pub async fn cancel_response(&self, response_id: &str) -> Result<responses::Response, Error> {
+        responses::cancel_with(Some(self), response_id).await
+    }
+
+    /// Get a fine-tuning job, see [`fine_tuning::get`]
+    pub async fn fine_tuning_job(&self, job_id: &str) -> Result<FineTuningJob, Error> {
+        fine_tuning::get_with(Some(self), job_id).await
+    }
+
+    /// Pause a running fine-tuning job, see [`fine_tuning::pause`]
+    pub async fn pause_fine_tuning_job(&self, job_id: &str) -> Result<FineTuningJob, Error> {
+        fine_tuning::pause_with(Some(self), job_id).await
+    }
+
+    /// Resume a paused fine-tuning job, see [`fine_tuning::resume`]
+    pub async fn resume_fine_tuning_job(&self, job_id: &str) -> Result<FineTuningJob, Error> {
+        fine_tuning::resume_with(Some(self), job_id).await
+    }
+
+    /// Download and parse a batch's error file, see [`batches::errors`]
+    pub async fn batch_errors(&self, error_file_id: &str) -> Result<Vec<FailedRequest>, Error> {
+        batches::errors_with(Some(self), error_file_id).await
+    }
+
+    /// Request a list of all currently available models, see [`models::list`]
+    pub async fn models(&self) -> Result<Vec<Model>, Error> {
+        models::list_with(Some(self)).await
+    }
+
+    /// Return information for a specific model, see [`models::get`]
+    pub async fn model(&self, model_name: String) -> Result<Model, Error> {
+        models::get_with(Some(self), model_name).await
+    }
+
+    /// Whether a model exists and is available to this client, see [`models::exists`]
+    pub async fn model_exists(&self, model_name: &str) -> Result<bool, Error> {
+        models::exists_with(Some(self), model_name).await
+    }
+
+    /// Check the API can be reached with this client's configuration, see [`ping`]
+    pub async fn ping(&self) -> Result<Ping, Error> {
+        let options = RequestOptions {
+            client: Some(self.clone()),
+            ..RequestOptions::default()
+        };
+        let started = Instant::now();
+        let response: Result<serde_json::Value, Error> = requester::models(&options, None).await;
+        let authenticated = match response {
+            Ok(_) => true,
+            Err(e) if e.status() == Some(StatusCode::UNAUTHORIZED) => false,
+            Err(e) => return Err(e),
+        };
+        Ok(Ping {
+            latency: started.elapsed(),
+            authenticated,
+        })
+    }
+
+    /// The full URL for an endpoint path, parsed URLs of fixed endpoints are cached
+    pub(crate) fn url(&self, path: Cow<'static, str>) -> Result<Url, Error> {
+        let parse = |path: &str| self.join(&self.base_url, path);
+        match path {
+            Cow::Borrowed(path) => {
+                if let Some(url) = self.urls.read().unwrap().get(path) {
+                    return Ok(url.clone());
+                }
+                let url = parse(path)?;
+                self.urls.write().unwrap().insert(path, url.clone());
+                Ok(url)
+            }
+            Cow::Owned(path) => parse(&path),
+        }
+    }
+
+    /// The URL for `path` on the failover base URL at `index`, `None` if there are no more
+    pub(crate) fn failover_url(&self, index: usize, path: &str) -> Option<Result<Url, Error>> {
+        let base_url = self.failover_base_urls.get(index)?;
+        Some(self.join(base_url, path))
+    }
+
+    /// The URL for `path` on `base_url`, with the path overridden by [`Client::endpoint_path`]
+    /// if one matches
+    fn join(&self, base_url: &str, path: &str) -> Result<Url, Error> {
+        let matched = self.endpoint_paths.iter()
+            .filter(|(endpoint, _)| {
+                path.strip_prefix(endpoint.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '?']))
+            })
+            .max_by_key(|(endpoint, _)| endpoint.len());
+        fn invalid(url: &str, e: impl fmt::Display) -> Error {
+            Error::InvalidParameter(format!("invalid URL {}: {}", url, e))
+        }
+        match matched {
+            Some((endpoint, target)) => {
+                let path = format!("{}{}", target, &path[endpoint.len()..]);
+                if path.starts_with("http://") || path.starts_with("https://") {
+                    Url::parse(&path).map_err(|e| invalid(&path, e))
+                } else if path.starts_with('/') {
+                    let base = Url::parse(base_url).map_err(|e| invalid(base_url, e))?;
+                    base.join(&path).map_err(|e| invalid(&path, e))
+                } else {
+                    let url = format!("{}/{}", base_url, path);
+                    Url::parse(&url).map_err(|e| invalid(&url, e))
+                }
+            }
+            None => {
+                let url = format!("{}/{}", base_url, path);
+                Url::parse(&url).map_err(|e| invalid(&url, e))
+            }
+        }
+    }
+
+    /// Check the base URLs and endpoint overrides make valid URLs
+    fn validate_urls(&self) -> Result<(), Error> {
+        let base_urls = std::iter::once(&self.base_url).chain(self.failover_base_urls.iter());
+        for base_url in base_urls {
+            self.join(base_url, "models")?;
+            for (endpoint, _) in self.endpoint_paths.iter() {
+                self.join(base_url, endpoint)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The `user` to send with requests, derived from the end user set with [`Client::for_user`]
+    pub(crate) fn end_user(&self) -> Option<String> {
+        let user_id = self.end_user.as_deref()?;
+        match &self.user_tagger {
+            Some(tagger) => Some(tagger(user_id)),
+            None => {
+                let digest = Sha256::digest(user_id.as_bytes());
+                Some(digest.iter().map(|b| format!("{:02x}", b)).collect())
+            }
+        }
+    }
+
+    /// The key to send the next attempt with, along with its position in the key pool if it came from one
+    pub(crate) fn api_key(&self) -> (Option<usize>, Arc<str>) {
+        match self.key_pool.as_ref().and_then(KeyPool::select) {
+            Some((index, key)) => (Some(index), key),
+            None => (None, self.api_key.clone()),
+        }
+    }
+
+    /// The `Authorization` header value for `api_key`, marked as sensitive so it is hidden from `Debug` output
+    pub(crate) fn authorization(&self, api_key: &str) -> HeaderValue {
+        let mut value = HeaderValue::from_str(&format!("Bearer {}", api_key))
+            .unwrap_or_else(|_| HeaderValue::from_static(""));
+        value.set_sensitive(true);
+        value
+    }
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("api_key", &"[redacted]")
+            .field("organization", &self.organization)
+            .field("project", &self.project)
+            .field("base_url", &self.base_url)
+            .field("failover_base_urls", &self.failover_base_urls)
+            .field("endpoint_paths", &self.endpoint_paths)
+            .field("retry_policy", &self.retry_policy)
+            .field("circuit_breaker", &self.circuit_breaker)
+            .field("budget", &self.budget)
+            .field("key_pool", &self.key_pool)
+            .field("scheduler", &self.scheduler)
+            .field("usage_tracker", &self.usage_tracker)
+            .field("response_cache", &self.response_cache.is_some())
+            .field("transport", &self.transport.is_some())
+            .field("label", &self.label)
+            .field("dump_requests", &self.dump_requests)
+            .field("stream_idle_timeout", &self.stream_idle_timeout)
+            .field("moderate_inputs", &self.moderate_inputs)
+            .field("default_params", &self.default_params)
+            .field("end_user", &self.end_user)
+            .finish()
+    }
+}
+
+/// Builds a [`Client`], gathering its configuration in one place
+///
+/// The API key, organization, project and base URL fall back to the same environment
+/// variables as [`Client::from_env`] when they aren't set, everything else defaults to what
+/// [`Client::new`] uses. Further options can be set on the built client, e.g.
+/// [`Client::budget`].
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::retry::RetryPolicy;
+/// use oai_rs::Client;
+/// use std::time::Duration;
+///
+/// let client = Client::builder()
+///     .api_key(String::from("sk-..."))
+///     .project(String::from("proj_abc123"))
+///     .timeout(Duration::from_secs(60))
+///     .connect_timeout(Duration::from_secs(5))
+///     .retry_policy(RetryPolicy::default())
+///     .default_param("temperature", serde_json::json!(0.2))
+///     .build()
+///     .expect("Invalid configuration");
+/// ```
+#[derive(Default)]
+pub struct ClientBuilder {
+    api_key: Option<String>,
+    organization: Option<String>,
+    project: Option<String>,
+    base_url: Option<String>,
+    endpoint_paths: Vec<(String, String)>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    stream_idle_timeout: Option<Duration>,
+    retry_policy: Option<RetryPolicy>,
+    transport: Option<Arc<dyn Transport>>,
+    default_params: Map<String, Value>,
+    client_certificate: Option<ClientCertificate>,
+    ca_bundles: Vec<Vec<u8>>,
+    built_in_root_certs: Option<bool>,
+}
+
+/// A client certificate for mutual TLS, parsed when the client is built
+enum ClientCertificate {
+    Pem { cert: Vec<u8>, key: Vec<u8> },
+    Pkcs12 { der: Vec<u8>, password: String },
+}
+
+impl ClientCertificate {
+    fn identity(&self) -> Result<Identity, Error> {
+        let identity = match self {
+            ClientCertificate::Pem { cert, key } => Identity::from_pkcs8_pem(cert, key),
+            ClientCertificate::Pkcs12 { der, password } => Identity::from_pkcs12_der(der, password),
+        };
+        identity.map_err(|e| Error::InvalidParameter(format!("client certificate: {}", e)))
+    }
+}
+
+/// The certificates in a PEM encoded CA bundle
+fn certificates(bundle: &[u8]) -> Result<Vec<Certificate>, Error> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+
+    let invalid = |reason: String| Error::InvalidParameter(format!("CA bundle: {}", reason));
+    let text = std::str::from_utf8(bundle).map_err(|e| invalid(e.to_string()))?;
+    let certificates = text.split_inclusive(END)
+        .filter_map(|block| block.find(BEGIN).map(|start| &block[start..]))
+        .map(|block| Certificate::from_pem(block.as_bytes()).map_err(|e| invalid(e.to_string())))
+        .collect::<Result<Vec<_>, _>>()?;
+    if certificates.is_empty() {
+        return Err(invalid(String::from("no PEM certificates found")));
+    }
+    Ok(certificates)
+}
+
+impl ClientBuilder {
+    /// A builder with nothing set
+    pub fn new() -> Self {
+        ClientBuilder::default()
+    }
+
+    /// The API key used to authenticate requests, `OPENAI_API_KEY` unless set
+    pub fn api_key(mut self, api_key: String) -> Self {
+        self.api_key = Some(api_key);
+        self
+    }
+
+    /// The organization the requests are made on behalf of, `OPENAI_ORG_ID` unless set
+    pub fn organization(mut self, organization: String) -> Self {
+        self.organization = Some(organization);
+        self
+    }
+
+    /// The project the requests are made on behalf of, `OPENAI_PROJECT_ID` unless set
+    pub fn project(mut self, project: String) -> Self {
+        self.project = Some(project);
+        self
+    }
+
+    /// The base URL, including the API version, `OPENAI_BASE_URL` or
+    /// `https://api.openai.com/v1` unless set
+    pub fn base_url(mut self, base_url: String) -> Self {
+        self.base_url = Some(base_url);
+        self
+    }
+
+    /// See [`Client::endpoint_path`]
+    pub fn endpoint_path(mut self, endpoint: &str, path: &str) -> Self {
+        self.endpoint_paths.push((endpoint.to_string(), path.to_string()));
+        self
+    }
+
+    /// The longest each attempt of a request may take, from connecting until the response
+    /// body is read. Unlimited unless set.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// The longest connecting may take. Unlimited unless set.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// See [`Client::stream_idle_timeout`]
+    pub fn stream_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.stream_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// See [`Client::retry_policy`]
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Middleware sending requests instead of the client's HTTP client, see [`Client::transport`]
+    pub fn transport<T: Transport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// See [`Client::default_param`]
+    pub fn default_param(mut self, key: &str, value: Value) -> Self {
+        self.default_params.insert(key.to_string(), value);
+        self
+    }
+
+    /// The certificate to authenticate with to gateways that require mutual TLS, from a PEM
+    /// encoded certificate chain and its PEM encoded PKCS #8 private key
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::Client;
+    ///
+    /// async {
+    ///     let client = Client::builder()
+    ///         .base_url(String::from("https://gateway.internal/v1"))
+    ///         .client_certificate_pem(std::fs::read("client.pem")?, std::fs::read("client.key")?)
+    ///         .ca_bundle_pem(std::fs::read("internal-ca.pem")?)
+    ///         .build()?;
+    ///     # Ok::<_, Box<dyn std::error::Error>>(client)
+    /// };
+    /// ```
+    pub fn client_certificate_pem(mut self, cert: Vec<u8>, key: Vec<u8>) -> Self {
+        self.client_certificate = Some(ClientCertificate::Pem { cert, key });
+        self
+    }
+
+    /// The certificate to authenticate with to gateways that require mutual TLS, from a DER
+    /// encoded PKCS #12 archive protected by `password`
+    pub fn client_certificate_pkcs12(mut self, der: Vec<u8>, password: String) -> Self {
+        self.client_certificate = Some(ClientCertificate::Pkcs12 { der, password });
+        self
+    }
+
+    /// Trust the certificates of a PEM encoded CA bundle as well, e.g. of a gateway with a
+    /// certificate issued by an internal CA. Can be called several times.
+    pub fn ca_bundle_pem(mut self, pem: Vec<u8>) -> Self {
+        self.ca_bundles.push(pem);
+        self
+    }
+
+    /// Whether to trust the system's root certificates, turn off to only trust the
+    /// certificates of [`ca_bundle_pem`](ClientBuilder::ca_bundle_pem). On unless set.
+    pub fn built_in_root_certs(mut self, enabled: bool) -> Self {
+        self.built_in_root_certs = Some(enabled);
+        self
+    }
+
+    /// Create the client
+    ///
+    /// Returns [`Error::MissingApiKey`] if no API key was set and `OPENAI_API_KEY` isn't set
+    /// either, or [`Error::InvalidParameter`] if the base URL or an endpoint override don't
+    /// make a valid URL, a certificate couldn't be parsed or the HTTP client couldn't be
+    /// created.
+    pub fn build(self) -> Result<Client, Error> {
+        dotenv().ok();
+
+        let api_key = match self.api_key {
+            Some(api_key) => api_key,
+            None => env::var("OPENAI_API_KEY").map_err(|_| Error::MissingApiKey)?,
+        };
+        let mut client = Client::new(api_key);
+        client.organization = self.organization.or_else(|| env::var("OPENAI_ORG_ID").ok()).map(Arc::from);
+        client.project = self.project.or_else(|| env::var("OPENAI_PROJECT_ID").ok()).map(Arc::from);
+        if let Some(base_url) = self.base_url.or_else(|| env::var("OPENAI_BASE_URL").ok()) {
+            client = client.base_url(base_url);
+        }
+        for (endpoint, path) in self.endpoint_paths.iter() {
+            client = client.endpoint_path(endpoint, path);
+        }
+        client.validate_urls()?;
+        let tls = self.client_certificate.is_some() || !self.ca_bundles.is_empty() || self.built_in_root_certs.is_some();
+        if self.timeout.is_some() || self.connect_timeout.is_some() || tls {
+            let mut http = reqwest::Client::builder();
+            if let Some(timeout) = self.timeout {
+                http = http.timeout(timeout);
+            }
+            if let Some(timeout) = self.connect_timeout {
+                http = http.connect_timeout(timeout);
+            }
+            if let Some(certificate) = &self.client_certificate {
+                http = http.identity(certificate.identity()?);
+            }
+            for bundle in self.ca_bundles.iter() {
+                for certificate in certificates(bundle)? {
+                    http = http.add_root_certificate(certificate);
+                }
+            }
+            if let Some(enabled) = self.built_in_root_certs {
+                http = http.tls_built_in_root_certs(enabled);
+            }
+            client.http = http.build().map_err(|e| Error::InvalidParameter(e.to_string()))?;
+        }
+        client.stream_idle_timeout = self.stream_idle_timeout;
+        if let Some(retry_policy) = self.retry_policy {
+            client.retry_policy = retry_policy;
+        }
+        client.transport = self.transport;
+        client.default_params = Arc::new(self.default_params);
+        Ok(client)
+    }
+}
+
+impl fmt::Debug for ClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientBuilder")
+            .field("api_key", &self.api_key.as_ref().map(|_| "[redacted]"))
+            .field("organization", &self.organization)
+            .field("project", &self.project)
+            .field("base_url", &self.base_url)
+            .field("endpoint_paths", &self.endpoint_paths)
+            .field("timeout", &self.timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("stream_idle_timeout", &self.stream_idle_timeout)
+            .field("retry_policy", &self.retry_policy)
+            .field("transport", &self.transport.is_some())
+            .field("default_params", &self.default_params)
+            .field("client_certificate", &self.client_certificate.as_ref().map(|_| "[redacted]"))
+            .field("ca_bundles", &self.ca_bundles.len())
+            .field("built_in_root_certs", &self.built_in_root_certs)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Client;
+    use crate::embeddings::{self, Embeddings};
+    use crate::error::Error;
+    use crate::models::EmbeddingModels;
+    use crate::testing::{CannedResponse, Fault, FaultInjector, Matcher, TestClient};
+    use crate::transport::{Transport, TransportFuture};
+    use reqwest::{Request, StatusCode};
+    use serde_json::json;
+
+    fn gateway() -> Client {
+        Client::new(String::from("sk-test")).base_url(String::from("https://gateway.test/v1/"))
+    }
+
+    fn url(client: &Client, path: &'static str) -> String {
+        client.url(path.into()).unwrap().to_string()
+    }
+
+    #[test]
+    fn endpoint_paths_override_relative_absolute_and_full_urls() {
+        let client = gateway()
+            .endpoint_path("chat/completions", "openai/chat")
+            .endpoint_path("embeddings", "/deployments/embed/embeddings")
+            .endpoint_path("moderations", "https://moderation.test/moderate");
+
+        assert_eq!(url(&client, "chat/completions"), "https://gateway.test/v1/openai/chat");
+        assert_eq!(url(&client, "embeddings"), "https://gateway.test/deployments/embed/embeddings");
+        assert_eq!(url(&client, "moderations"), "https://moderation.test/moderate");
+        assert_eq!(url(&client, "models"), "https://gateway.test/v1/models");
+    }
+
+    #[test]
+    fn endpoint_paths_apply_below_the_endpoint() {
+        let client = gateway()
+            .endpoint_path("responses", "/proxy/responses")
+            .endpoint_path("responses/input_items", "/items");
+
+        assert_eq!(url(&client, "responses/resp_1/cancel"), "https://gateway.test/proxy/responses/resp_1/cancel");
+        assert_eq!(url(&client, "responses?limit=2"), "https://gateway.test/proxy/responses?limit=2");
+        assert_eq!(url(&client, "responses/input_items"), "https://gateway.test/items");
+        assert_eq!(url(&client, "responses_archive"), "https://gateway.test/v1/responses_archive");
+    }
+
+    #[test]
+    fn endpoint_paths_apply_to_failover_base_urls() {
+        let client = gateway()
+            .failover_base_urls(vec![String::from("https://backup.test/v2")])
+            .endpoint_path("chat/completions", "openai/chat")
+            .endpoint_path("embeddings", "/deployments/embed/embeddings");
+
+        let failover = |path: &str| client.failover_url(0, path).unwrap().unwrap().to_string();
+        assert_eq!(failover("chat/completions"), "https://backup.test/v2/openai/chat");
+        assert_eq!(failover("embeddings"), "https://backup.test/deployments/embed/embeddings");
+        assert!(client.failover_url(1, "embeddings").is_none());
+    }
+
+    #[test]
+    fn invalid_urls_are_rejected_when_building() {
+        let built = Client::builder()
+            .api_key(String::from("sk-test"))
+            .base_url(String::from("gateway.test/v1"))
+            .build();
+        assert!(matches!(&built, Err(Error::InvalidParameter(reason)) if reason.starts_with("invalid URL gateway.test/v1/models")), "{:?}", built.err());
+
+        let built = Client::builder()
+            .api_key(String::from("sk-test"))
+            .base_url(String::from("https://gateway.test/v1"))
+            .endpoint_path("chat/completions", "https://[gateway/chat")
+            .build();
+        assert!(matches!(built, Err(Error::InvalidParameter(_))));
+
+        let client = gateway().endpoint_path("chat/completions", "https://[gateway/chat");
+        assert!(matches!(client.url("chat/completions".into()), Err(Error::InvalidParameter(_))));
+    }
+
+    /// Sends requests for the test base URL through `primary` and the rest to `secondary`
+    struct ByHost {
+        primary: FaultInjector<TestClient>,
+        secondary: TestClient,
+    }
+
+    impl Transport for ByHost {
+        fn execute(&self, request: Request) -> TransportFuture {
+            match request.url().host_str() {
+                Some("oai-rs.test") => self.primary.execute(request),
+                _ => self.secondary.execute(request),
+            }
+        }
+    }
+
+    /// A client failing over to a secondary that answers embeddings, with `fault` injected
+    /// into every request to the primary
+    fn failing_over(fault: Fault) -> (Client, FaultInjector<TestClient>, TestClient) {
+        let answering = || TestClient::new().on(Matcher::post("embeddings"), CannedResponse::json(&json!({
+            "object": "list",
+            "data": [{ "object": "embedding", "embedding": [0.5], "index": 0 }],
+            "model": "text-embedding-3-small",
+            "usage": null
+        })));
+        let primary = FaultInjector::new(answering()).rate(fault, 1.0);
+        let secondary = answering();
+        let client = TestClient::new().client()
+            .failover_base_urls(vec![String::from("https://secondary.test/v1")])
+            .transport(ByHost { primary: primary.clone(), secondary: secondary.clone() });
+        (client, primary, secondary)
+    }
+
+    async fn embed(client: &Client) -> Result<Embeddings, Error> {
+        embeddings::build(EmbeddingModels::TEXT_EMBEDDING_3_SMALL).client(client).input("cookies").embed().await
+    }
+
+    #[tokio::test]
+    async fn connection_failures_fail_over_to_the_next_base_url() {
+        let (client, primary, secondary) = failing_over(Fault::Connect);
+
+        let embedded = embed(&client).await.unwrap();
+
+        assert_eq!(embedded.data[0].embedding, [0.5]);
+        assert_eq!(primary.injected(Fault::Connect), 1);
+        assert_eq!(secondary.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn other_failures_do_not_fail_over() {
+        let (client, primary, secondary) = failing_over(Fault::Timeout);
+        assert!(matches!(embed(&client).await, Err(Error::Timeout)));
+        assert_eq!(primary.injected(Fault::Timeout), 1);
+        assert!(secondary.requests().is_empty());
+
+        let (client, _, secondary) = failing_over(Fault::ServerError);
+        let result = embed(&client).await;
+        assert_eq!(result.as_ref().err().and_then(Error::status), Some(StatusCode::INTERNAL_SERVER_ERROR), "{:?}", result);
+        assert!(secondary.requests().is_empty());
+    }
+}
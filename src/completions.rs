@@ -1,33 +1,150 @@
-use crate::requester;
+use crate::choices::{self, Choice, PostProcess};
+use crate::client::{Client, Timing};
+use crate::error::Error;
+use crate::requester::{self, Query, RequestOptions};
 use crate::models::CompletionModels;
-use crate::usage::Usage;
-use reqwest::StatusCode;
+use crate::moderations;
+use crate::stream::{EventStream, Flush, Resume, ResumableStream};
+#[cfg(feature = "tokenizer")]
+use crate::tokenizer::Tokenizer;
+use crate::usage::{OutputBudget, Usage};
 use serde::{Serialize, Deserialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::future::Future;
+use std::time::Instant;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::Sender;
+
+/// Log probabilities of the generated tokens, returned when `logprobs` is set
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Logprobs {
+    pub tokens: Vec<String>,
+    /// `None` for the first token of an echoed prompt
+    pub token_logprobs: Vec<Option<f64>>,
+    pub top_logprobs: Option<Vec<Option<HashMap<String, f64>>>>,
+    pub text_offset: Vec<usize>
+}
+
+impl Logprobs {
+    fn extend(&mut self, other: Logprobs) {
+        self.tokens.extend(other.tokens);
+        self.token_logprobs.extend(other.token_logprobs);
+        if let Some(top) = other.top_logprobs {
+            self.top_logprobs.get_or_insert_with(Vec::new).extend(top);
+        }
+        self.text_offset.extend(other.text_offset);
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CompletionChoice {
     pub text: String,
     pub index: i32,
-    pub logprobs: Option<i32>,
+    pub logprobs: Option<Logprobs>,
     pub finish_reason: String
 }
 
+impl Choice for CompletionChoice {
+    fn finish_reason(&self) -> Option<&str> {
+        Some(&self.finish_reason)
+    }
+
+    fn token_logprobs(&self) -> Vec<(&str, f64)> {
+        match &self.logprobs {
+            Some(logprobs) => logprobs.tokens.iter()
+                .zip(logprobs.token_logprobs.iter())
+                .filter_map(|(token, logprob)| logprob.map(|l| (token.as_str(), l)))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Completion {
+    #[serde(default)]
     pub id: String,
+    #[serde(default)]
     pub object: String,
+    #[serde(default, alias = "created_at")]
     pub created: u64,
+    /// Older deployments and some gateways send this as `engine`
+    #[serde(alias = "engine")]
     pub model: String,
+    #[serde(default)]
+    pub system_fingerprint: Option<String>,
     pub choices: Vec<CompletionChoice>,
-    pub usage: Usage
+    pub usage: Option<Usage>,
+    /// How long the request took, not part of the API's response
+    #[serde(skip)]
+    pub timing: Option<Timing>,
+}
+
+impl Completion {
+    /// The usage reported by the API, zero if it wasn't reported
+    pub fn usage_or_zero(&self) -> Usage {
+        self.usage.clone().unwrap_or_default()
+    }
+
+    /// The first choice that finished for `reason`, e.g. `stop` to skip truncated choices
+    pub fn choice_by_finish_reason(&self, reason: &str) -> Option<&CompletionChoice> {
+        choices::by_finish_reason(&self.choices, reason)
+    }
+
+    /// The choice with the highest average logprob, requires `logprobs` to be set on the request
+    pub fn choice_by_logprob(&self) -> Option<&CompletionChoice> {
+        choices::by_average_logprob(&self.choices)
+    }
+
+    /// The choice with the highest score given by `score`
+    pub fn choice_by<F>(&self, score: F) -> Option<&CompletionChoice>
+    where
+        F: FnMut(&CompletionChoice) -> f64
+    {
+        choices::by_score(&self.choices, score)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionChunkChoice {
+    pub text: String,
+    pub index: i32,
+    pub logprobs: Option<Logprobs>,
+    pub finish_reason: Option<String>
 }
 
+/// A partial completion received while streaming
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionChunk {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub object: String,
+    #[serde(default, alias = "created_at")]
+    pub created: u64,
+    /// Older deployments and some gateways send this as `engine`
+    #[serde(alias = "engine")]
+    pub model: String,
+    #[serde(default)]
+    pub system_fingerprint: Option<String>,
+    pub choices: Vec<CompletionChunkChoice>,
+    /// Only sent on the last chunk, when requested with [`include_usage`](Parameters::include_usage)
+    pub usage: Option<Usage>,
+}
+
+/// Stream of partial completions returned by [`Parameters::stream`]
+pub type CompletionStream = EventStream<CompletionChunk>;
+
 /// Available parameters that can be sent with a completion request
 pub struct Parameters<'a> {
+    options: RequestOptions,
     model: CompletionModels,
-    query: Vec<(&'a str, Value)>
+    max_rounds: u32,
+    output_budget: Option<u32>,
+    resume_attempts: u32,
+    post_process: Option<PostProcess>,
+    query: Query<'a>
 }
 
 /// Function to create a completion request
@@ -51,15 +168,44 @@ pub struct Parameters<'a> {
 ///         println!("{:?}", completions);
 /// };
 /// ```
-pub fn build<'a>(model: CompletionModels) -> Parameters<'a> {
+pub fn build<'a, M: Into<CompletionModels>>(model: M) -> Parameters<'a> {
     Parameters {
-        model,
-        query: Vec::new()
+        options: RequestOptions::default(),
+        model: model.into(),
+        max_rounds: 0,
+        output_budget: None,
+        resume_attempts: 0,
+        post_process: None,
+        query: Query::new()
     }
 }
 
 impl<'a> Parameters<'a> {
 
+    /// Send the request using the given client instead of one loaded from the environment
+    pub fn client(mut self, client: &Client) -> Self {
+        self.options.client = Some(client.clone());
+        self
+    }
+
+    /// Mark the request as safe to retry even when the client's retry policy
+    /// is restricted to idempotent requests, see [`RetryScope::IdempotentOnly`](crate::retry::RetryScope::IdempotentOnly)
+    pub fn idempotent(mut self) -> Self {
+        self.options.idempotent = true;
+        self
+    }
+
+    /// Set a parameter this crate doesn't model yet, sent as is in the request body
+    ///
+    /// Replaces the value set for `key` by earlier setters, and is replaced by later ones.
+    /// `model` and `stream` are set when the request is sent, as are `max_tokens` and `prompt`
+    /// in the rounds of an [`output_budget`](Parameters::output_budget), so extra values for
+    /// them are ignored.
+    pub fn extra_param(mut self, key: &'a str, value: Value) -> Self {
+        self.query.push((key, value));
+        self
+    }
+
     /// The prompt to generate completions for, encoded as a string.
     ///
     /// [OpenAI Reference](https://beta.openai.com/docs/api-reference/completions/create#completions/create-prompt)
@@ -166,6 +312,15 @@ impl<'a> Parameters<'a> {
         self
     }
 
+    /// Sample deterministically, as far as possible, so repeated requests with the same seed
+    /// and parameters return the same result
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/completions/create#completions-create-seed)
+    pub fn seed(mut self, input: i64) -> Self {
+        self.query.push(("seed", json!(input)));
+        self
+    }
+
     /// A unique identifier representing your end-user, which can help
     /// OpenAI to monitor and detect abuse.
     ///
@@ -226,22 +381,603 @@ impl<'a> Parameters<'a> {
         self
     }
 
-    // TODO logit_bias
+    /// Modify the likelihood of specified tokens appearing in the completion, maps
+    /// token IDs to a bias value from -100 to 100.
+    ///
+    /// [OpenAI Reference](https://beta.openai.com/docs/api-reference/completions/create#completions/create-logit_bias)
+    pub fn logit_bias(mut self, input: &'a HashMap<u32, i32>) -> Self {
+        let bias = input.iter().map(|(token, bias)| (token.to_string(), json!(bias))).collect();
+        self.query.merge("logit_bias", bias);
+        self
+    }
+
+    /// Bias whole words instead of token IDs, each word is tokenized for the request's
+    /// model and its bias applied to every one of its tokens, see [`Tokenizer::logit_bias`].
+    ///
+    /// Fails with [`Error::UnknownTokenizer`] if no tokenizer is known for the model.
+    #[cfg(feature = "tokenizer")]
+    pub fn logit_bias_words(mut self, words: &[(&str, i32)]) -> Result<Self, Error> {
+        let tokenizer = Tokenizer::for_model(self.model.as_str())?;
+        let bias = tokenizer.logit_bias(words).into_iter().map(|(token, bias)| (token.to_string(), json!(bias))).collect();
+        self.query.merge("logit_bias", bias);
+        Ok(self)
+    }
+
+    /// Send the usage for the whole request on an extra last chunk that has no choices,
+    /// only valid when streaming.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/completions/create#completions-create-stream_options)
+    pub fn include_usage(mut self) -> Self {
+        self.query.push(("stream_options", json!({ "include_usage": true })));
+        self
+    }
+
+    /// Continue the completion up to `max_rounds` times when it's cut off by `max_tokens`
+    ///
+    /// While the first choice finishes with `length`, the prompt is sent again followed by
+    /// the text generated so far, and the continuation is appended to the choice. The
+    /// returned completion holds the combined text and the usage of every round. Only
+    /// applies to [`complete`](Parameters::complete) with a single prompt.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{completions, models};
+    ///
+    /// async {
+    ///     let completion = completions::build(models::CompletionModels::TEXT_DAVINCI_003)
+    ///         .prompt("Write a short story about cookies.")
+    ///         .max_tokens(256)
+    ///         .auto_continue(3)
+    ///         .complete()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     println!("{}", completion.choices[0].text);
+    /// };
+    /// ```
+    pub fn auto_continue(mut self, max_rounds: u32) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    /// Generate up to `total` tokens, continuing the answer over as many rounds as it takes
+    ///
+    /// Each round asks for the tokens left in the budget, at most `max_tokens` if set, so
+    /// the total can exceed what the model generates in a single response. Rounds stop once
+    /// the budget is spent or the answer finishes, and at most after the rounds set with
+    /// [`auto_continue`](Parameters::auto_continue) if set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{completions, models};
+    ///
+    /// async {
+    ///     let completion = completions::build(models::CompletionModels::TEXT_DAVINCI_003)
+    ///         .prompt("Write a 10,000 word story about cookies.")
+    ///         .max_tokens(2048)
+    ///         .output_budget(10_000)
+    ///         .complete()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     println!("{}", completion.choices[0].text);
+    /// };
+    /// ```
+    pub fn output_budget(mut self, total: u32) -> Self {
+        self.output_budget = Some(total);
+        self
+    }
+
+    /// Resume the generation up to `max_attempts` times when the stream drops part way
+    /// through, e.g. on a flaky network. Off unless set.
+    ///
+    /// When the connection drops mid-stream ([`Error::Interrupted`]), times out or the stream
+    /// idles out, the request is sent again with
+    /// the text streamed so far appended to the prompt, and streaming carries on from there.
+    /// With the `tokenizer` feature `max_tokens` is lowered by the tokens already received;
+    /// without it `max_tokens` is sent unchanged, so the whole completion can run longer. The resumed text is
+    /// generated afresh, so it may not continue exactly as the dropped stream would have,
+    /// and each resumed request is billed for the longer prompt. Streamed usage only covers
+    /// the last request. Only applies to [`complete_with`](Parameters::complete_with),
+    /// [`stream_to`](Parameters::stream_to) and [`write_to`](Parameters::write_to) with a
+    /// single prompt and choice.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{completions, models, stream::Flush};
+    ///
+    /// async {
+    ///     let completion = completions::build(models::CompletionModels::TEXT_DAVINCI_003)
+    ///         .prompt("Write a long story about cookies.")
+    ///         .max_tokens(2048)
+    ///         .resume_on_disconnect(3)
+    ///         .write_to(&mut tokio::io::sink(), Flush::OnFinish)
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     println!("{}", completion.choices[0].text);
+    /// };
+    /// ```
+    pub fn resume_on_disconnect(mut self, max_attempts: u32) -> Self {
+        self.resume_attempts = max_attempts;
+        self
+    }
+
+    /// Clean up the text of every choice once the completion has arrived, e.g. strip the
+    /// prompt sent back by [`echo`](Parameters::echo), see [`PostProcess`]
+    ///
+    /// Applies to the completions returned by `complete()`, `complete_with()`, `stream_to()`
+    /// and `write_to()`, not to the partial completions streamed on the way.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{choices::PostProcess, completions, models};
+    ///
+    /// async {
+    ///     let completion = completions::build(models::CompletionModels::TEXT_DAVINCI_003)
+    ///         .prompt("Ice cream or cookies?")
+    ///         .echo(&true)
+    ///         .post_process(PostProcess::new().strip_echo(true).trim_leading_whitespace(true))
+    ///         .complete()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     println!("{}", completion.choices[0].text);
+    /// };
+    /// ```
+    pub fn post_process(mut self, post_process: PostProcess) -> Self {
+        self.post_process = Some(post_process);
+        self
+    }
+
+    /// Applies the [`post_process`](Parameters::post_process) to a completion for the prompt
+    /// and stop sequences of the request as it is now
+    fn finisher(&self) -> impl Fn(&mut Completion) {
+        let post_process = self.post_process;
+        let prompt = self.query.get("prompt").and_then(Value::as_str).map(String::from);
+        let stops: Vec<String> = match self.query.get("stop") {
+            Some(Value::String(stop)) => vec![stop.clone()],
+            Some(Value::Array(stops)) => stops.iter().filter_map(Value::as_str).map(String::from).collect(),
+            _ => Vec::new(),
+        };
+        move |completion| {
+            if let Some(post_process) = &post_process {
+                for choice in completion.choices.iter_mut() {
+                    choice.text = post_process.apply(&choice.text, prompt.as_deref(), &stops);
+                }
+            }
+        }
+    }
 
     /// Complete the request and send
-    pub async fn complete(self) -> Result<Completion, StatusCode> {
+    pub async fn complete(mut self) -> Result<Completion, Error> {
+        let started = Instant::now();
+        let finish = self.finisher();
+        moderations::preflight(&self.options, self.query.get("prompt")).await?;
+
+        self.query.push(("model", json!(self.model.as_str())));
+
+        let max_rounds = match self.output_budget {
+            Some(_) if self.max_rounds == 0 => u32::MAX,
+            _ => self.max_rounds,
+        };
+        let per_round = self.query.get("max_tokens").and_then(Value::as_u64);
+        let mut budget = self.output_budget.map(|total| OutputBudget::new(total, per_round));
+        if let Some(budget) = budget.as_mut() {
+            self.query.push(("max_tokens", json!(budget.next_round())));
+        }
+
+        let response: Result<Completion, Error> = requester::completions(&self.options, &self.query).await;
+        let mut completion = response?;
+        if let Some(budget) = budget.as_mut() {
+            budget.spend(completion.usage.as_ref());
+        }
+
+        let prompt = match self.query.get("prompt") {
+            Some(Value::String(prompt)) => prompt.clone(),
+            _ => {
+                finish(&mut completion);
+                completion.timing = Some(Timing::since(started));
+                return Ok(completion);
+            }
+        };
+        let mut rounds = 0;
+        while rounds < max_rounds
+            && completion.choices.first().is_some_and(|c| c.finish_reason == "length")
+            && budget.as_ref().is_none_or(OutputBudget::has_remaining)
+        {
+            if let Some(budget) = budget.as_mut() {
+                self.query.push(("max_tokens", json!(budget.next_round())));
+            }
+            self.query.push(("prompt", json!(format!("{}{}", prompt, completion.choices[0].text))));
+            let next: Completion = requester::completions(&self.options, &self.query).await?;
+            if let Some(budget) = budget.as_mut() {
+                budget.spend(next.usage.as_ref());
+            }
+            merge_continuation(&mut completion, next);
+            rounds += 1;
+        }
+        finish(&mut completion);
+        completion.timing = Some(Timing::since(started));
+        Ok(completion)
+    }
+
+    /// Complete the request and stream back partial completions as they are generated
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_util::StreamExt;
+    /// use oai_rs::{completions, models};
+    ///
+    /// async {
+    ///     let mut stream = completions::build(models::CompletionModels::TEXT_DAVINCI_003)
+    ///         .prompt("Ice cream or cookies?")
+    ///         .max_tokens(32)
+    ///         .stream()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     while let Some(chunk) = stream.next().await {
+    ///         print!("{}", chunk.expect("Error Getting Chunk").choices[0].text);
+    ///     }
+    /// };
+    /// ```
+    pub async fn stream(mut self) -> Result<CompletionStream, Error> {
+        moderations::preflight(&self.options, self.query.get("prompt")).await?;
+        self.query.push(("model", json!(self.model.as_str())));
+        self.query.push(("stream", json!(true)));
+
+        requester::completions_stream(&self.options, &self.query).await
+    }
+
+    /// Start streaming, resuming the stream if set with [`resume_on_disconnect`](Parameters::resume_on_disconnect)
+    async fn resumable_stream(self) -> Result<ResumableStream<'a, CompletionChunk>, Error> {
+        let started = Instant::now();
+        let single_choice = self.query.get("n").and_then(Value::as_u64).unwrap_or(1) == 1;
+        let resume = match self.query.get("prompt") {
+            Some(prompt @ Value::String(_)) if single_choice && self.resume_attempts > 0 => Some((prompt.clone(), self.resume_attempts)),
+            _ => None,
+        };
+        let limit = self.query.get("max_tokens").and_then(Value::as_u64).map(|limit| ("max_tokens", limit));
+        let model = self.model.as_str().to_string();
+        let options = self.options.clone();
+        let mut query = self.query.clone();
+        query.push(("model", json!(self.model.as_str())));
+        query.push(("stream", json!(true)));
+        let inner = self.stream().await?;
+
+        Ok(ResumableStream::new(inner, options, query, resume, limit, &model, started))
+    }
+
+    /// Complete the request, calling `on_chunk` with each partial completion as it is
+    /// streamed back, and return the assembled completion once the stream ends
+    ///
+    /// The completion's `usage` is only set if the request asked for it with
+    /// [`include_usage`](Parameters::include_usage).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{completions, models};
+    ///
+    /// async {
+    ///     let completion = completions::build(models::CompletionModels::TEXT_DAVINCI_003)
+    ///         .prompt("Ice cream or cookies?")
+    ///         .max_tokens(32)
+    ///         .complete_with(|chunk| print!("{}", chunk.choices[0].text))
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     println!("{:?}", completion);
+    /// };
+    /// ```
+    pub async fn complete_with<F>(self, mut on_chunk: F) -> Result<Completion, Error>
+    where
+        F: FnMut(&CompletionChunk)
+    {
+        let finish = self.finisher();
+        let mut stream = self.resumable_stream().await?;
+        let mut completion: Option<Completion> = None;
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            on_chunk(&chunk);
 
-        let mut map = HashMap::new();
-        map.insert("model", json!(self.model.as_string()));
-        for (k, v) in self.query.into_iter() {
-            map.insert(k, v);
+            merge_chunk(&mut completion, chunk);
         }
 
-        let response: Result<Completion, StatusCode> = requester::completions(map).await;
+        let mut completion = completion.ok_or(Error::EmptyStream)?;
+        finish(&mut completion);
+        completion.timing = Some(stream.timing());
+        Ok(completion)
+    }
+
+    /// Complete the request, forwarding each partial completion into `sender` as it is
+    /// streamed back, and return the assembled completion once the stream ends
+    ///
+    /// Sending waits for room in the channel, so a slow receiver slows down reading the
+    /// stream. If the receiver is dropped the stream is cancelled and the completion
+    /// assembled so far is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{completions, models};
+    ///
+    /// async {
+    ///     let (sender, mut receiver) = tokio::sync::mpsc::channel(16);
+    ///     let printer = tokio::spawn(async move {
+    ///         while let Some(chunk) = receiver.recv().await {
+    ///             let chunk: completions::CompletionChunk = chunk;
+    ///             print!("{}", chunk.choices[0].text);
+    ///         }
+    ///     });
+    ///
+    ///     let completion = completions::build(models::CompletionModels::TEXT_DAVINCI_003)
+    ///         .prompt("Ice cream or cookies?")
+    ///         .max_tokens(32)
+    ///         .stream_to(sender)
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     printer.await.unwrap();
+    ///     println!("{:?}", completion);
+    /// };
+    /// ```
+    pub async fn stream_to(self, sender: Sender<CompletionChunk>) -> Result<Completion, Error> {
+        let finish = self.finisher();
+        let mut stream = self.resumable_stream().await?;
+        let mut completion: Option<Completion> = None;
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            if sender.send(chunk.clone()).await.is_err() {
+                stream.cancel();
+                break;
+            }
 
-        match response {
-            Ok(t) => Ok(t),
-            Err(e) => Err(e),
+            merge_chunk(&mut completion, chunk);
         }
+
+        let mut completion = completion.ok_or(Error::EmptyStream)?;
+        finish(&mut completion);
+        completion.timing = Some(stream.timing());
+        Ok(completion)
     }
-}
\ No newline at end of file
+
+    /// Complete the request, writing the text of each partial completion to `writer` as it
+    /// is streamed back, and return the assembled completion once the stream ends
+    ///
+    /// The text of every choice is written as it arrives, so this is intended for requests
+    /// generating a single choice. The writer is always flushed once the stream ends.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{completions, models, stream::Flush};
+    ///
+    /// async {
+    ///     let mut output: Vec<u8> = Vec::new();
+    ///     let completion = completions::build(models::CompletionModels::TEXT_DAVINCI_003)
+    ///         .prompt("Ice cream or cookies?")
+    ///         .max_tokens(32)
+    ///         .write_to(&mut output, Flush::OnFinish)
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     println!("{:?}", completion);
+    /// };
+    /// ```
+    pub async fn write_to<W>(self, writer: &mut W, flush: Flush) -> Result<Completion, Error>
+    where
+        W: AsyncWrite + Unpin
+    {
+        let finish = self.finisher();
+        let mut stream = self.resumable_stream().await?;
+        let mut completion: Option<Completion> = None;
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            for choice in chunk.choices.iter() {
+                writer.write_all(choice.text.as_bytes()).await.map_err(Error::Io)?;
+            }
+            if flush == Flush::EveryChunk {
+                writer.flush().await.map_err(Error::Io)?;
+            }
+            merge_chunk(&mut completion, chunk);
+        }
+        writer.flush().await.map_err(Error::Io)?;
+
+        let mut completion = completion.ok_or(Error::EmptyStream)?;
+        finish(&mut completion);
+        completion.timing = Some(stream.timing());
+        Ok(completion)
+    }
+}
+
+impl Resume for CompletionChunk {
+    const RESUMED: &'static str = "prompt";
+
+    fn push_text(&self, text: &mut String) {
+        for choice in self.choices.iter() {
+            text.push_str(&choice.text);
+        }
+    }
+
+    fn resumed(original: &Value, received: &str) -> Value {
+        json!(format!("{}{}", original.as_str().unwrap_or_default(), received))
+    }
+
+    fn stream<'f>(options: &'f RequestOptions, query: &'f Query<'_>) -> impl Future<Output = Result<CompletionStream, Error>> + Send + 'f {
+        requester::completions_stream(options, query)
+    }
+}
+
+/// Append the first choice of a continuation round onto the first choice of the completion
+fn merge_continuation(completion: &mut Completion, next: Completion) {
+    if let Some(usage) = &next.usage {
+        completion.usage.get_or_insert_with(Usage::default).add(usage);
+    }
+    let (Some(choice), Some(part)) = (completion.choices.first_mut(), next.choices.into_iter().next()) else {
+        return;
+    };
+    choice.text.push_str(&part.text);
+    if let Some(logprobs) = part.logprobs {
+        choice.logprobs.get_or_insert_with(Logprobs::default).extend(logprobs);
+    }
+    choice.finish_reason = part.finish_reason;
+}
+
+/// Append a streamed chunk onto the completion assembled so far
+fn merge_chunk(completion: &mut Option<Completion>, chunk: CompletionChunk) {
+    let completion = completion.get_or_insert_with(|| Completion {
+        id: chunk.id.clone(),
+        object: chunk.object.clone(),
+        created: chunk.created,
+        model: chunk.model.clone(),
+        system_fingerprint: chunk.system_fingerprint.clone(),
+        choices: Vec::new(),
+        usage: None,
+        timing: None,
+    });
+    if chunk.usage.is_some() {
+        completion.usage = chunk.usage;
+    }
+    for part in chunk.choices {
+        let choice = match completion.choices.iter_mut().position(|c| c.index == part.index) {
+            Some(i) => &mut completion.choices[i],
+            None => {
+                completion.choices.push(CompletionChoice {
+                    text: String::new(),
+                    index: part.index,
+                    logprobs: None,
+                    finish_reason: String::new()
+                });
+                completion.choices.last_mut().unwrap()
+            }
+        };
+        choice.text.push_str(&part.text);
+        if let Some(logprobs) = part.logprobs {
+            choice.logprobs.get_or_insert_with(Logprobs::default).extend(logprobs);
+        }
+        if let Some(finish_reason) = part.finish_reason {
+            choice.finish_reason = finish_reason;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::build;
+    use crate::error::Error;
+    use crate::models::CompletionModels;
+    use crate::testing::{assert_snapshot, golden, CannedResponse, Matcher, TestClient};
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn parameters_use_wire_keys() {
+        let test = TestClient::new();
+        let bias = HashMap::from([(50256, -100)]);
+        let _ = build(CompletionModels::TEXT_DAVINCI_003)
+            .client(&test.client())
+            .prompt("Say this is a test")
+            .suffix("done")
+            .temperature(&0.5)
+            .top_p(&0.25)
+            .n(&2)
+            .logprobs(&3)
+            .echo(&true)
+            .stop("\n")
+            .seed(7)
+            .user("user-1")
+            .max_tokens(16)
+            .presence_penalty(&0.5)
+            .frequency_penalty(&-0.5)
+            .best_of(&4)
+            .logit_bias(&bias)
+            .include_usage()
+            .complete()
+            .await;
+
+        assert_snapshot(golden("completions"), &test.last_request());
+    }
+
+    fn chunk(text: &str, finish_reason: Option<&str>) -> Value {
+        json!({
+            "id": "cmpl-1",
+            "model": "text-davinci-003",
+            "choices": [{ "text": text, "index": 0, "logprobs": null, "finish_reason": finish_reason }],
+            "usage": null
+        })
+    }
+
+    #[tokio::test]
+    async fn dropped_stream_resumes_from_received_text() {
+        let test = TestClient::new()
+            .on(
+                Matcher::post("completions").body("/prompt", json!("Ice cream or cookies? Cookies")),
+                CannedResponse::stream(&[chunk(", obviously.", Some("stop"))]),
+            )
+            .on(
+                Matcher::post("completions"),
+                CannedResponse::interrupted_stream(&[chunk(" Cook", None), chunk("ies", None)]),
+            );
+
+        let mut texts = Vec::new();
+        let completion = build(CompletionModels::TEXT_DAVINCI_003)
+            .client(&test.client())
+            .prompt("Ice cream or cookies?")
+            .max_tokens(16)
+            .resume_on_disconnect(1)
+            .complete_with(|chunk| texts.push(chunk.choices[0].text.clone()))
+            .await
+            .unwrap();
+
+        assert_eq!(texts, [" Cook", "ies", ", obviously."]);
+        assert_eq!(completion.choices[0].text, " Cookies, obviously.");
+        assert_eq!(completion.choices[0].finish_reason, "stop");
+        let requests = test.requests();
+        assert_eq!(requests.len(), 2);
+        let resumed = requests[1].body.as_ref().unwrap();
+        assert_eq!(resumed["prompt"], json!("Ice cream or cookies? Cookies"));
+        #[cfg(feature = "tokenizer")]
+        let received = crate::tokenizer::Tokenizer::for_model("text-davinci-003").unwrap().count(" Cookies") as u64;
+        #[cfg(not(feature = "tokenizer"))]
+        let received = 0;
+        assert_eq!(resumed["max_tokens"], json!(16 - received));
+    }
+
+    #[tokio::test]
+    async fn dropped_stream_fails_without_resume() {
+        let test = TestClient::new()
+            .on(Matcher::post("completions"), CannedResponse::interrupted_stream(&[chunk(" Cook", None)]));
+
+        let result = build(CompletionModels::TEXT_DAVINCI_003)
+            .client(&test.client())
+            .prompt("Ice cream or cookies?")
+            .complete_with(|_| {})
+            .await;
+
+        assert!(matches!(result, Err(Error::Interrupted(_))), "{:?}", result);
+        assert_eq!(test.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn stream_without_chunks_is_empty() {
+        let test = TestClient::new().on(Matcher::post("completions"), CannedResponse::stream::<Value>(&[]));
+
+        let result = build(CompletionModels::TEXT_DAVINCI_003)
+            .client(&test.client())
+            .prompt("Ice cream or cookies?")
+            .complete_with(|_| {})
+            .await;
+
+        assert!(matches!(result, Err(Error::EmptyStream)), "{:?}", result);
+    }
+}
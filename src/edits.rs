@@ -1,10 +1,12 @@
+use crate::client::{Client, Timing};
+use crate::error::Error;
 use crate::models::EditModels;
-use crate::requester;
+use crate::moderations;
+use crate::requester::{self, Query, RequestOptions};
 use crate::usage::Usage;
-use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::time::Instant;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EditChoice {
@@ -14,17 +16,30 @@ pub struct EditChoice {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Edit {
+    #[serde(default)]
     pub object: String,
+    #[serde(default, alias = "created_at")]
     pub created: u64,
     pub choices: Vec<EditChoice>,
-    pub usage: Usage,
+    pub usage: Option<Usage>,
+    /// How long the request took, not part of the API's response
+    #[serde(skip)]
+    pub timing: Option<Timing>,
+}
+
+impl Edit {
+    /// The usage reported by the API, zero if it wasn't reported
+    pub fn usage_or_zero(&self) -> Usage {
+        self.usage.clone().unwrap_or_default()
+    }
 }
 
 /// Available parameters that can be sent with an edit request
 pub struct Parameters<'a> {
+    options: RequestOptions,
     model: EditModels,
     instruction: String,
-    query: Vec<(&'a str, Value)>,
+    query: Query<'a>,
 }
 
 /// Function to create a edit request
@@ -47,15 +62,39 @@ pub struct Parameters<'a> {
 ///         println!("{:?}", edits);
 /// };
 /// ```
-pub fn build<'a>(model: EditModels, instruction: String) -> Parameters<'a> {
+pub fn build<'a, M: Into<EditModels>>(model: M, instruction: String) -> Parameters<'a> {
     Parameters {
-        model,
+        options: RequestOptions::default(),
+        model: model.into(),
         instruction,
-        query: Vec::new(),
+        query: Query::new(),
     }
 }
 
 impl<'a> Parameters<'a> {
+    /// Send the request using the given client instead of one loaded from the environment
+    pub fn client(mut self, client: &Client) -> Self {
+        self.options.client = Some(client.clone());
+        self
+    }
+
+    /// Mark the request as safe to retry even when the client's retry policy
+    /// is restricted to idempotent requests, see [`RetryScope::IdempotentOnly`](crate::retry::RetryScope::IdempotentOnly)
+    pub fn idempotent(mut self) -> Self {
+        self.options.idempotent = true;
+        self
+    }
+
+    /// Set a parameter this crate doesn't model yet, sent as is in the request body
+    ///
+    /// Replaces the value set for `key` by earlier setters, and is replaced by later ones.
+    /// `model` and `instruction` are set when the request is sent, so extra values for them are
+    /// ignored.
+    pub fn extra_param(mut self, key: &'a str, value: Value) -> Self {
+        self.query.push((key, value));
+        self
+    }
+
     /// The text to generate edits for, encoded as a string.
     ///
     /// [OpenAI Reference](https://beta.openai.com/docs/api-reference/edits/create#edits/create-input)
@@ -99,20 +138,53 @@ impl<'a> Parameters<'a> {
         self
     }
 
+    /// A unique identifier representing your end-user, which can help
+    /// OpenAI to monitor and detect abuse.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/edits/create#edits-create-user)
+    pub fn user(mut self, input: &'a str) -> Self {
+        self.query.push(("user", json!(input)));
+        self
+    }
+
     /// Complete the request and send
-    pub async fn edit(self) -> Result<Edit, StatusCode> {
-        let mut map = HashMap::new();
-        map.insert("model", json!(self.model.as_string()));
-        map.insert("instruction", json!(self.instruction));
-        for (k, v) in self.query.into_iter() {
-            map.insert(k, v);
-        }
+    pub async fn edit(mut self) -> Result<Edit, Error> {
+        let started = Instant::now();
+        moderations::preflight(&self.options, self.query.get("input")).await?;
+        self.query.push(("model", json!(self.model.as_str())));
+        self.query.push(("instruction", json!(self.instruction)));
 
-        let response: Result<Edit, StatusCode> = requester::edits(map).await;
+        let response: Result<Edit, Error> = requester::edits(&self.options, &self.query).await;
 
         match response {
-            Ok(t) => Ok(t),
+            Ok(mut t) => {
+                t.timing = Some(Timing::since(started));
+                Ok(t)
+            }
             Err(e) => Err(e),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::build;
+    use crate::models::EditModels;
+    use crate::testing::{assert_snapshot, golden, TestClient};
+
+    #[tokio::test]
+    async fn parameters_use_wire_keys() {
+        let test = TestClient::new();
+        let _ = build(EditModels::TEXT_DAVINCI_EDIT_001, "Fix the spelling mistakes".to_string())
+            .client(&test.client())
+            .input("What day of the wek is it?")
+            .n(&2)
+            .temperature(&0.5)
+            .top_p(&0.25)
+            .user("user-1")
+            .edit()
+            .await;
+
+        assert_snapshot(golden("edits"), &test.last_request());
+    }
+}
@@ -0,0 +1,91 @@
+use std::cmp::Ordering;
+
+/// An item returned from a store query along with its similarity to the query embedding
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredItem<'a, T> {
+    pub score: f32,
+    pub item: &'a T,
+}
+
+/// Somewhere embeddings can be stored alongside an item and searched by similarity
+///
+/// [`InMemoryStore`] is a simple implementation for prototyping, the trait can be
+/// implemented on top of a vector database once the data outgrows it.
+pub trait EmbeddingStore<T> {
+    /// Store an item with its embedding
+    fn add(&mut self, embedding: Vec<f32>, item: T);
+
+    /// Return up to `top_k` items most similar to `embedding`, most similar first
+    fn query(&self, embedding: &[f32], top_k: usize) -> Vec<ScoredItem<'_, T>>;
+}
+
+/// Stores embeddings in memory and searches them by comparing against every entry
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::embedding_store::{EmbeddingStore, InMemoryStore};
+///
+/// let mut store = InMemoryStore::new();
+/// store.add(vec![1.0, 0.0], "cookies");
+/// store.add(vec![0.0, 1.0], "ice cream");
+///
+/// let results = store.query(&[0.9, 0.1], 1);
+/// assert_eq!(*results[0].item, "cookies");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryStore<T> {
+    entries: Vec<(Vec<f32>, T)>,
+}
+
+impl<T> InMemoryStore<T> {
+    pub fn new() -> Self {
+        InMemoryStore { entries: Vec::new() }
+    }
+
+    /// The number of items stored
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the store is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<T> EmbeddingStore<T> for InMemoryStore<T> {
+    fn add(&mut self, embedding: Vec<f32>, item: T) {
+        self.entries.push((embedding, item));
+    }
+
+    fn query(&self, embedding: &[f32], top_k: usize) -> Vec<ScoredItem<'_, T>> {
+        let mut scored: Vec<ScoredItem<'_, T>> = self.entries.iter()
+            .map(|(e, item)| ScoredItem {
+                score: cosine_similarity(embedding, e),
+                item,
+            })
+            .collect();
+        scored.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+        scored.truncate(top_k);
+        scored
+    }
+}
+
+/// Cosine similarity of two embeddings, `0.0` if either has no magnitude
+///
+/// Embeddings from the API are normalized so this is equivalent to their dot product.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let mut dot = 0.0;
+    let mut norm_a = 0.0;
+    let mut norm_b = 0.0;
+    for (x, y) in a.iter().zip(b.iter()) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
@@ -0,0 +1,208 @@
+use crate::client::{Client, Timing};
+use crate::error::Error;
+use crate::models::EmbeddingModels;
+use crate::requester::{self, Query, RequestOptions};
+use crate::usage::Usage;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Instant;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Embedding {
+    #[serde(default)]
+    pub object: String,
+    pub embedding: Vec<f32>,
+    pub index: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Embeddings {
+    #[serde(default)]
+    pub object: String,
+    pub data: Vec<Embedding>,
+    #[serde(alias = "engine")]
+    pub model: String,
+    pub usage: Option<Usage>,
+    /// How long the request took, not part of the API's response
+    #[serde(skip)]
+    pub timing: Option<Timing>,
+}
+
+impl Embeddings {
+    /// The usage reported by the API, zero if it wasn't reported
+    pub fn usage_or_zero(&self) -> Usage {
+        self.usage.clone().unwrap_or_default()
+    }
+
+    /// The embeddings as a matrix with a row for each input, in input order
+    ///
+    /// Fails if the embeddings don't all have the same number of dimensions.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "ndarray")] {
+    /// use oai_rs::{embeddings, models};
+    ///
+    /// async {
+    ///     let inputs = vec!["Ice cream", "Cookies"];
+    ///     let embeddings = embeddings::build(models::EmbeddingModels::TEXT_EMBEDDING_3_SMALL)
+    ///         .inputs(&inputs)
+    ///         .embed()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     let matrix = embeddings.to_array2().unwrap();
+    ///     let similarities = matrix.dot(&matrix.t());
+    ///     println!("{:?}", similarities);
+    /// };
+    /// # }
+    /// ```
+    #[cfg(feature = "ndarray")]
+    pub fn to_array2(&self) -> Result<ndarray::Array2<f32>, ndarray::ShapeError> {
+        let mut rows: Vec<&Embedding> = self.data.iter().collect();
+        rows.sort_by_key(|e| e.index);
+        let dimensions = rows.first().map_or(0, |e| e.embedding.len());
+        let values: Vec<f32> = rows.iter().flat_map(|e| e.embedding.iter().copied()).collect();
+        ndarray::Array2::from_shape_vec((rows.len(), dimensions), values)
+    }
+}
+
+/// Available parameters that can be sent with an embeddings request
+pub struct Parameters<'a> {
+    options: RequestOptions,
+    model: EmbeddingModels,
+    query: Query<'a>,
+}
+
+/// Function to create an embeddings request
+///
+/// Call it using [`build`] and add valid [`Parameters`] to the request to build an
+/// embeddings request and close with `embed()`.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{embeddings, models};
+///
+/// async {
+///     let embeddings = embeddings::build(models::EmbeddingModels::TEXT_EMBEDDING_ADA_002)
+///         .input("Ice cream or cookies?")
+///         .embed()
+///         .await
+///         .expect("Error Getting Response");
+///
+///     println!("{:?}", embeddings);
+/// };
+/// ```
+pub fn build<'a, M: Into<EmbeddingModels>>(model: M) -> Parameters<'a> {
+    Parameters {
+        options: RequestOptions::default(),
+        model: model.into(),
+        query: Query::new(),
+    }
+}
+
+impl<'a> Parameters<'a> {
+    /// Send the request using the given client instead of one loaded from the environment
+    pub fn client(mut self, client: &Client) -> Self {
+        self.options.client = Some(client.clone());
+        self
+    }
+
+    /// Set a parameter this crate doesn't model yet, sent as is in the request body
+    ///
+    /// Replaces the value set for `key` by earlier setters, and is replaced by later ones.
+    /// `model` is set when the request is sent, so an extra value for it is ignored.
+    pub fn extra_param(mut self, key: &'a str, value: Value) -> Self {
+        self.query.push((key, value));
+        self
+    }
+
+    /// The text to embed, encoded as a string.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/embeddings/create#embeddings-create-input)
+    pub fn input(mut self, input: &'a str) -> Self {
+        self.query.push(("input", json!(input)));
+        self
+    }
+
+    /// The texts to embed, each is embedded separately and returned in the same order.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/embeddings/create#embeddings-create-input)
+    pub fn inputs(mut self, input: &'a Vec<&str>) -> Self {
+        self.query.push(("input", json!(input)));
+        self
+    }
+
+    /// The number of dimensions the embeddings should have, only supported by
+    /// `text-embedding-3` and later models
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/embeddings/create#embeddings-create-dimensions)
+    pub fn dimensions(mut self, input: u32) -> Self {
+        self.query.push(("dimensions", json!(input)));
+        self
+    }
+
+    /// A unique identifier representing your end-user, which can help
+    /// OpenAI to monitor and detect abuse.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/embeddings/create#embeddings-create-user)
+    pub fn user(mut self, input: &'a str) -> Self {
+        self.query.push(("user", json!(input)));
+        self
+    }
+
+    /// Complete the request and send
+    pub async fn embed(mut self) -> Result<Embeddings, Error> {
+        let started = Instant::now();
+        self.query.push(("model", json!(self.model.as_str())));
+
+        let response: Result<Embeddings, Error> = requester::embeddings(&self.options, &self.query).await;
+
+        match response {
+            Ok(mut t) => {
+                t.timing = Some(Timing::since(started));
+                Ok(t)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::build;
+    use crate::models::EmbeddingModels;
+    use crate::testing::{assert_snapshot, golden, TestClient};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn parameters_use_wire_keys() {
+        let test = TestClient::new();
+        let _ = build(EmbeddingModels::TEXT_EMBEDDING_3_SMALL)
+            .client(&test.client())
+            .input("The food was delicious")
+            .dimensions(256)
+            .user("user-1")
+            .embed()
+            .await;
+
+        assert_snapshot(golden("embeddings"), &test.last_request());
+    }
+
+    #[tokio::test]
+    async fn client_defaults_are_not_sent() {
+        let test = TestClient::new();
+        let client = test.client().default_param("temperature", json!(0.2));
+        let _ = build(EmbeddingModels::TEXT_EMBEDDING_3_SMALL)
+            .client(&client)
+            .input("The food was delicious")
+            .embed()
+            .await;
+
+        let body = test.last_request().unwrap().body.unwrap();
+        assert_eq!(body.get("temperature"), None);
+        assert_eq!(body["model"], json!("text-embedding-3-small"));
+    }
+}
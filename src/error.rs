@@ -0,0 +1,205 @@
+use crate::transport::TransportError;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::fmt;
+
+/// The most of a response body kept in [`Error::Deserialize`]
+const MAX_BODY_LEN: usize = 1024;
+
+/// Errors that can be returned by any request made through this crate
+#[derive(Debug)]
+pub enum Error {
+    /// The `OPENAI_API_KEY` environment variable was not set
+    MissingApiKey,
+    /// The request failed with the given status code
+    Status(StatusCode),
+    /// The request timed out before a response arrived
+    Timeout,
+    /// No connection could be made to the API
+    Connect,
+    /// The request could not be sent for another reason than a timeout or failing to
+    /// connect, e.g. too many redirects, holds the reason
+    Request(String),
+    /// The API responded with an error status, along with the error it described
+    Api(ApiError),
+    /// The client's circuit breaker is open, the request was not sent
+    CircuitOpen,
+    /// No data was received on a stream for longer than the client's stream idle timeout
+    StreamIdleTimeout,
+    /// The connection failed while the response body was being received, e.g. a stream
+    /// dropped part way through, holds the reason
+    Interrupted(String),
+    /// A stream ended without sending a single chunk, so there is nothing to assemble
+    EmptyStream,
+    /// The response body could not be deserialized into the expected type
+    Deserialize {
+        reason: String,
+        /// The response body, truncated to its first 1024 bytes
+        body: String,
+    },
+    /// The client's budget for the current window has been spent, the request was not sent
+    BudgetExceeded,
+    /// Writing streamed output failed
+    Io(std::io::Error),
+    /// No tokenizer is known for the given model
+    UnknownTokenizer(String),
+    /// The input was flagged by the moderations endpoint and was not sent, holds the flagged categories
+    ContentFlagged(Vec<String>),
+    /// The arguments of a tool call could not be deserialized, holds the reason
+    ToolArguments(String),
+    /// A parameter is outside of the range accepted by the API, the request was not sent
+    InvalidParameter(String),
+    /// The model's answer could not be parsed into the requested type
+    UnparsableOutput {
+        /// The answer as returned by the model
+        text: String,
+        reason: String,
+    },
+    /// An embeddings response held fewer embeddings than inputs were sent
+    MissingEmbeddings {
+        expected: usize,
+        received: usize,
+    },
+    /// The uploaded parts don't add up to the expected size or checksum, the upload was not completed
+    UploadMismatch(String),
+    /// Image data could not be decoded or encoded, holds the reason
+    InvalidImage(String),
+    /// The global client was already configured or loaded by an earlier request, see
+    /// [`configure`](crate::configure)
+    AlreadyConfigured,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingApiKey => write!(f, "OPENAI_API_KEY is not set"),
+            Error::Status(status) => write!(f, "request failed with status {}", status),
+            Error::Timeout => write!(f, "request timed out"),
+            Error::Connect => write!(f, "could not connect to the API"),
+            Error::Request(reason) => write!(f, "request could not be sent: {}", reason),
+            Error::Api(error) => write!(f, "request failed with status {}: {}", error.status, error.message),
+            Error::CircuitOpen => write!(f, "circuit breaker is open, request not sent"),
+            Error::StreamIdleTimeout => write!(f, "no data received on stream before the idle timeout"),
+            Error::Interrupted(reason) => write!(f, "connection failed while receiving the response: {}", reason),
+            Error::EmptyStream => write!(f, "stream ended without sending any chunks"),
+            Error::Deserialize { reason, body } => write!(f, "failed to deserialize response: {} in {}", reason, body),
+            Error::BudgetExceeded => write!(f, "budget exceeded, request not sent"),
+            Error::Io(e) => write!(f, "failed to write streamed output: {}", e),
+            Error::UnknownTokenizer(model) => write!(f, "no tokenizer found for model {}", model),
+            Error::ContentFlagged(categories) => write!(f, "input flagged by moderation: {}", categories.join(", ")),
+            Error::ToolArguments(reason) => write!(f, "invalid tool call arguments: {}", reason),
+            Error::InvalidParameter(reason) => write!(f, "invalid parameter: {}", reason),
+            Error::UnparsableOutput { reason, .. } => write!(f, "could not parse model output: {}", reason),
+            Error::MissingEmbeddings { expected, received } => write!(f, "expected {} embeddings, the response held {}", expected, received),
+            Error::UploadMismatch(reason) => write!(f, "upload verification failed: {}", reason),
+            Error::InvalidImage(reason) => write!(f, "invalid image: {}", reason),
+            Error::AlreadyConfigured => write!(f, "global client already configured"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// An error returned by the API, e.g. explaining which parameter of a `400 Bad Request` was wrong
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+    /// The kind of error, e.g. `invalid_request_error`
+    pub kind: Option<String>,
+    /// The parameter that caused the error, if any
+    pub param: Option<String>,
+    /// A machine readable error code, e.g. `context_length_exceeded`
+    pub code: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: ErrorDetails,
+}
+
+#[derive(Deserialize)]
+struct ErrorDetails {
+    message: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    param: Option<String>,
+    code: Option<serde_json::Value>,
+}
+
+impl ApiError {
+    /// Parse the error from a response body, keeping the body as the message if it isn't
+    /// in the API's error format
+    pub(crate) fn from_body(status: StatusCode, body: &str) -> Self {
+        match serde_json::from_str::<ErrorBody>(body) {
+            Ok(ErrorBody { error }) => ApiError {
+                status,
+                message: error.message,
+                kind: error.kind,
+                param: error.param,
+                code: error.code.map(|code| match code {
+                    serde_json::Value::String(code) => code,
+                    code => code.to_string(),
+                }),
+            },
+            Err(_) => ApiError {
+                status,
+                message: body.trim().to_string(),
+                kind: None,
+                param: None,
+                code: None,
+            },
+        }
+    }
+}
+
+impl Error {
+    /// The HTTP status of the failed request, if it got a response
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::Status(status) => Some(*status),
+            Error::Api(error) => Some(error.status),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn from_reqwest(error: reqwest::Error) -> Self {
+        match error.status() {
+            Some(status) => Error::Status(status),
+            None if error.is_timeout() => Error::Timeout,
+            None if error.is_connect() => Error::Connect,
+            None if error.is_body() || error.is_decode() => Error::Interrupted(error.to_string()),
+            None if error.is_builder() => Error::InvalidParameter(error.to_string()),
+            None => Error::Request(error.to_string()),
+        }
+    }
+
+    pub(crate) fn from_transport(error: TransportError) -> Self {
+        match error {
+            TransportError::Timeout => Error::Timeout,
+            TransportError::Connect => Error::Connect,
+            TransportError::Http(error) => Error::from_reqwest(error),
+        }
+    }
+
+    pub(crate) fn deserialize(error: serde_json::Error, body: &str) -> Self {
+        let mut end = body.len().min(MAX_BODY_LEN);
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        let mut truncated = body[..end].to_string();
+        if end < body.len() {
+            truncated.push_str("...");
+        }
+        Error::Deserialize {
+            reason: error.to_string(),
+            body: truncated,
+        }
+    }
+}
+
+impl From<StatusCode> for Error {
+    fn from(status: StatusCode) -> Self {
+        Error::Status(status)
+    }
+}
@@ -0,0 +1,166 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::requester::{self, RequestOptions};
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_util::stream;
+use reqwest::multipart::{Form, Part};
+use reqwest::Body;
+use serde::{Deserialize, Serialize};
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// How much of a file is read into memory at once while it's uploaded
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The largest file the API accepts, 512 MB
+pub const MAX_FILE_SIZE: u64 = 512 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileObject {
+    pub id: String,
+    pub object: String,
+    pub bytes: u64,
+    pub created_at: u64,
+    pub filename: String,
+    pub purpose: String,
+}
+
+/// Upload the file at `path` for `purpose`, e.g. `fine-tune` or `batch`
+///
+/// The file is streamed from disk as it's sent rather than read into memory, so large
+/// fine-tuning and batch input files can be uploaded. If the request is retried the file is
+/// read again from the start. Files larger than [`MAX_FILE_SIZE`] are rejected without
+/// being sent, use [`uploads`](crate::uploads) for those.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::files;
+///
+/// async {
+///     let file = files::upload("./training.jsonl", "fine-tune").await.expect("Error Getting Response");
+///     println!("{}", file.id);
+/// };
+/// ```
+pub async fn upload<P: AsRef<Path>>(path: P, purpose: &str) -> Result<FileObject, Error> {
+    upload_with(None, path.as_ref(), purpose).await
+}
+
+pub(crate) async fn upload_with(client: Option<&Client>, path: &Path, purpose: &str) -> Result<FileObject, Error> {
+    let len = tokio::fs::metadata(path).await.map_err(Error::Io)?.len();
+    if len > MAX_FILE_SIZE {
+        return Err(Error::InvalidParameter(format!("files must be at most {} bytes, {} has {}", MAX_FILE_SIZE, path.display(), len)));
+    }
+    let filename = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let mime = mime_type(path);
+    let form = || {
+        let mut part = Part::stream_with_length(Body::wrap_stream(read_range(path.to_path_buf(), 0, len)), len)
+            .file_name(filename.clone());
+        if let Some(mime) = mime {
+            part = part.mime_str(mime).expect("known MIME types are valid");
+        }
+        Form::new()
+            .text("purpose", purpose.to_string())
+            .part("file", part)
+    };
+
+    requester::files(&options(client), form).await
+}
+
+/// Download the content of the file with the given identifier
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::files;
+///
+/// async {
+///     let content = files::content("file-abc123").await.expect("Error Getting Response");
+///     println!("{} bytes", content.len());
+/// };
+/// ```
+pub async fn content(file_id: &str) -> Result<Bytes, Error> {
+    content_with(None, file_id).await
+}
+
+pub(crate) async fn content_with(client: Option<&Client>, file_id: &str) -> Result<Bytes, Error> {
+    requester::file_content(&options(client), file_id).await
+}
+
+/// Download the content of the file with the given identifier into `path`
+pub async fn download<P: AsRef<Path>>(file_id: &str, path: P) -> Result<(), Error> {
+    download_with(None, file_id, path.as_ref()).await
+}
+
+pub(crate) async fn download_with(client: Option<&Client>, file_id: &str, path: &Path) -> Result<(), Error> {
+    let content = content_with(client, file_id).await?;
+    tokio::fs::write(path, &content).await.map_err(Error::Io)
+}
+
+/// The MIME type of a file, inferred from its extension
+pub(crate) fn mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let mime = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "wav" => "audio/wav",
+        "mp3" => "audio/mpeg",
+        "jsonl" => "application/jsonl",
+        "json" => "application/json",
+        "txt" => "text/plain",
+        "pdf" => "application/pdf",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+/// Read the file at `path`, failing without reading it if it's larger than `max_len` bytes
+pub(crate) async fn read_limited(path: &Path, max_len: u64) -> Result<Bytes, Error> {
+    let len = tokio::fs::metadata(path).await.map_err(Error::Io)?.len();
+    if len > max_len {
+        return Err(Error::InvalidParameter(format!("{} has {} bytes, at most {} are allowed", path.display(), len, max_len)));
+    }
+    tokio::fs::read(path).await.map(Bytes::from).map_err(Error::Io)
+}
+
+/// Stream `len` bytes of the file at `path` starting at `offset`, opening the file once
+/// the stream is first polled
+pub(crate) fn read_range(path: PathBuf, offset: u64, len: u64) -> impl Stream<Item = std::io::Result<Bytes>> + Send + 'static {
+    enum State {
+        Unopened(PathBuf),
+        Open(File, u64),
+    }
+
+    stream::try_unfold(State::Unopened(path), move |state| async move {
+        let (mut file, remaining) = match state {
+            State::Unopened(path) => {
+                let mut file = File::open(path).await?;
+                file.seek(SeekFrom::Start(offset)).await?;
+                (file, len)
+            }
+            State::Open(file, remaining) => (file, remaining),
+        };
+        if remaining == 0 {
+            return Ok(None);
+        }
+        let mut buffer = vec![0; READ_CHUNK_SIZE.min(remaining as usize)];
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        buffer.truncate(read);
+        Ok(Some((Bytes::from(buffer), State::Open(file, remaining - read as u64))))
+    })
+}
+
+fn options(client: Option<&Client>) -> RequestOptions {
+    RequestOptions {
+        client: client.cloned(),
+        ..RequestOptions::default()
+    }
+}
@@ -0,0 +1,130 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::requester::{self, RequestOptions};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The state of a fine-tuning job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    ValidatingFiles,
+    Queued,
+    Running,
+    /// Paused with [`pause`], continues from its last checkpoint once resumed
+    Paused,
+    Succeeded,
+    Failed,
+    Cancelled,
+    /// A status this version of the crate doesn't know
+    #[serde(other)]
+    Unknown,
+}
+
+impl JobStatus {
+    /// Whether the job has stopped for good, successfully or not
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+/// A job fine-tuning a model
+///
+/// [OpenAI Reference](https://platform.openai.com/docs/api-reference/fine-tuning/object)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FineTuningJob {
+    pub id: String,
+    #[serde(default)]
+    pub object: String,
+    pub created_at: u64,
+    #[serde(default)]
+    pub finished_at: Option<u64>,
+    /// The base model being fine-tuned
+    pub model: String,
+    /// The fine-tuned model, once the job succeeded
+    #[serde(default)]
+    pub fine_tuned_model: Option<String>,
+    pub status: JobStatus,
+    pub training_file: String,
+    #[serde(default)]
+    pub validation_file: Option<String>,
+    #[serde(default)]
+    pub result_files: Vec<String>,
+    #[serde(default)]
+    pub trained_tokens: Option<u64>,
+    /// Unix timestamp the job is expected to finish at
+    #[serde(default)]
+    pub estimated_finish: Option<u64>,
+    #[serde(default)]
+    pub hyperparameters: Option<Value>,
+    /// Why the job failed
+    #[serde(default)]
+    pub error: Option<Value>,
+}
+
+/// Get a fine-tuning job, e.g. to poll its status
+///
+/// [OpenAI Reference](https://platform.openai.com/docs/api-reference/fine-tuning/retrieve)
+pub async fn get(job_id: &str) -> Result<FineTuningJob, Error> {
+    get_with(None, job_id).await
+}
+
+pub(crate) async fn get_with(client: Option<&Client>, job_id: &str) -> Result<FineTuningJob, Error> {
+    let response: Result<FineTuningJob, Error> = requester::fine_tuning_job(&options(client), Method::GET, job_id, None).await;
+
+    match response {
+        Ok(t) => Ok(t),
+        Err(e) => Err(e),
+    }
+}
+
+/// Pause a running fine-tuning job, keeping its progress so far
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::fine_tuning::{self, JobStatus};
+///
+/// async {
+///     let job = fine_tuning::pause("ftjob-abc123").await.expect("Error Pausing Job");
+///     assert_eq!(job.status, JobStatus::Paused);
+///
+///     // Later on
+///     let job = fine_tuning::resume("ftjob-abc123").await.expect("Error Resuming Job");
+///     println!("{:?}", job.status);
+/// };
+/// ```
+pub async fn pause(job_id: &str) -> Result<FineTuningJob, Error> {
+    pause_with(None, job_id).await
+}
+
+pub(crate) async fn pause_with(client: Option<&Client>, job_id: &str) -> Result<FineTuningJob, Error> {
+    let response: Result<FineTuningJob, Error> = requester::fine_tuning_job(&options(client), Method::POST, job_id, Some("pause")).await;
+
+    match response {
+        Ok(t) => Ok(t),
+        Err(e) => Err(e),
+    }
+}
+
+/// Resume a paused fine-tuning job from its last checkpoint, see [`pause`]
+pub async fn resume(job_id: &str) -> Result<FineTuningJob, Error> {
+    resume_with(None, job_id).await
+}
+
+pub(crate) async fn resume_with(client: Option<&Client>, job_id: &str) -> Result<FineTuningJob, Error> {
+    let response: Result<FineTuningJob, Error> = requester::fine_tuning_job(&options(client), Method::POST, job_id, Some("resume")).await;
+
+    match response {
+        Ok(t) => Ok(t),
+        Err(e) => Err(e),
+    }
+}
+
+fn options(client: Option<&Client>) -> RequestOptions {
+    RequestOptions {
+        client: client.cloned(),
+        ..RequestOptions::default()
+    }
+}
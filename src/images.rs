@@ -1,38 +1,223 @@
-use crate::requester;
+use crate::client::{self, Client, Timing};
+use crate::error::Error;
+use crate::files;
+use crate::requester::{self, Query, RequestOptions};
+use crate::stream::EventStream;
+use crate::usage::Usage;
+use base64::Engine;
+use bytes::Bytes;
+use futures_util::{stream, StreamExt, TryStreamExt};
 use reqwest::StatusCode;
 use serde::{Serialize, Deserialize};
-use serde_json::{json, Value};
-use std::collections::HashMap;
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
+/// A generated image, either as a URL or base64 encoded depending on the requested
+/// `response_format`
 #[derive(Debug, Serialize, Deserialize)]
-pub struct ImageURL {
-    pub url: String
+pub struct ImageData {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub b64_json: Option<String>,
+    /// The prompt the image was actually generated from, DALL·E 3 rewrites prompts before
+    /// generating
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revised_prompt: Option<String>,
+    /// Any other per-image fields the response included
+    #[serde(flatten)]
+    pub metadata: Map<String, Value>
+}
+
+/// The previous name of [`ImageData`]
+pub type ImageURL = ImageData;
+
+impl ImageData {
+    /// The PNG bytes of the image, decoded from `b64_json` or downloaded from `url`
+    pub async fn bytes(&self) -> Result<Bytes, Error> {
+        if let Some(b64_json) = &self.b64_json {
+            return base64::engine::general_purpose::STANDARD.decode(b64_json)
+                .map(Bytes::from)
+                .map_err(|e| Error::InvalidImage(e.to_string()));
+        }
+        let url = self.url.as_deref().ok_or_else(|| Error::InvalidImage(String::from("neither url nor b64_json is set")))?;
+        let response = client::shared_http().get(url).send().await.map_err(Error::from_reqwest)?;
+        if response.status() != StatusCode::OK {
+            return Err(Error::Status(response.status()));
+        }
+        response.bytes().await.map_err(Error::from_reqwest)
+    }
+
+    /// Decode the image, downloading it first if it was returned as a URL
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "image")] {
+    /// use oai_rs::images;
+    ///
+    /// async {
+    ///     let images = images::build()
+    ///         .generate(String::from("Modern SVG stroke gradient CPU in the shape of a brain icon"))
+    ///         .response_format("b64_json")
+    ///         .done()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     let image = images.data[0].to_dynamic_image().await.expect("Error Decoding Image");
+    ///     println!("{}x{}", image.width(), image.height());
+    /// };
+    /// # }
+    /// ```
+    #[cfg(feature = "image")]
+    pub async fn to_dynamic_image(&self) -> Result<image::DynamicImage, Error> {
+        let bytes = self.bytes().await?;
+        image::load_from_memory(&bytes).map_err(|e| Error::InvalidImage(e.to_string()))
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Images {
     pub created: u64,
-    pub data: Vec<ImageURL>
+    pub data: Vec<ImageData>,
+    /// How long the request took, not part of the API's response
+    #[serde(skip)]
+    pub timing: Option<Timing>,
+}
+
+impl Images {
+    /// Download every image into `dir`, fetching up to `concurrency` images at once
+    ///
+    /// Files are named `{created}-{index}.png` after the response's creation time and the
+    /// image's position in `data`, the returned paths are in the same order as `data`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::images;
+    ///
+    /// async {
+    ///     let images = images::build()
+    ///         .generate(String::from("Modern SVG stroke gradient CPU in the shape of a brain icon"))
+    ///         .n(&3)
+    ///         .done()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     let paths = images.download_all("./images", 3).await;
+    ///     println!("{:?}", paths);
+    /// };
+    /// ```
+    pub async fn download_all<P>(&self, dir: P, concurrency: usize) -> Result<Vec<PathBuf>, Error>
+    where
+        P: AsRef<Path>
+    {
+        let dir = dir.as_ref();
+        let downloads = self.data.iter().enumerate().map(|(index, image)| {
+            let path = dir.join(format!("{}-{}.png", self.created, index));
+            async move {
+                let bytes = image.bytes().await?;
+                tokio::fs::write(&path, &bytes).await.map_err(Error::Io)?;
+                Ok(path)
+            }
+        });
+
+        stream::iter(downloads)
+            .buffered(concurrency.max(1))
+            .try_collect()
+            .await
+    }
+}
+
+/// An event of a streamed image generation, see [`GenerateParameters::stream`]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ImageStreamEvent {
+    /// A preview of the image while it's being generated
+    #[serde(rename = "image_generation.partial_image")]
+    PartialImage {
+        b64_json: String,
+        /// 0 for the first preview
+        partial_image_index: u32,
+        #[serde(default)]
+        created_at: u64,
+    },
+    /// The finished image
+    #[serde(rename = "image_generation.completed")]
+    Completed {
+        b64_json: String,
+        #[serde(default)]
+        created_at: u64,
+        #[serde(default)]
+        usage: Option<Usage>,
+    },
+}
+
+impl ImageStreamEvent {
+    /// The image of the event, decoded from `b64_json`
+    pub fn bytes(&self) -> Result<Bytes, Error> {
+        let b64_json = match self {
+            ImageStreamEvent::PartialImage { b64_json, .. } | ImageStreamEvent::Completed { b64_json, .. } => b64_json,
+        };
+        base64::engine::general_purpose::STANDARD.decode(b64_json)
+            .map(Bytes::from)
+            .map_err(|e| Error::InvalidImage(e.to_string()))
+    }
+}
+
+/// Stream of image generation events returned by [`GenerateParameters::stream`]
+pub type ImageStream = EventStream<ImageStreamEvent>;
+
+/// The largest input image the edits and variations endpoints accept, 4 MB
+pub const MAX_INPUT_IMAGE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Read a PNG input image from `path`, checking it against the endpoints' size limit
+async fn read_png(path: &Path) -> Result<Bytes, Error> {
+    if files::mime_type(path) != Some("image/png") {
+        return Err(Error::InvalidParameter(format!("{} is not a PNG image", path.display())));
+    }
+    files::read_limited(path, MAX_INPUT_IMAGE_SIZE).await
+}
+
+/// An input image, either sent as is in the request body or as a PNG file
+enum ImageSource {
+    Name(String),
+    Png(Bytes),
+}
+
+/// Encode an image as PNG to send it as an input image
+#[cfg(feature = "image")]
+fn encode_png(image: &image::DynamicImage) -> Result<Bytes, Error> {
+    let mut png = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut png, image::ImageFormat::Png).map_err(|e| Error::InvalidImage(e.to_string()))?;
+    Ok(Bytes::from(png.into_inner()))
 }
 
 pub struct GenerateParameters<'a> {
+    options: RequestOptions,
     prompt: String,
-    query: Vec<(&'a str, Value)>
+    query: Query<'a>
 }
 
 pub struct VariationParameters<'a> {
-    image: String,
-    query: Vec<(&'a str, Value)>
+    options: RequestOptions,
+    image: ImageSource,
+    query: Query<'a>
 }
 
 pub struct EditParameters<'a> {
+    options: RequestOptions,
     prompt: String,
-    image: String,
-    query: Vec<(&'a str, Value)>
+    image: ImageSource,
+    mask: Option<Bytes>,
+    query: Query<'a>
 }
 
 /// Available parameters that can be sent with an image request
-pub struct Parameters {}
+pub struct Parameters {
+    options: RequestOptions
+}
 
 /// Function to create a edit request
 ///
@@ -60,38 +245,155 @@ pub struct Parameters {}
 /// ```
 ///
 pub fn build() -> Parameters {
-    Parameters {}
+    Parameters {
+        options: RequestOptions::default()
+    }
 }
 
 /// Parameter to set the request type for the images endpoint either, `generate`, `edit` or `variations`.
 impl Parameters {
+    /// Send the request using the given client instead of one loaded from the environment
+    pub fn client(mut self, client: &Client) -> Self {
+        self.options.client = Some(client.clone());
+        self
+    }
+
     /// Genertes image(s) given a prompt.
     pub fn generate<'a>(self, prompt: String) -> GenerateParameters<'a> {
         GenerateParameters {
+            options: self.options,
             prompt,
-            query: Vec::new()
+            query: Query::new()
         }
     }
 
     /// Creates an edited or extended image given an original image and a prompt.
     pub fn edits<'a>(self, image: String, prompt: String) -> EditParameters<'a> {
         EditParameters {
+            options: self.options,
             prompt,
-            image,
-            query: Vec::new()
+            image: ImageSource::Name(image),
+            mask: None,
+            query: Query::new()
+        }
+    }
+
+    /// Creates an edited or extended image given the path of a PNG original image and a prompt.
+    ///
+    /// Fails without sending the request if the image isn't a PNG or is larger than 4 MB.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::images;
+    ///
+    /// async {
+    ///     let images = images::build()
+    ///         .edits_path("./kitchen.png", String::from("A kitchen with a plate of cookies"))
+    ///         .await
+    ///         .expect("Error Reading Image")
+    ///         .mask_path("./kitchen-mask.png")
+    ///         .await
+    ///         .expect("Error Reading Mask")
+    ///         .done()
+    ///         .await;
+    ///
+    ///     println!("{:?}", images);
+    /// };
+    /// ```
+    pub async fn edits_path<'a, P: AsRef<Path>>(self, image: P, prompt: String) -> Result<EditParameters<'a>, Error> {
+        let png = read_png(image.as_ref()).await?;
+        Ok(self.edits_png(png, prompt))
+    }
+
+    /// Creates an edited or extended image given an original image and a prompt, the
+    /// image is encoded as PNG.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "image")] {
+    /// use oai_rs::images;
+    ///
+    /// async {
+    ///     let image = image::DynamicImage::new_rgba8(256, 256);
+    ///     let images = images::build()
+    ///         .edits_image(&image, String::from("A cookie on a plate"))
+    ///         .expect("Error Encoding Image")
+    ///         .done()
+    ///         .await;
+    ///
+    ///     println!("{:?}", images);
+    /// };
+    /// # }
+    /// ```
+    #[cfg(feature = "image")]
+    pub fn edits_image<'a>(self, image: &image::DynamicImage, prompt: String) -> Result<EditParameters<'a>, Error> {
+        Ok(self.edits_png(encode_png(image)?, prompt))
+    }
+
+    /// Creates an edited or extended image given the PNG encoded original image and a prompt.
+    pub fn edits_png<'a>(self, png: Bytes, prompt: String) -> EditParameters<'a> {
+        EditParameters {
+            options: self.options,
+            prompt,
+            image: ImageSource::Png(png),
+            mask: None,
+            query: Query::new()
         }
     }
 
     /// Creates a variation of a given image.
     pub fn variation<'a>(self, image: String) -> VariationParameters<'a> {
         VariationParameters {
-            image,
-            query: Vec::new()
+            options: self.options,
+            image: ImageSource::Name(image),
+            query: Query::new()
+        }
+    }
+
+    /// Creates a variation of the PNG image at the given path.
+    ///
+    /// Fails without sending the request if the image isn't a PNG or is larger than 4 MB.
+    pub async fn variation_path<'a, P: AsRef<Path>>(self, image: P) -> Result<VariationParameters<'a>, Error> {
+        let png = read_png(image.as_ref()).await?;
+        Ok(self.variation_png(png))
+    }
+
+    /// Creates a variation of a given image, the image is encoded as PNG.
+    #[cfg(feature = "image")]
+    pub fn variation_image<'a>(self, image: &image::DynamicImage) -> Result<VariationParameters<'a>, Error> {
+        Ok(self.variation_png(encode_png(image)?))
+    }
+
+    /// Creates a variation of a given PNG encoded image.
+    pub fn variation_png<'a>(self, png: Bytes) -> VariationParameters<'a> {
+        VariationParameters {
+            options: self.options,
+            image: ImageSource::Png(png),
+            query: Query::new()
         }
     }
 }
 
 impl<'a> GenerateParameters<'a> {
+    /// The model to generate images with, e.g. `dall-e-3` or `gpt-image-1`
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/images/create#images-create-model)
+    pub fn model(mut self, input: &'a str) -> Self {
+        self.query.push(("model", json!(input)));
+        self
+    }
+
+    /// How many previews of the image to stream while it's generated, between 0 and 3, only
+    /// supported by `gpt-image-1` and used with [`stream`](GenerateParameters::stream)
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/images/create#images-create-partial_images)
+    pub fn partial_images(mut self, input: u8) -> Self {
+        self.query.push(("partial_images", json!(input)));
+        self
+    }
+
     /// How many images to generate. Must be number between 1 and 10
     ///
     /// [OpenAI Reference](https://beta.openai.com/docs/api-reference/images/create#images/create-n)
@@ -108,11 +410,28 @@ impl<'a> GenerateParameters<'a> {
         self
     }
 
+    /// The quality of the generated images, e.g. `standard` or `hd` for `dall-e-3`, `low`,
+    /// `medium` or `high` for `gpt-image-1`
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/images/create#images-create-quality)
+    pub fn quality(mut self, input: &'a str) -> Self {
+        self.query.push(("quality", json!(input)));
+        self
+    }
+
+    /// The style of the generated images, `vivid` or `natural`, only supported by `dall-e-3`
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/images/create#images-create-style)
+    pub fn style(mut self, input: &'a str) -> Self {
+        self.query.push(("style", json!(input)));
+        self
+    }
+
     /// The format in which the generated images are returned. Must be one of `url` or `b64_json`.
     ///
     /// [OpenAI Reference](https://beta.openai.com/docs/api-reference/images/create#images/create-response_format)
     pub fn response_format(mut self, input: &'a str) -> Self {
-        self.query.push(("size", json!(input)));
+        self.query.push(("response_format", json!(input)));
         self
     }
 
@@ -125,26 +444,85 @@ impl<'a> GenerateParameters<'a> {
         self
     }
 
+    /// Mark the request as safe to retry even when the client's retry policy
+    /// is restricted to idempotent requests, see [`RetryScope::IdempotentOnly`](crate::retry::RetryScope::IdempotentOnly)
+    pub fn idempotent(mut self) -> Self {
+        self.options.idempotent = true;
+        self
+    }
+
+    /// Set a parameter this crate doesn't model yet, sent as is in the request body
+    ///
+    /// Replaces the value set for `key` by earlier setters, and is replaced by later ones.
+    /// `prompt` and `stream` are set when the request is sent, so extra values for them are
+    /// ignored.
+    pub fn extra_param(mut self, key: &'a str, value: Value) -> Self {
+        self.query.push((key, value));
+        self
+    }
+
     /// Complete the request and send
-    pub async fn done(self) -> Result<Images, StatusCode> {
+    pub async fn done(mut self) -> Result<Images, Error> {
+        let started = Instant::now();
 
-        let mut map = HashMap::new();
-        map.insert("prompt", json!(self.prompt));
-        for (k, v) in self.query.into_iter() {
-            map.insert(k, v);
-        }
+        self.query.push(("prompt", json!(self.prompt)));
 
-        let response: Result<Images, StatusCode> = requester::images(requester::ImageRequestType::Generations, map).await;
+        let response: Result<Images, Error> = requester::images(&self.options, requester::ImageRequestType::Generations, &self.query).await;
 
         match response {
-            Ok(t) => Ok(t),
+            Ok(mut t) => {
+                t.timing = Some(Timing::since(started));
+                Ok(t)
+            }
             Err(e) => Err(e),
         }
     }
+
+    /// Send the request and stream back previews of the image as it's generated, followed by
+    /// the finished image, only supported by `gpt-image-1`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_util::StreamExt;
+    /// use oai_rs::images::{self, ImageStreamEvent};
+    ///
+    /// async {
+    ///     let mut stream = images::build()
+    ///         .generate(String::from("A plate of cookies, watercolor"))
+    ///         .model("gpt-image-1")
+    ///         .partial_images(2)
+    ///         .stream()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     while let Some(event) = stream.next().await {
+    ///         match event.expect("Error Getting Event") {
+    ///             ImageStreamEvent::PartialImage { partial_image_index, .. } => println!("preview {}", partial_image_index),
+    ///             event @ ImageStreamEvent::Completed { .. } => {
+    ///                 tokio::fs::write("cookies.png", event.bytes().unwrap()).await.unwrap();
+    ///             }
+    ///         }
+    ///     }
+    /// };
+    /// ```
+    pub async fn stream(mut self) -> Result<ImageStream, Error> {
+        self.query.push(("prompt", json!(self.prompt)));
+        self.query.push(("stream", json!(true)));
+
+        requester::images_stream(&self.options, requester::ImageRequestType::Generations, &self.query).await
+    }
 }
 
 
 impl<'a> EditParameters<'a> {
+    /// The model to edit the image with, `dall-e-2` or `gpt-image-1`
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/images/createEdit#images-createedit-model)
+    pub fn model(mut self, input: &'a str) -> Self {
+        self.query.push(("model", json!(input)));
+        self
+    }
 
     ///An additional image whose fully transparent areas
     ///(e.g. where alpha is zero) indicate where image should
@@ -157,6 +535,20 @@ impl<'a> EditParameters<'a> {
         self
     }
 
+    /// The mask as a PNG encoded image, see [`mask`](EditParameters::mask).
+    pub fn mask_png(mut self, png: Bytes) -> Self {
+        self.mask = Some(png);
+        self
+    }
+
+    /// The mask read from the PNG image at the given path, see [`mask`](EditParameters::mask).
+    ///
+    /// Fails without sending the request if the mask isn't a PNG or is larger than 4 MB.
+    pub async fn mask_path<P: AsRef<Path>>(self, path: P) -> Result<Self, Error> {
+        let png = read_png(path.as_ref()).await?;
+        Ok(self.mask_png(png))
+    }
+
     /// How many images to generate. Must be number between 1 and 10
     ///
     /// [OpenAI Reference](https://beta.openai.com/docs/api-reference/images/create-edit#images/create-edit-n)
@@ -177,7 +569,7 @@ impl<'a> EditParameters<'a> {
     ///
     /// [OpenAI Reference](https://beta.openai.com/docs/api-reference/images/create-edit#images/create-edit-response_format)
     pub fn response_format(mut self, input: &'a str) -> Self {
-        self.query.push(("size", json!(input)));
+        self.query.push(("response_format", json!(input)));
         self
     }
 
@@ -190,26 +582,63 @@ impl<'a> EditParameters<'a> {
         self
     }
 
+    /// Mark the request as safe to retry even when the client's retry policy
+    /// is restricted to idempotent requests, see [`RetryScope::IdempotentOnly`](crate::retry::RetryScope::IdempotentOnly)
+    pub fn idempotent(mut self) -> Self {
+        self.options.idempotent = true;
+        self
+    }
+
+    /// Set a parameter this crate doesn't model yet, sent as is in the request body
+    ///
+    /// Replaces the value set for `key` by earlier setters, and is replaced by later ones.
+    /// `prompt` and `image` are set when the request is sent, so extra values for them are
+    /// ignored.
+    pub fn extra_param(mut self, key: &'a str, value: Value) -> Self {
+        self.query.push((key, value));
+        self
+    }
+
     /// Complete the request and send
-    pub async fn done(self) -> Result<Images, StatusCode> {
+    pub async fn done(mut self) -> Result<Images, Error> {
+        let started = Instant::now();
+
+        self.query.push(("prompt", json!(self.prompt)));
 
-        let mut map = HashMap::new();
-        map.insert("prompt", json!(self.prompt));
-        map.insert("image", json!(self.image));
-        for (k, v) in self.query.into_iter() {
-            map.insert(k, v);
+        let mut pngs = Vec::new();
+        match self.image {
+            ImageSource::Name(image) => self.query.push(("image", json!(image))),
+            ImageSource::Png(png) => pngs.push(("image", png)),
+        }
+        if let Some(mask) = self.mask {
+            pngs.push(("mask", mask));
         }
 
-        let response: Result<Images, StatusCode> = requester::images(requester::ImageRequestType::Edits, map).await;
+        let response: Result<Images, Error> = if pngs.is_empty() {
+            requester::images(&self.options, requester::ImageRequestType::Edits, &self.query).await
+        } else {
+            requester::images_multipart(&self.options, requester::ImageRequestType::Edits, &self.query, &pngs).await
+        };
 
         match response {
-            Ok(t) => Ok(t),
+            Ok(mut t) => {
+                t.timing = Some(Timing::since(started));
+                Ok(t)
+            }
             Err(e) => Err(e),
         }
     }
 }
 
 impl<'a> VariationParameters<'a> {
+    /// The model to create the variations with, only `dall-e-2` is supported
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/images/createVariation#images-createvariation-model)
+    pub fn model(mut self, input: &'a str) -> Self {
+        self.query.push(("model", json!(input)));
+        self
+    }
+
     /// How many images to generate. Must be number between 1 and 10
     ///
     /// [OpenAI Reference](https://beta.openai.com/docs/api-reference/images/create-variation#images/create-variation-n)
@@ -230,7 +659,7 @@ impl<'a> VariationParameters<'a> {
     ///
     /// [OpenAI Reference](https://beta.openai.com/docs/api-reference/images/create-variation#images/create-variation-response_format)
     pub fn response_format(mut self, input: &'a str) -> Self {
-        self.query.push(("size", json!(input)));
+        self.query.push(("response_format", json!(input)));
         self
     }
 
@@ -243,20 +672,100 @@ impl<'a> VariationParameters<'a> {
         self
     }
 
-    /// Complete the request and send
-    pub async fn done(self) -> Result<Images, StatusCode> {
+    /// Mark the request as safe to retry even when the client's retry policy
+    /// is restricted to idempotent requests, see [`RetryScope::IdempotentOnly`](crate::retry::RetryScope::IdempotentOnly)
+    pub fn idempotent(mut self) -> Self {
+        self.options.idempotent = true;
+        self
+    }
 
-        let mut map = HashMap::new();
-        map.insert("image", json!(self.image));
-        for (k, v) in self.query.into_iter() {
-            map.insert(k, v);
-        }
+    /// Set a parameter this crate doesn't model yet, sent as is in the request body
+    ///
+    /// Replaces the value set for `key` by earlier setters, and is replaced by later ones.
+    /// `image` is set when the request is sent, so an extra value for it is ignored.
+    pub fn extra_param(mut self, key: &'a str, value: Value) -> Self {
+        self.query.push((key, value));
+        self
+    }
+
+    /// Complete the request and send
+    pub async fn done(mut self) -> Result<Images, Error> {
+        let started = Instant::now();
 
-        let response: Result<Images, StatusCode> = requester::images(requester::ImageRequestType::Variations, map).await;
+        let response: Result<Images, Error> = match self.image {
+            ImageSource::Name(image) => {
+                self.query.push(("image", json!(image)));
+                requester::images(&self.options, requester::ImageRequestType::Variations, &self.query).await
+            }
+            ImageSource::Png(png) => requester::images_multipart(&self.options, requester::ImageRequestType::Variations, &self.query, &[("image", png)]).await,
+        };
 
         match response {
-            Ok(t) => Ok(t),
+            Ok(mut t) => {
+                t.timing = Some(Timing::since(started));
+                Ok(t)
+            }
             Err(e) => Err(e),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::build;
+    use crate::testing::{assert_snapshot, golden, TestClient};
+
+    #[tokio::test]
+    async fn generate_parameters_use_wire_keys() {
+        let test = TestClient::new();
+        let _ = build()
+            .client(&test.client())
+            .generate("A cute baby sea otter".to_string())
+            .model("dall-e-3")
+            .n(&1)
+            .size("1024x1024")
+            .quality("hd")
+            .style("natural")
+            .response_format("b64_json")
+            .user("user-1")
+            .done()
+            .await;
+
+        assert_snapshot(golden("images_generate"), &test.last_request());
+    }
+
+    #[tokio::test]
+    async fn edit_parameters_use_wire_keys() {
+        let test = TestClient::new();
+        let _ = build()
+            .client(&test.client())
+            .edits("otter.png".to_string(), "Add a hat".to_string())
+            .model("dall-e-2")
+            .mask("mask.png")
+            .n(&2)
+            .size("512x512")
+            .response_format("url")
+            .user("user-1")
+            .done()
+            .await;
+
+        assert_snapshot(golden("images_edit"), &test.last_request());
+    }
+
+    #[tokio::test]
+    async fn variation_parameters_use_wire_keys() {
+        let test = TestClient::new();
+        let _ = build()
+            .client(&test.client())
+            .variation("otter.png".to_string())
+            .model("dall-e-2")
+            .n(&2)
+            .size("256x256")
+            .response_format("url")
+            .user("user-1")
+            .done()
+            .await;
+
+        assert_snapshot(golden("images_variation"), &test.last_request());
+    }
+}
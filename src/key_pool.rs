@@ -0,0 +1,207 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a rate limited key is passed over by [`KeySelection::LeastRecentlyThrottled`]
+const COOL_DOWN: Duration = Duration::from_secs(60);
+
+/// How a [`KeyPool`] picks the key for each request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeySelection {
+    /// Use each key in turn
+    RoundRobin,
+    /// Use the key that was rate limited longest ago. Keys that weren't rate limited in the
+    /// last minute come first and are used in turn, like [`RoundRobin`](KeySelection::RoundRobin).
+    LeastRecentlyThrottled,
+}
+
+/// Spreads requests over several API keys
+///
+/// Every attempt of a request, including retries, picks a key from the pool, so a retry
+/// after a `429` can go out with a different key. The pool replaces the key the client was
+/// created with.
+///
+/// The pool is shared between all clones of the [`Client`](crate::Client) it is set on.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{Client, key_pool::{KeyPool, KeySelection}};
+///
+/// let pool = KeyPool::new(["sk-first...", "sk-second..."])
+///     .selection(KeySelection::LeastRecentlyThrottled);
+/// let client = Client::new(String::from("sk-first...")).key_pool(pool);
+/// ```
+#[derive(Clone)]
+pub struct KeyPool {
+    keys: Arc<[Arc<str>]>,
+    selection: KeySelection,
+    state: Arc<Mutex<State>>,
+}
+
+struct State {
+    next: usize,
+    throttled_at: Vec<Option<Instant>>,
+}
+
+impl KeyPool {
+    /// A pool of the given keys, used round-robin
+    pub fn new<I, S>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>
+    {
+        let keys: Arc<[Arc<str>]> = keys.into_iter().map(|k| Arc::from(k.into())).collect();
+        let throttled_at = vec![None; keys.len()];
+        KeyPool {
+            keys,
+            selection: KeySelection::RoundRobin,
+            state: Arc::new(Mutex::new(State { next: 0, throttled_at })),
+        }
+    }
+
+    /// Set how the key for each request is picked
+    pub fn selection(mut self, selection: KeySelection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// The number of keys in the pool
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether the pool has no keys, the client's own key is used if so
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Pick the key for the next attempt, along with its position in the pool
+    pub(crate) fn select(&self) -> Option<(usize, Arc<str>)> {
+        if self.keys.is_empty() {
+            return None;
+        }
+        let mut state = self.state.lock().unwrap();
+        let count = self.keys.len();
+        let index = match self.selection {
+            KeySelection::RoundRobin => state.next % count,
+            // Keys whose throttle has cooled down tie with those never throttled, and ties
+            // go to the first key in turn from the last one used
+            KeySelection::LeastRecentlyThrottled => (0..count)
+                .map(|offset| (state.next + offset) % count)
+                .min_by_key(|i| state.throttled_at[*i].filter(|at| at.elapsed() < COOL_DOWN))
+                .unwrap_or(0),
+        };
+        state.next = index + 1;
+        Some((index, self.keys[index].clone()))
+    }
+
+    pub(crate) fn record_throttled(&self, index: usize) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(throttled_at) = state.throttled_at.get_mut(index) {
+            *throttled_at = Some(Instant::now());
+        }
+    }
+}
+
+impl fmt::Debug for KeyPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyPool")
+            .field("keys", &format_args!("[{} redacted]", self.keys.len()))
+            .field("selection", &self.selection)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyPool, KeySelection, COOL_DOWN};
+    use crate::embeddings;
+    use crate::models::EmbeddingModels;
+    use crate::retry::{RetryPolicy, RetryScope};
+    use crate::testing::{CannedResponse, Matcher, TestClient};
+    use crate::transport::{Transport, TransportFuture};
+    use reqwest::header::AUTHORIZATION;
+    use reqwest::Request;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    fn picks(pool: &KeyPool, count: usize) -> Vec<usize> {
+        (0..count).map(|_| pool.select().unwrap().0).collect()
+    }
+
+    #[test]
+    fn round_robin_uses_each_key_in_turn() {
+        let pool = KeyPool::new(["a", "b", "c"]);
+        pool.record_throttled(1);
+        assert_eq!(picks(&pool, 5), [0, 1, 2, 0, 1]);
+        assert!(KeyPool::new(Vec::<String>::new()).select().is_none());
+    }
+
+    #[test]
+    fn least_recently_throttled_passes_over_throttled_keys() {
+        let pool = KeyPool::new(["a", "b", "c", "d"]).selection(KeySelection::LeastRecentlyThrottled);
+        pool.record_throttled(1);
+        pool.record_throttled(2);
+        assert_eq!(picks(&pool, 4), [0, 3, 0, 3]);
+
+        // Once every key was throttled, the one throttled longest ago goes first
+        pool.record_throttled(0);
+        pool.record_throttled(3);
+        assert_eq!(picks(&pool, 2), [1, 1]);
+    }
+
+    #[test]
+    fn least_recently_throttled_rotates_keys_once_cooled_down() {
+        let pool = KeyPool::new(["a", "b", "c"]).selection(KeySelection::LeastRecentlyThrottled);
+        pool.state.lock().unwrap().throttled_at[0] = Instant::now().checked_sub(COOL_DOWN + Duration::from_secs(1));
+        assert_eq!(picks(&pool, 6), [0, 1, 2, 0, 1, 2]);
+    }
+
+    /// Rate limits the first request, recording the `Authorization` header of every request
+    struct FirstThrottled {
+        inner: TestClient,
+        authorizations: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Transport for FirstThrottled {
+        fn execute(&self, request: Request) -> TransportFuture {
+            let authorization = request.headers()[AUTHORIZATION].to_str().unwrap().to_string();
+            let mut authorizations = self.authorizations.lock().unwrap();
+            authorizations.push(authorization);
+            if authorizations.len() == 1 {
+                let response = http::Response::builder()
+                    .status(429)
+                    .body(r#"{"error": {"message": "Rate limit reached", "type": "requests"}}"#)
+                    .unwrap();
+                return Box::pin(async move { Ok(response.into()) });
+            }
+            self.inner.execute(request)
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_after_a_rate_limit_use_another_key() {
+        let test = TestClient::new().on(Matcher::post("embeddings"), CannedResponse::json(&json!({
+            "object": "list",
+            "data": [{ "object": "embedding", "embedding": [0.5], "index": 0 }],
+            "model": "text-embedding-3-small",
+            "usage": null
+        })));
+        let authorizations = Arc::new(Mutex::new(Vec::new()));
+        let client = test.client()
+            .transport(FirstThrottled { inner: test.clone(), authorizations: authorizations.clone() })
+            .retry_policy(RetryPolicy::default().max_retries(1).backoff(Duration::ZERO, Duration::ZERO).scope(RetryScope::All))
+            .key_pool(KeyPool::new(["sk-first", "sk-second"]).selection(KeySelection::LeastRecentlyThrottled));
+
+        embeddings::build(EmbeddingModels::TEXT_EMBEDDING_3_SMALL)
+            .client(&client)
+            .input("cookies")
+            .embed()
+            .await
+            .unwrap();
+
+        assert_eq!(*authorizations.lock().unwrap(), ["Bearer sk-first", "Bearer sk-second"]);
+    }
+}
@@ -1,9 +1,52 @@
 //! Just a Rust wrapper for OpenAI's API
 //!
 //![OpenAI API](https://beta.openai.com/docs/api-reference/)
+#[cfg(all(test, feature = "derive"))]
+extern crate self as oai_rs;
 mod requester;
-mod usage;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod budget;
+pub mod cache;
+#[cfg(feature = "tokenizer")]
+pub mod chunking;
+pub mod choices;
+pub mod circuit_breaker;
+pub mod client;
+pub mod embedding_store;
+pub mod error;
+pub mod key_pool;
+pub mod retry;
+pub mod scheduler;
+#[cfg(feature = "tower")]
+pub mod service;
+pub mod stream;
+mod telemetry;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+#[cfg(feature = "tokenizer")]
+pub mod tokenizer;
+pub mod transport;
+pub mod usage;
+pub mod assistants;
+pub mod batches;
+pub mod chat;
 pub mod completions;
 pub mod edits;
+pub mod embeddings;
+pub mod files;
+pub mod fine_tuning;
 pub mod models;
+pub mod moderations;
 pub mod images;
+pub mod realtime;
+pub mod responses;
+pub mod schema;
+pub mod search;
+pub mod threads;
+pub mod tools;
+pub mod uploads;
+pub mod vector_stores;
+
+pub use client::{configure, Client, ClientBuilder};
+pub use error::Error;
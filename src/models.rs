@@ -1,4 +1,6 @@
-use crate::requester;
+use crate::client::Client;
+use crate::error::Error;
+use crate::requester::{self, RequestOptions};
 use reqwest::StatusCode;
 use serde::{Serialize, Deserialize};
 
@@ -32,6 +34,86 @@ pub struct RootModel {
     pub data: Vec<Model>
 }
 
+/// What a model can be used for, guessed from its identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    Chat,
+    Completions,
+    Embeddings,
+    Moderations,
+    Images,
+    Audio,
+}
+
+impl Model {
+    /// What the model can be used for, `None` if it can't be told from the identifier
+    pub fn capability(&self) -> Option<Capability> {
+        let id = self.id.strip_prefix("ft:").unwrap_or(&self.id);
+        if id.contains("embedding") {
+            Some(Capability::Embeddings)
+        } else if id.contains("moderation") {
+            Some(Capability::Moderations)
+        } else if id.starts_with("dall-e") || id.starts_with("gpt-image") {
+            Some(Capability::Images)
+        } else if id.starts_with("whisper") || id.starts_with("tts") || id.contains("audio") || id.contains("realtime") || id.contains("transcribe") {
+            Some(Capability::Audio)
+        } else if id.contains("instruct") || id.starts_with("davinci") || id.starts_with("babbage") || id.starts_with("text-") {
+            Some(Capability::Completions)
+        } else if id.starts_with("gpt-") || id.starts_with("chatgpt") || id.starts_with('o') {
+            Some(Capability::Chat)
+        } else {
+            None
+        }
+    }
+}
+
+/// Filters for the models returned by [`list`]
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::models::{self, Capability, ModelList};
+///
+/// async {
+///     let models = models::list().await.expect("Error Getting Response");
+///     for model in models.with_capability(Capability::Chat) {
+///         println!("{}", model.id);
+///     }
+/// };
+/// ```
+pub trait ModelList {
+    /// Models owned by `owner`, e.g. `system` or your organization
+    fn owned_by(&self, owner: &str) -> Vec<&Model>;
+
+    /// Models whose identifier starts with `prefix`, e.g. `gpt-4o` or `ft:`
+    fn with_prefix(&self, prefix: &str) -> Vec<&Model>;
+
+    /// Models that can be used for `capability`, see [`Model::capability`]
+    fn with_capability(&self, capability: Capability) -> Vec<&Model>;
+
+    /// The model with the identifier `id`
+    fn find(&self, id: &str) -> Option<&Model>;
+}
+
+impl ModelList for [Model] {
+    fn owned_by(&self, owner: &str) -> Vec<&Model> {
+        self.iter().filter(|m| m.owned_by.as_deref() == Some(owner)).collect()
+    }
+
+    fn with_prefix(&self, prefix: &str) -> Vec<&Model> {
+        self.iter().filter(|m| m.id.starts_with(prefix)).collect()
+    }
+
+    fn with_capability(&self, capability: Capability) -> Vec<&Model> {
+        self.iter().filter(|m| m.capability() == Some(capability)).collect()
+    }
+
+    fn find(&self, id: &str) -> Option<&Model> {
+        self.iter().find(|m| m.id == id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CompletionModels {
     /// Uses the text-davinci-003 model.
     ///
@@ -83,28 +165,118 @@ pub enum CompletionModels {
     /// # Examples
     ///
     /// ```rust
-    /// use crate::models;
+    /// use oai_rs::models;
     ///
-    /// let completionModel = models::CompletionModel::from_str("text-davinci-003");
+    /// let completion_model = models::CompletionModels::from_str(String::from("text-davinci-003"));
+    /// let fine_tuned = models::CompletionModels::from(format!("ft:davinci-002:{}::abc123", "my-org"));
     /// ```
     #[allow(non_camel_case_types)]
-    from_str(&'static str)
+    from_str(String)
 }
 
 impl CompletionModels {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CompletionModels::TEXT_DAVINCI_003 => "text-davinci-003",
+            CompletionModels::TEXT_DAVINCI_002 => "text-davinci-002",
+            CompletionModels::TEXT_DAVINCI_001 => "text-davinci-001",
+            CompletionModels::TEXT_CURIE_001 => "text-curie-001",
+            CompletionModels::TEXT_BABBAGE_001 => "text-babbage-001",
+            CompletionModels::TEXT_ADA_001 => "text-ada-001",
+            CompletionModels::from_str(t) => t
+        }
+    }
+
     pub fn as_string(&self) -> String {
-        match &*self {
-            CompletionModels::TEXT_DAVINCI_003 => String::from("text-davinci-003"),
-            CompletionModels::TEXT_DAVINCI_002 => String::from("text-davinci-002"),
-            CompletionModels::TEXT_DAVINCI_001 => String::from("text-davinci-001"),
-            CompletionModels::TEXT_CURIE_001 => String::from("text-curie-001"),
-            CompletionModels::TEXT_BABBAGE_001 => String::from("text-babbage-001"),
-            CompletionModels::TEXT_ADA_001 => String::from("text-ada-001"),
-            CompletionModels::from_str(t) => String::from(*t)
+        String::from(self.as_str())
+    }
+}
+
+impl From<String> for CompletionModels {
+    fn from(model: String) -> Self {
+        CompletionModels::from_str(model)
+    }
+}
+
+impl From<&str> for CompletionModels {
+    fn from(model: &str) -> Self {
+        CompletionModels::from_str(model.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChatModels {
+    /// Uses the gpt-4o model.
+    ///
+    /// Flagship multimodal model, faster and cheaper than GPT-4 Turbo with
+    /// stronger vision capabilities.
+    ///
+    /// **Strengths:** Complex reasoning, multi-step tasks, vision.
+    #[allow(non_camel_case_types)]
+    GPT_4O,
+    /// Uses the gpt-4o-mini model.
+    ///
+    /// Small, affordable model for fast, lightweight tasks.
+    ///
+    /// **Strengths:** Classification, extraction, chat at scale.
+    #[allow(non_camel_case_types)]
+    GPT_4O_MINI,
+    /// Uses the gpt-4-turbo model.
+    ///
+    /// Previous generation high-intelligence model with a 128k context window.
+    ///
+    /// **Strengths:** Complex intent, long documents.
+    #[allow(non_camel_case_types)]
+    GPT_4_TURBO,
+    /// Uses the gpt-3.5-turbo model.
+    ///
+    /// Fast, inexpensive model for simple tasks.
+    ///
+    /// **Strengths:** Chat, simple classification, summarization.
+    #[allow(non_camel_case_types)]
+    GPT_3_5_TURBO,
+    /// Use a model through it's identifier
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::models;
+    ///
+    /// let chat_model = models::ChatModels::from_str(String::from("gpt-4o-2024-08-06"));
+    /// ```
+    #[allow(non_camel_case_types)]
+    from_str(String)
+}
+
+impl ChatModels {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ChatModels::GPT_4O => "gpt-4o",
+            ChatModels::GPT_4O_MINI => "gpt-4o-mini",
+            ChatModels::GPT_4_TURBO => "gpt-4-turbo",
+            ChatModels::GPT_3_5_TURBO => "gpt-3.5-turbo",
+            ChatModels::from_str(t) => t
         }
     }
+
+    pub fn as_string(&self) -> String {
+        String::from(self.as_str())
+    }
+}
+
+impl From<String> for ChatModels {
+    fn from(model: String) -> Self {
+        ChatModels::from_str(model)
+    }
 }
 
+impl From<&str> for ChatModels {
+    fn from(model: &str) -> Self {
+        ChatModels::from_str(model.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum EditModels {
     /// Uses the text-davinci-edit-001 model.
     ///
@@ -119,26 +291,167 @@ pub enum EditModels {
     /// # Examples
     ///
     /// ```rust
-    /// use crate::models;
+    /// use oai_rs::models;
     ///
-    /// let editModel = models::EditModels::from_str("text-davinci-edit-001");
+    /// let edit_model = models::EditModels::from_str(String::from("text-davinci-edit-001"));
     /// ```
     #[allow(non_camel_case_types)]
-    from_str(&'static str)
+    from_str(String)
 }
 
 impl EditModels {
+    pub fn as_str(&self) -> &str {
+        match self {
+            EditModels::TEXT_DAVINCI_EDIT_001 => "text-davinci-edit-001",
+            EditModels::from_str(t) => t
+        }
+    }
+
+    pub fn as_string(&self) -> String {
+        String::from(self.as_str())
+    }
+}
+
+impl From<String> for EditModels {
+    fn from(model: String) -> Self {
+        EditModels::from_str(model)
+    }
+}
+
+impl From<&str> for EditModels {
+    fn from(model: &str) -> Self {
+        EditModels::from_str(model.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmbeddingModels {
+    /// Uses the text-embedding-ada-002 model.
+    ///
+    /// Second generation embedding model, replacing the 16 first generation
+    /// embedding models at a fraction of the cost.
+    ///
+    /// **Strengths:** Search, clustering, recommendations, classification.
+    #[allow(non_camel_case_types)]
+    TEXT_EMBEDDING_ADA_002,
+    /// Uses the text-embedding-3-small model.
+    ///
+    /// Third generation embedding model, stronger and cheaper than
+    /// text-embedding-ada-002.
+    ///
+    /// **Strengths:** Search, clustering, recommendations, classification.
+    #[allow(non_camel_case_types)]
+    TEXT_EMBEDDING_3_SMALL,
+    /// Uses the text-embedding-3-large model.
+    ///
+    /// Most capable embedding model, with embeddings of up to 3072 dimensions.
+    ///
+    /// **Strengths:** Search, clustering, recommendations, classification.
+    #[allow(non_camel_case_types)]
+    TEXT_EMBEDDING_3_LARGE,
+    /// Use a model through it's identifier
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::models;
+    ///
+    /// let embedding_model = models::EmbeddingModels::from_str(String::from("text-embedding-ada-002"));
+    /// ```
+    #[allow(non_camel_case_types)]
+    from_str(String)
+}
+
+impl EmbeddingModels {
+    pub fn as_str(&self) -> &str {
+        match self {
+            EmbeddingModels::TEXT_EMBEDDING_ADA_002 => "text-embedding-ada-002",
+            EmbeddingModels::TEXT_EMBEDDING_3_SMALL => "text-embedding-3-small",
+            EmbeddingModels::TEXT_EMBEDDING_3_LARGE => "text-embedding-3-large",
+            EmbeddingModels::from_str(t) => t
+        }
+    }
+
     pub fn as_string(&self) -> String {
-        match &*self {
-            EditModels::TEXT_DAVINCI_EDIT_001 => String::from("text-davinci-edit-001"),
-            EditModels::from_str(t) => String::from(*t)
+        String::from(self.as_str())
+    }
+}
+
+impl From<String> for EmbeddingModels {
+    fn from(model: String) -> Self {
+        EmbeddingModels::from_str(model)
+    }
+}
+
+impl From<&str> for EmbeddingModels {
+    fn from(model: &str) -> Self {
+        EmbeddingModels::from_str(model.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModerationModels {
+    /// Uses the omni-moderation-latest model.
+    ///
+    /// Classifies both text and images, with more categories than the
+    /// text moderation models.
+    ///
+    /// **Strengths:** Multimodal moderation, more accurate classification.
+    #[allow(non_camel_case_types)]
+    OMNI_MODERATION_LATEST,
+    /// Uses the text-moderation-latest model.
+    ///
+    /// Text only moderation model, automatically upgraded over time.
+    ///
+    /// **Strengths:** Text moderation.
+    #[allow(non_camel_case_types)]
+    TEXT_MODERATION_LATEST,
+    /// Use a model through it's identifier
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::models;
+    ///
+    /// let moderation_model = models::ModerationModels::from_str(String::from("text-moderation-stable"));
+    /// ```
+    #[allow(non_camel_case_types)]
+    from_str(String)
+}
+
+impl ModerationModels {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ModerationModels::OMNI_MODERATION_LATEST => "omni-moderation-latest",
+            ModerationModels::TEXT_MODERATION_LATEST => "text-moderation-latest",
+            ModerationModels::from_str(t) => t
         }
     }
+
+    pub fn as_string(&self) -> String {
+        String::from(self.as_str())
+    }
+}
+
+impl From<String> for ModerationModels {
+    fn from(model: String) -> Self {
+        ModerationModels::from_str(model)
+    }
+}
+
+impl From<&str> for ModerationModels {
+    fn from(model: &str) -> Self {
+        ModerationModels::from_str(model.to_string())
+    }
 }
 
 /// Request a list of all currently available models from the API
-pub async fn list() -> Result<Vec<Model>, StatusCode> {
-    let response: Result<RootModel, StatusCode> = requester::models(None).await;
+pub async fn list() -> Result<Vec<Model>, Error> {
+    list_with(None).await
+}
+
+pub(crate) async fn list_with(client: Option<&Client>) -> Result<Vec<Model>, Error> {
+    let response: Result<RootModel, Error> = requester::models(&options(client), None).await;
 
     match response {
         Ok(t) => Ok(t.data),
@@ -147,11 +460,35 @@ pub async fn list() -> Result<Vec<Model>, StatusCode> {
 }
 
 /// Return information for a specific model by its identifier
-pub async fn get(model_name: String) -> Result<Model, StatusCode> {
-    let response: Result<Model, StatusCode> = requester::models(Some(model_name)).await;
+pub async fn get(model_name: String) -> Result<Model, Error> {
+    get_with(None, model_name).await
+}
+
+pub(crate) async fn get_with(client: Option<&Client>, model_name: String) -> Result<Model, Error> {
+    let response: Result<Model, Error> = requester::models(&options(client), Some(model_name)).await;
 
     match response {
         Ok(t) => Ok(t),
         Err(e) => Err(e),
     }
-}
\ No newline at end of file
+}
+
+/// Whether a model with the identifier exists and is available to the API key
+pub async fn exists(model_name: &str) -> Result<bool, Error> {
+    exists_with(None, model_name).await
+}
+
+pub(crate) async fn exists_with(client: Option<&Client>, model_name: &str) -> Result<bool, Error> {
+    match get_with(client, model_name.to_string()).await {
+        Ok(_) => Ok(true),
+        Err(e) if e.status() == Some(StatusCode::NOT_FOUND) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn options(client: Option<&Client>) -> RequestOptions {
+    RequestOptions {
+        client: client.cloned(),
+        ..RequestOptions::default()
+    }
+}
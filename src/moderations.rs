@@ -0,0 +1,179 @@
+use crate::client::{Client, Timing};
+use crate::error::Error;
+use crate::models::ModerationModels;
+use crate::requester::{self, Query, RequestOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Instant;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModerationResult {
+    pub flagged: bool,
+    pub categories: HashMap<String, bool>,
+    pub category_scores: HashMap<String, f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Moderation {
+    #[serde(default)]
+    pub id: String,
+    #[serde(alias = "engine")]
+    pub model: String,
+    pub results: Vec<ModerationResult>,
+    /// How long the request took, not part of the API's response
+    #[serde(skip)]
+    pub timing: Option<Timing>,
+}
+
+impl Moderation {
+    /// Whether any of the inputs were flagged
+    pub fn flagged(&self) -> bool {
+        self.results.iter().any(|r| r.flagged)
+    }
+
+    /// The categories flagged for any of the inputs, sorted and without duplicates
+    pub fn flagged_categories(&self) -> Vec<String> {
+        let mut categories: Vec<String> = self.results.iter()
+            .flat_map(|r| r.categories.iter())
+            .filter(|(_, flagged)| **flagged)
+            .map(|(category, _)| category.clone())
+            .collect();
+        categories.sort();
+        categories.dedup();
+        categories
+    }
+}
+
+/// Available parameters that can be sent with a moderation request
+pub struct Parameters<'a> {
+    options: RequestOptions,
+    query: Query<'a>,
+}
+
+/// Function to create a moderation request
+///
+/// Call it using [`build`] and add valid [`Parameters`] to the request to build a
+/// moderations request and close with `moderate()`.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::moderations;
+///
+/// async {
+///     let moderation = moderations::build()
+///         .input("I want to eat all of the cookies")
+///         .moderate()
+///         .await
+///         .expect("Error Getting Response");
+///
+///     println!("{:?}", moderation.flagged());
+/// };
+/// ```
+pub fn build<'a>() -> Parameters<'a> {
+    Parameters {
+        options: RequestOptions::default(),
+        query: Query::new(),
+    }
+}
+
+impl<'a> Parameters<'a> {
+    /// Send the request using the given client instead of one loaded from the environment
+    pub fn client(mut self, client: &Client) -> Self {
+        self.options.client = Some(client.clone());
+        self
+    }
+
+    /// Set a parameter this crate doesn't model yet, sent as is in the request body
+    ///
+    /// Replaces the value set for `key` by earlier setters, and is replaced by later ones.
+    pub fn extra_param(mut self, key: &'a str, value: Value) -> Self {
+        self.query.push((key, value));
+        self
+    }
+
+    /// The text to classify, encoded as a string.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/moderations/create#moderations-create-input)
+    pub fn input(mut self, input: &'a str) -> Self {
+        self.query.push(("input", json!(input)));
+        self
+    }
+
+    /// The texts to classify, each is classified separately.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/moderations/create#moderations-create-input)
+    pub fn inputs(mut self, input: &'a Vec<&str>) -> Self {
+        self.query.push(("input", json!(input)));
+        self
+    }
+
+    /// The moderation model to use, defaults to `omni-moderation-latest`.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/moderations/create#moderations-create-model)
+    pub fn model<M: Into<ModerationModels>>(mut self, input: M) -> Self {
+        self.query.push(("model", json!(input.into().as_str())));
+        self
+    }
+
+    /// Complete the request and send
+    pub async fn moderate(self) -> Result<Moderation, Error> {
+        let started = Instant::now();
+        let response: Result<Moderation, Error> = requester::moderations(&self.options, &self.query).await;
+
+        match response {
+            Ok(mut t) => {
+                t.timing = Some(Timing::since(started));
+                Ok(t)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Run the prompt through the moderations endpoint first when the client has
+/// [`moderate_inputs`](Client::moderate_inputs) enabled
+pub(crate) async fn preflight(options: &RequestOptions, input: Option<&Value>) -> Result<(), Error> {
+    let input = match input {
+        Some(input) => input,
+        None => return Ok(()),
+    };
+    let client = options.client()?;
+    if !client.moderate_inputs {
+        return Ok(());
+    }
+
+    let mut query = Query::new();
+    query.push(("input", input.clone()));
+    let options = RequestOptions {
+        client: Some(client.clone()),
+        ..RequestOptions::default()
+    };
+    let moderation: Moderation = requester::moderations(&options, &query).await?;
+    if moderation.flagged() {
+        return Err(Error::ContentFlagged(moderation.flagged_categories()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::build;
+    use crate::models::ModerationModels;
+    use crate::testing::{assert_snapshot, golden, TestClient};
+
+    #[tokio::test]
+    async fn parameters_use_wire_keys() {
+        let test = TestClient::new();
+        let inputs = vec!["I want to hug them", "I want to kill them"];
+        let _ = build()
+            .client(&test.client())
+            .inputs(&inputs)
+            .model(ModerationModels::OMNI_MODERATION_LATEST)
+            .moderate()
+            .await;
+
+        assert_snapshot(golden("moderations"), &test.last_request());
+    }
+}
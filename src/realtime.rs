@@ -0,0 +1,478 @@
+//! Events of the Realtime API, sent and received as JSON text frames over a WebSocket
+//!
+//! The crate doesn't open the WebSocket, use any WebSocket client and send
+//! [`ClientEvent::to_frame`] and parse received text frames with [`ServerEvent::from_frame`].
+//!
+//! [OpenAI Reference](https://platform.openai.com/docs/api-reference/realtime)
+use crate::chat::{Modality, Role};
+use crate::error::Error;
+use crate::tools::FunctionDefinition;
+use crate::usage::Usage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Encoding of audio sent to and received from the Realtime API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioFormat {
+    /// 16-bit PCM at 24kHz, mono, little-endian
+    Pcm16,
+    G711Ulaw,
+    G711Alaw,
+}
+
+/// How the server detects when the user starts and stops speaking
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TurnDetection {
+    ServerVad {
+        /// Between 0 and 1, higher requires louder audio to count as speech
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        threshold: Option<f32>,
+        /// Audio to include before the detected start of speech
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        prefix_padding_ms: Option<u32>,
+        /// Silence after which speech is considered stopped
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        silence_duration_ms: Option<u32>,
+    },
+}
+
+/// The model used to transcribe input audio
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputAudioTranscription {
+    pub model: String,
+}
+
+/// A function the model may call during a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RealtimeTool {
+    /// Always `function`
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(flatten)]
+    pub function: FunctionDefinition,
+}
+
+impl From<FunctionDefinition> for RealtimeTool {
+    fn from(function: FunctionDefinition) -> Self {
+        RealtimeTool {
+            kind: String::from("function"),
+            function,
+        }
+    }
+}
+
+/// Configuration of a session, every field left as `None` keeps its current value
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    /// Only set by the server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modalities: Option<Vec<Modality>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+    /// e.g. `alloy`, can't be changed once the model answered with audio
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voice: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_audio_format: Option<AudioFormat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_audio_format: Option<AudioFormat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_audio_transcription: Option<InputAudioTranscription>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turn_detection: Option<TurnDetection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<RealtimeTool>>,
+    /// `auto`, `none`, `required`, or `{"type": "function", "name": ...}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// A number of tokens, or `inf`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_response_output_tokens: Option<Value>,
+}
+
+/// Configuration of a single response, overriding the session's for that response
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResponseConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modalities: Option<Vec<Modality>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voice: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_audio_format: Option<AudioFormat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<RealtimeTool>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_response_output_tokens: Option<Value>,
+}
+
+/// A part of the content of a conversation item
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ItemContent {
+    InputText { text: String },
+    InputAudio {
+        /// Base64 encoded audio
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        audio: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        transcript: Option<String>,
+    },
+    /// A reference to another item, e.g. an item of an earlier response
+    ItemReference { id: String },
+    Text { text: String },
+    Audio {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        audio: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        transcript: Option<String>,
+    },
+}
+
+/// An item of a conversation, a message, a function call or a function call's output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Item {
+    Message {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        role: Role,
+        content: Vec<ItemContent>,
+        /// `completed`, `in_progress` or `incomplete`, only set by the server
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        status: Option<String>,
+    },
+    FunctionCall {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        call_id: String,
+        name: String,
+        /// JSON encoded arguments
+        arguments: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        status: Option<String>,
+    },
+    FunctionCallOutput {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        call_id: String,
+        output: String,
+    },
+}
+
+impl Item {
+    /// A user message with text content
+    pub fn user_text<S: Into<String>>(text: S) -> Self {
+        Item::Message {
+            id: None,
+            role: Role::User,
+            content: vec![ItemContent::InputText { text: text.into() }],
+            status: None,
+        }
+    }
+
+    /// The output of the function call `call_id`
+    pub fn function_output<S: Into<String>, O: Into<String>>(call_id: S, output: O) -> Self {
+        Item::FunctionCallOutput {
+            id: None,
+            call_id: call_id.into(),
+            output: output.into(),
+        }
+    }
+}
+
+/// A response of the model, made up of output items
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response {
+    pub id: String,
+    /// `in_progress`, `completed`, `cancelled`, `incomplete` or `failed`
+    pub status: String,
+    /// Why the response was cancelled, incomplete or failed
+    #[serde(default)]
+    pub status_details: Option<Value>,
+    #[serde(default)]
+    pub output: Vec<Item>,
+    /// Only set once the response is done
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+/// The conversation of a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: String,
+}
+
+/// An error reported by the server, the session stays open
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RealtimeError {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub code: Option<String>,
+    pub message: String,
+    #[serde(default)]
+    pub param: Option<String>,
+    /// The ID of the client event that caused the error
+    #[serde(default)]
+    pub event_id: Option<String>,
+}
+
+/// A rate limit of the session, as of the last response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// `requests` or `tokens`
+    pub name: String,
+    pub limit: u64,
+    pub remaining: u64,
+    pub reset_seconds: f64,
+}
+
+/// An event sent by the client
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::realtime::{ClientEvent, Item, Session};
+///
+/// let update = ClientEvent::SessionUpdate {
+///     session: Session { instructions: Some(String::from("Answer briefly.")), ..Session::default() },
+/// };
+/// let message = ClientEvent::ConversationItemCreate { previous_item_id: None, item: Item::user_text("Ice cream or cookies?") };
+/// let respond = ClientEvent::ResponseCreate { response: None };
+///
+/// assert_eq!(respond.to_frame(), r#"{"type":"response.create"}"#);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ClientEvent {
+    #[serde(rename = "session.update")]
+    SessionUpdate { session: Session },
+    /// Append base64 encoded audio to the input audio buffer
+    #[serde(rename = "input_audio_buffer.append")]
+    InputAudioBufferAppend { audio: String },
+    /// Commit the input audio buffer as a user message, not needed with server VAD
+    #[serde(rename = "input_audio_buffer.commit")]
+    InputAudioBufferCommit,
+    #[serde(rename = "input_audio_buffer.clear")]
+    InputAudioBufferClear,
+    #[serde(rename = "conversation.item.create")]
+    ConversationItemCreate {
+        /// Insert the item after this one, at the end of the conversation if `None`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        previous_item_id: Option<String>,
+        item: Item,
+    },
+    /// Cut an assistant message's audio short, e.g. when the user interrupted it
+    #[serde(rename = "conversation.item.truncate")]
+    ConversationItemTruncate {
+        item_id: String,
+        content_index: u32,
+        audio_end_ms: u32,
+    },
+    #[serde(rename = "conversation.item.delete")]
+    ConversationItemDelete { item_id: String },
+    /// Have the model respond, needed unless server VAD responds on its own
+    #[serde(rename = "response.create")]
+    ResponseCreate {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        response: Option<ResponseConfig>,
+    },
+    #[serde(rename = "response.cancel")]
+    ResponseCancel,
+}
+
+impl ClientEvent {
+    /// The event as the text of a WebSocket frame
+    pub fn to_frame(&self) -> String {
+        serde_json::to_string(self).expect("client events always serialize")
+    }
+}
+
+/// An event sent by the server
+///
+/// Events added to the API after this version of the crate are parsed as
+/// [`Unknown`](ServerEvent::Unknown).
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::realtime::ServerEvent;
+///
+/// let frame = r#"{"event_id": "event_1", "type": "response.text.delta", "response_id": "resp_1", "item_id": "item_1", "output_index": 0, "content_index": 0, "delta": "Ice"}"#;
+/// match ServerEvent::from_frame(frame).unwrap() {
+///     ServerEvent::ResponseTextDelta { delta, .. } => assert_eq!(delta, "Ice"),
+///     other => panic!("unexpected event {:?}", other),
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ServerEvent {
+    #[serde(rename = "error")]
+    Error { error: RealtimeError },
+    #[serde(rename = "session.created")]
+    SessionCreated { session: Session },
+    #[serde(rename = "session.updated")]
+    SessionUpdated { session: Session },
+    #[serde(rename = "conversation.created")]
+    ConversationCreated { conversation: Conversation },
+    #[serde(rename = "conversation.item.created")]
+    ConversationItemCreated {
+        #[serde(default)]
+        previous_item_id: Option<String>,
+        item: Item,
+    },
+    #[serde(rename = "conversation.item.input_audio_transcription.completed")]
+    InputAudioTranscriptionCompleted {
+        item_id: String,
+        content_index: u32,
+        transcript: String,
+    },
+    #[serde(rename = "conversation.item.input_audio_transcription.failed")]
+    InputAudioTranscriptionFailed {
+        item_id: String,
+        content_index: u32,
+        error: RealtimeError,
+    },
+    #[serde(rename = "conversation.item.truncated")]
+    ConversationItemTruncated {
+        item_id: String,
+        content_index: u32,
+        audio_end_ms: u32,
+    },
+    #[serde(rename = "conversation.item.deleted")]
+    ConversationItemDeleted { item_id: String },
+    #[serde(rename = "input_audio_buffer.committed")]
+    InputAudioBufferCommitted {
+        #[serde(default)]
+        previous_item_id: Option<String>,
+        item_id: String,
+    },
+    #[serde(rename = "input_audio_buffer.cleared")]
+    InputAudioBufferCleared,
+    #[serde(rename = "input_audio_buffer.speech_started")]
+    InputAudioBufferSpeechStarted { audio_start_ms: u32, item_id: String },
+    #[serde(rename = "input_audio_buffer.speech_stopped")]
+    InputAudioBufferSpeechStopped { audio_end_ms: u32, item_id: String },
+    #[serde(rename = "response.created")]
+    ResponseCreated { response: Response },
+    #[serde(rename = "response.done")]
+    ResponseDone { response: Response },
+    #[serde(rename = "response.output_item.added")]
+    ResponseOutputItemAdded {
+        response_id: String,
+        output_index: u32,
+        item: Item,
+    },
+    #[serde(rename = "response.output_item.done")]
+    ResponseOutputItemDone {
+        response_id: String,
+        output_index: u32,
+        item: Item,
+    },
+    #[serde(rename = "response.content_part.added")]
+    ResponseContentPartAdded {
+        response_id: String,
+        item_id: String,
+        output_index: u32,
+        content_index: u32,
+        part: ItemContent,
+    },
+    #[serde(rename = "response.content_part.done")]
+    ResponseContentPartDone {
+        response_id: String,
+        item_id: String,
+        output_index: u32,
+        content_index: u32,
+        part: ItemContent,
+    },
+    #[serde(rename = "response.text.delta")]
+    ResponseTextDelta {
+        response_id: String,
+        item_id: String,
+        output_index: u32,
+        content_index: u32,
+        delta: String,
+    },
+    #[serde(rename = "response.text.done")]
+    ResponseTextDone {
+        response_id: String,
+        item_id: String,
+        output_index: u32,
+        content_index: u32,
+        text: String,
+    },
+    #[serde(rename = "response.audio_transcript.delta")]
+    ResponseAudioTranscriptDelta {
+        response_id: String,
+        item_id: String,
+        output_index: u32,
+        content_index: u32,
+        delta: String,
+    },
+    #[serde(rename = "response.audio_transcript.done")]
+    ResponseAudioTranscriptDone {
+        response_id: String,
+        item_id: String,
+        output_index: u32,
+        content_index: u32,
+        transcript: String,
+    },
+    /// Base64 encoded audio
+    #[serde(rename = "response.audio.delta")]
+    ResponseAudioDelta {
+        response_id: String,
+        item_id: String,
+        output_index: u32,
+        content_index: u32,
+        delta: String,
+    },
+    #[serde(rename = "response.audio.done")]
+    ResponseAudioDone {
+        response_id: String,
+        item_id: String,
+        output_index: u32,
+        content_index: u32,
+    },
+    #[serde(rename = "response.function_call_arguments.delta")]
+    ResponseFunctionCallArgumentsDelta {
+        response_id: String,
+        item_id: String,
+        output_index: u32,
+        call_id: String,
+        delta: String,
+    },
+    #[serde(rename = "response.function_call_arguments.done")]
+    ResponseFunctionCallArgumentsDone {
+        response_id: String,
+        item_id: String,
+        output_index: u32,
+        call_id: String,
+        arguments: String,
+    },
+    #[serde(rename = "rate_limits.updated")]
+    RateLimitsUpdated { rate_limits: Vec<RateLimit> },
+    /// An event this version of the crate doesn't know
+    #[serde(other)]
+    Unknown,
+}
+
+impl ServerEvent {
+    /// Parse the text of a WebSocket frame
+    pub fn from_frame(frame: &str) -> Result<Self, Error> {
+        serde_json::from_str(frame).map_err(|e| Error::deserialize(e, frame))
+    }
+}
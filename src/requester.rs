@@ -1,112 +1,857 @@
-use dotenv::dotenv;
-use std::env;
-use reqwest::StatusCode;
+use crate::budget::Budget;
+use crate::client::{self, Client};
+use crate::error::{ApiError, Error};
+use crate::retry::{self, RetryEvent};
+use crate::scheduler::Scheduler;
+use crate::stream::EventStream;
+use crate::telemetry;
+use crate::transport::TransportError;
+use crate::usage::{Usage, UsageTracker};
+use bytes::Bytes;
+use reqwest::header::AUTHORIZATION;
+use reqwest::multipart::{Form, Part};
+use reqwest::{Body, Method, Request, RequestBuilder, Response, StatusCode};
 use serde::de::DeserializeOwned;
-use serde_json::Value;
-use std::collections::HashMap;
+use serde::ser::{SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-const API_BASE_URL: &str = "https://api.openai.com";
-const API_VERSION: &str = "v1";
+pub(crate) const API_BASE_URL: &str = "https://api.openai.com";
+pub(crate) const API_VERSION: &str = "v1";
 
-async fn request<T>(method: String, url: String, body: Option<HashMap<&str, Value>>) -> Result<T, StatusCode>
+/// Endpoints that are part of the Assistants API and need the beta header
+const ASSISTANTS_PATHS: [&str; 3] = ["assistants", "threads", "vector_stores"];
+
+/// Endpoints whose responses are answered from the client's response cache
+const CACHED_PATHS: [&str; 5] = ["completions", "chat/completions", "edits", "embeddings", "moderations"];
+
+/// Generation endpoints the client's default parameters are sent to, other endpoints reject
+/// parameters like `temperature` or `store` they don't know
+const DEFAULTED_PATHS: [&str; 3] = ["completions", "chat/completions", "responses"];
+
+/// Per-request options shared by all of the request builders
+#[derive(Clone, Default)]
+pub(crate) struct RequestOptions {
+    pub client: Option<Client>,
+    pub idempotent: bool,
+}
+
+impl RequestOptions {
+    /// The client set on the request, or the global client
+    pub fn client(&self) -> Result<&Client, Error> {
+        match &self.client {
+            Some(c) => Ok(c),
+            None => client::global(),
+        }
+    }
+}
+
+/// Parameters of a request body, serialized directly as a JSON object
+#[derive(Clone, Default)]
+pub(crate) struct Query<'a>(Vec<(&'a str, Value)>);
+
+impl<'a> Query<'a> {
+    pub fn new() -> Self {
+        Query(Vec::new())
+    }
+
+    /// Set a parameter, replacing any value previously set for the same key
+    pub fn push(&mut self, (key, value): (&'a str, Value)) {
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.0.push((key, value)),
+        }
+    }
+
+    /// Add the entries of `object` to the object set for a parameter, setting it if unset
+    pub fn merge(&mut self, key: &'a str, object: Map<String, Value>) {
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, Value::Object(existing))) => existing.extend(object),
+            Some(entry) => entry.1 = Value::Object(object),
+            None => self.0.push((key, Value::Object(object))),
+        }
+    }
+
+    /// The value set for a parameter
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.0.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+}
+
+impl Serialize for Query<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (k, v) in &self.0 {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+/// Copy of the arguments with `user` set to the client's end user, if the client has one
+/// and the arguments don't already set `user`
+fn tag_user<'q>(options: &RequestOptions, arguments: &Query<'q>) -> Result<Option<Query<'q>>, Error> {
+    let client = options.client()?;
+    match client.end_user() {
+        Some(user) if arguments.get("user").is_none() => {
+            let mut tagged = arguments.clone();
+            tagged.push(("user", Value::String(user)));
+            Ok(Some(tagged))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Check sampling parameters are within the ranges accepted by the API, so an out of range
+/// value fails locally with a description instead of a `400 Bad Request`
+fn validate(arguments: &Query<'_>) -> Result<(), Error> {
+    let ranges = [
+        ("presence_penalty", -2.0, 2.0),
+        ("frequency_penalty", -2.0, 2.0),
+        ("top_p", 0.0, 1.0),
+    ];
+    for (key, min, max) in ranges {
+        if let Some(value) = arguments.get(key).and_then(Value::as_f64) {
+            if !(min..=max).contains(&value) {
+                return Err(Error::InvalidParameter(format!("{} must be between {} and {}, got {}", key, min, max, value)));
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn request<T, B>(options: &RequestOptions, method: Method, path: Cow<'static, str>, body: Option<&B>) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+    B: Serialize + ?Sized
+{
+    let client = options.client()?;
+    let defaults = defaults(client, &path);
+    let cache = match (&client.response_cache, body) {
+        (Some(cache), Some(body)) if method == Method::POST && CACHED_PATHS.contains(&&*path) => {
+            let key = cache_key(client, &path, body, defaults)?;
+            if let Some(cached) = cache.get(&key) {
+                return serde_json::from_str::<T>(&cached).map_err(|e| Error::deserialize(e, &cached));
+            }
+            Some((cache.clone(), key))
+        }
+        _ => None,
+    };
+
+    match cache {
+        Some((cache, key)) => send(options, method, path, json(body, defaults), |r, client| {
+            let usage = UsageSink::of(client);
+            async move {
+                let body = r.text().await.map_err(Error::from_reqwest)?;
+                let parsed = usage.parse(&body)?;
+                cache.put(&key, &body);
+                Ok(parsed)
+            }
+        }).await,
+        None => send(options, method, path, json(body, defaults), parse).await,
+    }
+}
+
+/// The client's default parameters, if they are sent to the endpoint at `path`
+fn defaults<'c>(client: &'c Client, path: &str) -> Option<&'c Map<String, Value>> {
+    DEFAULTED_PATHS.contains(&path).then_some(&*client.default_params)
+}
+
+/// The key a request's response is cached under, its path and the SHA-256 checksum of its body,
+/// the default parameters sent with it and who the request is sent to and on behalf of, so clients
+/// of different accounts sharing a cache never see each other's responses
+fn cache_key<B>(client: &Client, path: &str, body: &B, defaults: Option<&Map<String, Value>>) -> Result<String, Error>
+where
+    B: Serialize + ?Sized
+{
+    let body = serde_json::to_vec(body).map_err(|e| Error::InvalidParameter(e.to_string()))?;
+    let mut hasher = Sha256::new();
+    hasher.update(&body);
+    if let Some(defaults) = defaults.filter(|d| !d.is_empty()) {
+        hasher.update(serde_json::to_vec(defaults).map_err(|e| Error::InvalidParameter(e.to_string()))?);
+    }
+    let tenant = [
+        Some(&*client.api_key),
+        client.organization.as_deref(),
+        client.project.as_deref(),
+        Some(&*client.base_url),
+    ];
+    for part in tenant {
+        // Separated by a byte that can't appear in the parts, so their boundaries can't shift
+        hasher.update([0xff]);
+        hasher.update(part.unwrap_or_default().as_bytes());
+    }
+    let digest = hasher.finalize();
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("{}/{}", path, hex))
+}
+
+/// Sends a multipart form built by `form` for each attempt
+async fn multipart<T, M>(options: &RequestOptions, path: Cow<'static, str>, form: M) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+    M: Fn() -> Form
+{
+    send(options, Method::POST, path, |builder| builder.multipart(form()), parse).await
+}
+
+/// Deserializes a response body, recording any usage it reports to the client's budget and tracker
+fn parse<T>(r: Response, client: &Client) -> impl Future<Output = Result<T, Error>>
 where
     T: DeserializeOwned
 {
-    dotenv().ok();
+    let usage = UsageSink::of(client);
+    async move {
+        let body = r.text().await.map_err(Error::from_reqwest)?;
+        usage.parse(&body)
+    }
+}
 
-    let api_key = env::var("OPENAI_API_KEY").expect("Please define openai api key");
+/// Sends a request without a body and deserializes the response without recording its usage,
+/// for requests on existing objects, e.g. reading back a stored completion whose usage was
+/// recorded when it was created
+async fn fetch<T>(options: &RequestOptions, method: Method, path: Cow<'static, str>) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    send(options, method, path, json(None::<&Value>, None), |r, _| async move {
+        let body = r.text().await.map_err(Error::from_reqwest)?;
+        serde_json::from_str::<T>(&body).map_err(|e| Error::deserialize(e, &body))
+    }).await
+}
 
-    let response;
-    if method == "POST" {
-        let client = reqwest::Client::new();
-        response = client.post(url)
-            .header("Content-type", "application/json")
-            .header("Authorization", "Bearer ".to_owned() + &api_key)
-            .json(&body)
-            .send()
-            .await;
-    } else {
-        // Assume GET
-        let client = reqwest::Client::new();
-        response = client.get(url)
-            .header("Content-type", "application/json")
-            .header("Authorization", "Bearer ".to_owned() + &api_key)
-            .send()
-            .await;
+/// Appends `query` to `path` as a percent-encoded query string
+fn with_query(mut path: String, query: &[(String, String)]) -> String {
+    let encode = |text: &str| {
+        text.bytes().map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            b => format!("%{:02X}", b),
+        }).collect::<String>()
+    };
+    for (i, (key, value)) in query.iter().enumerate() {
+        path.push(if i == 0 { '?' } else { '&' });
+        path.push_str(&encode(key));
+        path.push('=');
+        path.push_str(&encode(value));
+    }
+    path
+}
+
+/// Where the usage reported by a response is recorded, taken from the client that sent it
+pub(crate) struct UsageSink {
+    budget: Option<Budget>,
+    tracker: Option<UsageTracker>,
+    label: Option<Arc<str>>,
+    #[cfg(feature = "opentelemetry")]
+    metrics: Option<telemetry::Metrics>,
+}
+
+impl UsageSink {
+    fn of(client: &Client) -> Self {
+        UsageSink {
+            budget: client.budget.clone(),
+            tracker: client.usage_tracker.clone(),
+            label: client.label.clone(),
+            #[cfg(feature = "opentelemetry")]
+            metrics: client.metrics.clone(),
+        }
+    }
+
+    /// Whether the usage of responses is recorded anywhere
+    fn records(&self) -> bool {
+        #[cfg(feature = "opentelemetry")]
+        if self.metrics.is_some() {
+            return true;
+        }
+        self.budget.is_some() || self.tracker.is_some() || telemetry::RECORDS_USAGE
     }
 
-    match &response {
+    /// Deserialize a response body or stream chunk, recording any usage it reports
+    pub(crate) fn parse<T>(&self, body: &str) -> Result<T, Error>
+    where
+        T: DeserializeOwned
+    {
+        if !self.records() {
+            return serde_json::from_str::<T>(body).map_err(|e| Error::deserialize(e, body));
+        }
+        let value = serde_json::from_str::<Value>(body).map_err(|e| Error::deserialize(e, body))?;
+        // Streamed Responses API events report usage on the response they carry
+        let reported = value.get("response").filter(|r| r.is_object()).unwrap_or(&value);
+        let model = reported.get("model").and_then(Value::as_str);
+        let usage = reported.get("usage").and_then(|u| Usage::deserialize(u).ok());
+        telemetry::record_usage(model, usage.as_ref());
+        if let Some(usage) = usage {
+            #[cfg(feature = "opentelemetry")]
+            if let Some(metrics) = &self.metrics {
+                metrics.record_tokens(model, &usage);
+            }
+            if let Some(budget) = &self.budget {
+                budget.record(model, &usage);
+            }
+            if let Some(tracker) = &self.tracker {
+                tracker.record(self.label.as_deref(), model, &usage);
+            }
+        }
+        T::deserialize(value).map_err(|e| Error::deserialize(e, body))
+    }
+}
+
+async fn stream<T>(options: &RequestOptions, path: Cow<'static, str>, body: &Query<'_>) -> Result<EventStream<T>, Error>
+where
+    T: DeserializeOwned
+{
+    let client = options.client()?;
+    let started = tokio::time::Instant::now();
+    let defaults = defaults(client, &path);
+    send(options, Method::POST, path, json(Some(body), defaults), |r, client| {
+        let idle_timeout = client.stream_idle_timeout;
+        let usage = UsageSink::of(client);
+        async move { Ok(EventStream::new(r, idle_timeout, started).record_usage(usage)) }
+    }).await
+}
+
+/// Attaches `body` as JSON to each attempt of a request, along with any of the client's
+/// `defaults` the body doesn't set itself
+fn json<'b, B>(body: Option<&'b B>, defaults: Option<&'b Map<String, Value>>) -> impl Fn(RequestBuilder) -> RequestBuilder + 'b
+where
+    B: Serialize + ?Sized
+{
+    move |builder| {
+        let builder = builder.header("Content-type", "application/json");
+        match (body, defaults) {
+            (Some(body), Some(defaults)) if !defaults.is_empty() => match serde_json::to_value(body) {
+                Ok(Value::Object(mut object)) => {
+                    for (key, value) in defaults {
+                        object.entry(key.clone()).or_insert_with(|| value.clone());
+                    }
+                    builder.json(&object)
+                }
+                _ => builder.json(body),
+            },
+            (Some(body), _) => builder.json(body),
+            (None, _) => builder,
+        }
+    }
+}
+
+/// Sends the request, retrying as configured on the client, and passes any successful
+/// response on to `handle`
+///
+/// `attach` adds the body to each attempt, so bodies that can only be sent once (e.g.
+/// multipart forms) are built again for every retry.
+async fn send<T, A, F, Fut>(options: &RequestOptions, method: Method, path: Cow<'static, str>, attach: A, handle: F) -> Result<T, Error>
+where
+    A: Fn(RequestBuilder) -> RequestBuilder,
+    F: FnOnce(Response, &Client) -> Fut,
+    Fut: Future<Output = Result<T, Error>>
+{
+    let (span_method, span_path) = (method.clone(), path.clone());
+    let correlation_id = correlation_id();
+    #[cfg(feature = "opentelemetry")]
+    let started = std::time::Instant::now();
+    let attempts = send_attempts(options, method, path, &correlation_id, attach, handle);
+    let result = telemetry::instrument(attempts, &span_method, &span_path, &correlation_id).await;
+    #[cfg(feature = "opentelemetry")]
+    if let Some(metrics) = options.client().ok().and_then(|c| c.metrics.as_ref()) {
+        metrics.record_request(&span_path, &result, started.elapsed());
+    }
+    result
+}
+
+async fn send_attempts<T, A, F, Fut>(options: &RequestOptions, method: Method, path: Cow<'static, str>, correlation_id: &str, attach: A, handle: F) -> Result<T, Error>
+where
+    A: Fn(RequestBuilder) -> RequestBuilder,
+    F: FnOnce(Response, &Client) -> Fut,
+    Fut: Future<Output = Result<T, Error>>
+{
+    let client = options.client()?;
+
+    let beta = ASSISTANTS_PATHS.iter().any(|p| path.starts_with(p));
+    let mut url = client.url(path.clone())?;
+    let mut failovers = 0;
+    let policy = &client.retry_policy;
+    let retryable = method == Method::GET || policy.allows(options.idempotent);
+
+    let scheduler = client.scheduler.as_ref();
+    let mut ticket = scheduler.map(Scheduler::submit);
+
+    let mut attempt = 0;
+    let mut requeues = 0;
+    let response = loop {
+        if let (Some(scheduler), Some(ticket)) = (scheduler, ticket.as_mut()) {
+            ticket.dispatch().await;
+            // Retries keep their slot, but still wait out a pause caused by other requests
+            scheduler.ready().await;
+        }
+        if let Some(breaker) = &client.circuit_breaker {
+            if !breaker.admit() {
+                return Err(Error::CircuitOpen);
+            }
+        }
+        if let Some(budget) = &client.budget {
+            budget.check()?;
+        }
+
+        let (key_index, api_key) = client.api_key();
+        let mut builder = client.http.request(method.clone(), url.clone())
+            .header(AUTHORIZATION, client.authorization(&api_key))
+            .header("X-Client-Request-Id", correlation_id);
+        if let Some(organization) = &client.organization {
+            builder = builder.header("OpenAI-Organization", &**organization);
+        }
+        if let Some(project) = &client.project {
+            builder = builder.header("OpenAI-Project", &**project);
+        }
+        if beta {
+            builder = builder.header("OpenAI-Beta", "assistants=v2");
+        }
+        builder = attach(builder);
+
+        let response = match builder.build() {
+            Ok(request) => {
+                if client.dump_requests {
+                    dump_request(&request);
+                }
+                match &client.transport {
+                    Some(transport) => transport.execute(request).await,
+                    None => client.http.execute(request).await.map_err(TransportError::from),
+                }
+            }
+            Err(e) => Err(TransportError::from(e)),
+        };
+        if let Some(breaker) = &client.circuit_breaker {
+            match &response {
+                Ok(r) if r.status().is_server_error() => breaker.record_failure(),
+                Ok(_) => breaker.record_success(),
+                Err(e) if e.is_timeout() || e.is_connect() => breaker.record_failure(),
+                // Never reached the upstream, e.g. the request couldn't be built, so says
+                // nothing about its health
+                Err(_) => breaker.release(),
+            }
+        }
+
+        if let Err(e) = &response {
+            if e.is_connect() {
+                if let Some(failover) = client.failover_url(failovers, &path) {
+                    url = failover?;
+                    failovers += 1;
+                    continue;
+                }
+            }
+        }
+        if let (Some(pool), Some(index), Ok(r)) = (&client.key_pool, key_index, &response) {
+            if r.status() == StatusCode::TOO_MANY_REQUESTS {
+                pool.record_throttled(index);
+            }
+        }
+        if let (Some(scheduler), Some(ticket), Ok(r)) = (scheduler, ticket.as_mut(), &response) {
+            if r.status() == StatusCode::TOO_MANY_REQUESTS && requeues < scheduler.max_requeues {
+                ticket.requeue(r.headers());
+                requeues += 1;
+                continue;
+            }
+        }
+
+        let reason = match retry::retry_reason(&response) {
+            Some(reason) if retryable && attempt < policy.max_retries => reason,
+            _ => break response,
+        };
+        let wait = policy.delay(attempt);
+        attempt += 1;
+        if let Some(hook) = &policy.on_retry {
+            hook(&RetryEvent { path: url.path(), attempt, wait, reason, correlation_id });
+        }
+        tokio::time::sleep(wait).await;
+    };
+    telemetry::record_response(response.as_ref().ok().map(Response::status), attempt);
+
+    match response {
         Ok(r) => {
-            println!("{:?}", r.status());
+            if client.dump_requests {
+                println!("{:?}", r.status());
+            }
             if r.status() != StatusCode::OK {
-                return Err(r.status());
+                let status = r.status();
+                let body = r.text().await.unwrap_or_default();
+                Err(Error::Api(ApiError::from_body(status, &body)))
             } else {
-                let content = response.unwrap().json::<T>().await;
-                match content {
-                    Ok(s) => Ok(s),
-                    Err(e) => {
-                        println!("{:?}", e);
-                        Err(StatusCode::BAD_REQUEST)
-                    }
-                }
+                handle(r, client).await
             }
         }
         Err(e) => {
-            println!("{} - {:?}", e.is_status(), e.status());
-            if e.is_status() {
-                return Err(e.status().unwrap());
-            } else {
-                return Err(StatusCode::BAD_REQUEST);
+            if client.dump_requests {
+                println!("{}", e);
             }
+            Err(Error::from_transport(e))
         }
     }
 }
 
+/// A new ID for a request, UUID-formatted and unique per process
+///
+/// Derived from the time, the process ID and a counter rather than a random number
+/// generator, so IDs aren't unpredictable, only distinct, and no dependency is pulled in.
+fn correlation_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+    let mut hasher = Sha256::new();
+    hasher.update(nanos.to_le_bytes());
+    hasher.update(std::process::id().to_le_bytes());
+    hasher.update(COUNTER.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+    let mut bytes: [u8; 16] = hasher.finalize()[..16].try_into().expect("digest is longer than 16 bytes");
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// Prints the request line and headers, the `Authorization` header is always redacted
+fn dump_request(request: &Request) {
+    println!("{} {}", request.method(), request.url());
+    for (name, value) in request.headers() {
+        if name == AUTHORIZATION {
+            println!("{}: [redacted]", name);
+        } else {
+            println!("{}: {:?}", name, value);
+        }
+    }
+}
+
+/// Handles a request for any endpoint, with the body and response left as JSON
+#[cfg(feature = "tower")]
+pub async fn raw(options: &RequestOptions, method: Method, path: String, body: Option<Value>) -> Result<Value, Error> {
+    request(options, method, path.into(), body.as_ref()).await
+}
+
 /// Handles requests for the `/completions` endpoint
-pub async fn completions<T>(arguments: HashMap<&str, Value>) -> Result<T, StatusCode>
+pub async fn completions<T>(options: &RequestOptions, arguments: &Query<'_>) -> Result<T, Error>
 where
     T: DeserializeOwned
 {
-    let url = format!("{}/{}/completions", API_BASE_URL, API_VERSION);
-    request(String::from("POST"), url, Some(arguments)).await
+    validate(arguments)?;
+    let tagged = tag_user(options, arguments)?;
+    request(options, Method::POST, "completions".into(), Some(tagged.as_ref().unwrap_or(arguments))).await
+}
+
+/// Handles streamed requests for the `/completions` endpoint
+pub async fn completions_stream<T>(options: &RequestOptions, arguments: &Query<'_>) -> Result<EventStream<T>, Error>
+where
+    T: DeserializeOwned
+{
+    validate(arguments)?;
+    let tagged = tag_user(options, arguments)?;
+    stream(options, "completions".into(), tagged.as_ref().unwrap_or(arguments)).await
+}
+
+/// Handles requests for the `/chat/completions` endpoint
+pub async fn chat<T>(options: &RequestOptions, arguments: &Query<'_>) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    validate(arguments)?;
+    let tagged = tag_user(options, arguments)?;
+    request(options, Method::POST, "chat/completions".into(), Some(tagged.as_ref().unwrap_or(arguments))).await
+}
+
+/// Handles streamed requests for the `/chat/completions` endpoint
+pub async fn chat_stream<T>(options: &RequestOptions, arguments: &Query<'_>) -> Result<EventStream<T>, Error>
+where
+    T: DeserializeOwned
+{
+    validate(arguments)?;
+    let tagged = tag_user(options, arguments)?;
+    stream(options, "chat/completions".into(), tagged.as_ref().unwrap_or(arguments)).await
+}
+
+/// Handles GET and DELETE requests for the `/chat/completions/{completion_id}` endpoint
+pub async fn chat_completion<T>(options: &RequestOptions, method: Method, completion_id: &str) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    fetch(options, method, format!("chat/completions/{}", completion_id).into()).await
+}
+
+/// Handles list requests for the `/chat/completions` endpoint
+pub async fn chat_completions_list<T>(options: &RequestOptions, query: &[(String, String)]) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    fetch(options, Method::GET, with_query(String::from("chat/completions"), query).into()).await
+}
+
+/// Handles requests for the `/chat/completions/{completion_id}/messages` endpoint
+pub async fn chat_completion_messages<T>(options: &RequestOptions, completion_id: &str, query: &[(String, String)]) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    fetch(options, Method::GET, with_query(format!("chat/completions/{}/messages", completion_id), query).into()).await
+}
+
+/// Handles requests for the `/responses` endpoint
+pub async fn responses<T>(options: &RequestOptions, arguments: &Query<'_>) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    validate(arguments)?;
+    let tagged = tag_user(options, arguments)?;
+    request(options, Method::POST, "responses".into(), Some(tagged.as_ref().unwrap_or(arguments))).await
+}
+
+/// Handles streamed requests for the `/responses` endpoint
+pub async fn responses_stream<T>(options: &RequestOptions, arguments: &Query<'_>) -> Result<EventStream<T>, Error>
+where
+    T: DeserializeOwned
+{
+    validate(arguments)?;
+    let tagged = tag_user(options, arguments)?;
+    stream(options, "responses".into(), tagged.as_ref().unwrap_or(arguments)).await
+}
+
+/// Handles requests for the `/responses/{response_id}/input_items` endpoint
+pub async fn response_input_items<T>(options: &RequestOptions, response_id: &str, query: &[(String, String)]) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    fetch(options, Method::GET, with_query(format!("responses/{}/input_items", response_id), query).into()).await
+}
+
+/// Handles requests for the `/responses/{response_id}` endpoint, and its `action` endpoints
+/// e.g. `cancel`
+pub async fn response<T>(options: &RequestOptions, method: Method, response_id: &str, action: Option<&str>) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    let path = match action {
+        Some(action) => format!("responses/{}/{}", response_id, action),
+        None => format!("responses/{}", response_id),
+    };
+    fetch(options, method, path.into()).await
 }
 
 /// Handles requests for the `/edits` endpoint
-pub async fn edits<T>(arguments: HashMap<&str, Value>) -> Result<T, StatusCode>
+pub async fn edits<T>(options: &RequestOptions, arguments: &Query<'_>) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    validate(arguments)?;
+    request(options, Method::POST, "edits".into(), Some(arguments)).await
+}
+
+/// Handles requests for the `/embeddings` endpoint
+pub async fn embeddings<T>(options: &RequestOptions, arguments: &Query<'_>) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    let tagged = tag_user(options, arguments)?;
+    request(options, Method::POST, "embeddings".into(), Some(tagged.as_ref().unwrap_or(arguments))).await
+}
+
+/// Handles requests for the `/moderations` endpoint
+pub async fn moderations<T>(options: &RequestOptions, arguments: &Query<'_>) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    request(options, Method::POST, "moderations".into(), Some(arguments)).await
+}
+
+/// Handles requests for the `/assistants` endpoint
+pub async fn assistants<T>(options: &RequestOptions, arguments: &Query<'_>) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    request(options, Method::POST, "assistants".into(), Some(arguments)).await
+}
+
+/// Handles requests for the `/threads` endpoint
+pub async fn threads<T>(options: &RequestOptions, arguments: &Query<'_>) -> Result<T, Error>
 where
     T: DeserializeOwned
 {
-    let url = format!("{}/{}/edits", API_BASE_URL, API_VERSION);
-    request(String::from("POST"), url, Some(arguments)).await
+    request(options, Method::POST, "threads".into(), Some(arguments)).await
+}
+
+/// Handles requests for the `/threads/{thread_id}/messages` endpoint
+pub async fn thread_messages<T>(options: &RequestOptions, thread_id: &str, arguments: &Query<'_>) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    request(options, Method::POST, format!("threads/{}/messages", thread_id).into(), Some(arguments)).await
+}
+
+/// Handles requests for the `/threads/{thread_id}/runs` endpoint
+pub async fn thread_runs<T>(options: &RequestOptions, thread_id: &str, arguments: &Query<'_>) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    validate(arguments)?;
+    request(options, Method::POST, format!("threads/{}/runs", thread_id).into(), Some(arguments)).await
+}
+
+/// Handles requests for the `/threads/{thread_id}/runs/{run_id}/steps` endpoint
+pub async fn thread_run_steps<T>(options: &RequestOptions, thread_id: &str, run_id: &str, include: &[&str]) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    let mut path = format!("threads/{}/runs/{}/steps", thread_id, run_id);
+    for (i, field) in include.iter().enumerate() {
+        path.push(if i == 0 { '?' } else { '&' });
+        path.push_str("include%5B%5D=");
+        path.push_str(field);
+    }
+    request(options, Method::GET, path.into(), None::<&Value>).await
+}
+
+/// Handles requests for the `/vector_stores` endpoint
+pub async fn vector_stores<T>(options: &RequestOptions, arguments: &Query<'_>) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    request(options, Method::POST, "vector_stores".into(), Some(arguments)).await
+}
+
+/// Handles requests for the `/vector_stores/{vector_store_id}/files` endpoint
+pub async fn vector_store_files<T>(options: &RequestOptions, vector_store_id: &str, arguments: &Query<'_>) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    request(options, Method::POST, format!("vector_stores/{}/files", vector_store_id).into(), Some(arguments)).await
+}
+
+/// Handles requests for the `/fine_tuning/jobs/{job_id}` endpoint, and its `action`
+/// endpoints e.g. `pause`
+pub async fn fine_tuning_job<T>(options: &RequestOptions, method: Method, job_id: &str, action: Option<&str>) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    let path = match action {
+        Some(action) => format!("fine_tuning/jobs/{}/{}", job_id, action),
+        None => format!("fine_tuning/jobs/{}", job_id),
+    };
+    fetch(options, method, path.into()).await
+}
+
+/// Handles requests for the `/files` endpoint
+pub async fn files<T, M>(options: &RequestOptions, form: M) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+    M: Fn() -> Form
+{
+    multipart(options, "files".into(), form).await
+}
+
+/// Handles requests for the `/files/{file_id}/content` endpoint
+pub async fn file_content(options: &RequestOptions, file_id: &str) -> Result<Bytes, Error> {
+    send(options, Method::GET, format!("files/{}/content", file_id).into(), json(None::<&Value>, None), |r, _| async move {
+        r.bytes().await.map_err(Error::from_reqwest)
+    }).await
+}
+
+/// Handles requests for the `/uploads` endpoint
+pub async fn uploads<T>(options: &RequestOptions, arguments: &Query<'_>) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    request(options, Method::POST, "uploads".into(), Some(arguments)).await
+}
+
+/// Handles requests for the `/uploads/{upload_id}/parts` endpoint
+pub async fn upload_parts<T, M>(options: &RequestOptions, upload_id: &str, form: M) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+    M: Fn() -> Form
+{
+    multipart(options, format!("uploads/{}/parts", upload_id).into(), form).await
+}
+
+/// Handles requests for the `/uploads/{upload_id}/complete` endpoint
+pub async fn upload_complete<T>(options: &RequestOptions, upload_id: &str, arguments: &Query<'_>) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    request(options, Method::POST, format!("uploads/{}/complete", upload_id).into(), Some(arguments)).await
 }
 
-#[derive(strum_macros::Display)]
 pub enum ImageRequestType {
-    #[strum(serialize = "generations")]
     Generations,
-    #[strum(serialize = "edits")]
     Edits,
-    #[strum(serialize = "variations")]
     Variations
 }
 
+impl ImageRequestType {
+    fn path(&self) -> &'static str {
+        match self {
+            ImageRequestType::Generations => "images/generations",
+            ImageRequestType::Edits => "images/edits",
+            ImageRequestType::Variations => "images/variations",
+        }
+    }
+}
+
 /// Handles requests for the `/images` endpoint
-pub async fn images<T>(request_type: ImageRequestType, arguments: HashMap<&str, Value>) -> Result<T, StatusCode>
+pub async fn images<T>(options: &RequestOptions, request_type: ImageRequestType, arguments: &Query<'_>) -> Result<T, Error>
 where
 T: DeserializeOwned
 {
-    let url = format!("{}/{}/images/{}", API_BASE_URL, API_VERSION, request_type.to_string());
-    request(String::from("POST"), url, Some(arguments)).await
+    let tagged = tag_user(options, arguments)?;
+    request(options, Method::POST, request_type.path().into(), Some(tagged.as_ref().unwrap_or(arguments))).await
+}
+
+/// Handles streamed requests for the `/images` endpoints
+pub async fn images_stream<T>(options: &RequestOptions, request_type: ImageRequestType, arguments: &Query<'_>) -> Result<EventStream<T>, Error>
+where
+    T: DeserializeOwned
+{
+    let tagged = tag_user(options, arguments)?;
+    stream(options, request_type.path().into(), tagged.as_ref().unwrap_or(arguments)).await
+}
+
+/// Handles multipart requests for the `/images` endpoints, sending each of `images` as a PNG
+/// file part alongside the arguments
+pub async fn images_multipart<T>(options: &RequestOptions, request_type: ImageRequestType, arguments: &Query<'_>, images: &[(&'static str, Bytes)]) -> Result<T, Error>
+where
+    T: DeserializeOwned
+{
+    let tagged = tag_user(options, arguments)?;
+    let arguments = tagged.as_ref().unwrap_or(arguments);
+    let form = || {
+        let mut form = Form::new();
+        for (key, value) in &arguments.0 {
+            let text = match value {
+                Value::String(text) => text.clone(),
+                value => value.to_string(),
+            };
+            form = form.text(key.to_string(), text);
+        }
+        for (name, png) in images {
+            let part = Part::stream_with_length(Body::from(png.clone()), png.len() as u64)
+                .file_name(format!("{}.png", name))
+                .mime_str("image/png")
+                .expect("image/png is a valid MIME type");
+            form = form.part(*name, part);
+        }
+        form
+    };
+    multipart(options, request_type.path().into(), form).await
 }
 
 /// Handles requests for the `/models` endpoint
-pub async fn models<T>(model_name: Option<String>) -> Result<T, StatusCode>
+pub async fn models<T>(options: &RequestOptions, model_name: Option<String>) -> Result<T, Error>
 where
     T: DeserializeOwned
 {
-    let mut url = format!("{}/{}/models", API_BASE_URL, API_VERSION);
-    if model_name != None {
-        url = format!("{}/{}/models/{}", API_BASE_URL, API_VERSION, model_name.unwrap());
-    }
-    request(String::from("GET"), url, None).await
-}
\ No newline at end of file
+    let path = match model_name {
+        Some(name) => Cow::Owned(format!("models/{}", name)),
+        None => Cow::Borrowed("models"),
+    };
+    request(options, Method::GET, path, None::<&Value>).await
+}
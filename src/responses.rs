@@ -0,0 +1,1017 @@
+use crate::chat::{ChatMessage, Metadata, Page, Role};
+use crate::client::{Client, Timing};
+use crate::error::Error;
+use crate::models::ChatModels;
+use crate::moderations;
+use crate::requester::{self, Query, RequestOptions};
+use crate::schema::ResponseFormat;
+use crate::stream::EventStream;
+use crate::tools::{FileSearch, FunctionDefinition};
+use crate::usage::Usage;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Instant;
+
+/// The state of a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseStatus {
+    /// A background response waiting to be started
+    Queued,
+    InProgress,
+    Completed,
+    Failed,
+    Cancelled,
+    /// Stopped early, e.g. by `max_output_tokens`, see `incomplete_details`
+    Incomplete,
+    /// A status this version of the crate doesn't know
+    #[serde(other)]
+    Unknown,
+}
+
+impl ResponseStatus {
+    /// Whether the response won't change anymore
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, ResponseStatus::Queued | ResponseStatus::InProgress)
+    }
+}
+
+/// How much context `web_search_preview` gathers from the web, `medium` unless set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchContextSize {
+    Low,
+    Medium,
+    High,
+}
+
+/// The approximate location of the user, to localise web search results
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserLocation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Two letter ISO country code, e.g. `GB`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// IANA timezone, e.g. `Europe/London`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+/// A tool the model may use in a response, either a function or one of the built-in tools
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{models, responses};
+/// use oai_rs::responses::ResponseTool;
+///
+/// async {
+///     let tools = [ResponseTool::web_search()];
+///     let response = responses::build(models::ChatModels::GPT_4O)
+///         .input("What was a positive news story from today?")
+///         .tools(&tools)
+///         .create()
+///         .await
+///         .expect("Error Getting Response");
+///
+///     println!("{}", response.output_text());
+///     for citation in response.citations() {
+///         println!("{:?}", citation);
+///     }
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseTool {
+    Function {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        parameters: Value,
+    },
+    WebSearchPreview {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        search_context_size: Option<SearchContextSize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        user_location: Option<UserLocation>,
+    },
+    FileSearch {
+        vector_store_ids: Vec<String>,
+        #[serde(flatten)]
+        config: FileSearch,
+    },
+    ComputerUsePreview {
+        display_width: u32,
+        display_height: u32,
+        /// `browser`, `mac`, `windows` or `ubuntu`
+        environment: String,
+    },
+}
+
+impl ResponseTool {
+    /// The `web_search_preview` tool, letting the model search the web before answering
+    pub fn web_search() -> Self {
+        ResponseTool::WebSearchPreview { search_context_size: None, user_location: None }
+    }
+
+    /// The `file_search` tool, searching the files in the given vector stores
+    pub fn file_search<S: Into<String>>(vector_store_ids: impl IntoIterator<Item = S>, config: FileSearch) -> Self {
+        ResponseTool::FileSearch {
+            vector_store_ids: vector_store_ids.into_iter().map(Into::into).collect(),
+            config,
+        }
+    }
+
+    /// The `computer_use_preview` tool, controlling a `display_width` by `display_height`
+    /// `environment`, answered with [`OutputItem::ComputerCall`] actions to carry out
+    pub fn computer_use<S: Into<String>>(display_width: u32, display_height: u32, environment: S) -> Self {
+        ResponseTool::ComputerUsePreview { display_width, display_height, environment: environment.into() }
+    }
+}
+
+impl From<FunctionDefinition> for ResponseTool {
+    fn from(function: FunctionDefinition) -> Self {
+        ResponseTool::Function {
+            name: function.name,
+            description: function.description,
+            parameters: function.parameters,
+        }
+    }
+}
+
+/// A citation of a source in an output message's text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Annotation {
+    /// A web page found by `web_search_preview`, cited by `text[start_index..end_index]`
+    UrlCitation {
+        url: String,
+        #[serde(default)]
+        title: Option<String>,
+        start_index: usize,
+        end_index: usize,
+    },
+    /// A file found by `file_search`, cited at `index` in the text
+    FileCitation {
+        file_id: String,
+        #[serde(default)]
+        filename: Option<String>,
+        #[serde(default)]
+        index: Option<usize>,
+    },
+    FilePath {
+        file_id: String,
+        #[serde(default)]
+        index: Option<usize>,
+    },
+    /// An annotation this version of the crate doesn't know
+    #[serde(other)]
+    Unknown,
+}
+
+/// A chunk of a file found by `file_search`, only returned when
+/// [`include`](Parameters::include)ing `file_search_call.results`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSearchResult {
+    pub file_id: String,
+    #[serde(default)]
+    pub filename: Option<String>,
+    #[serde(default)]
+    pub score: Option<f32>,
+    pub text: String,
+    #[serde(default)]
+    pub attributes: Option<Value>,
+}
+
+/// A part of the content of an output message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputContent {
+    OutputText {
+        text: String,
+        #[serde(default)]
+        annotations: Vec<Annotation>,
+    },
+    Refusal { refusal: String },
+}
+
+/// A part of the content of an input message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputContent {
+    InputText { text: String },
+    InputImage {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        image_url: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        detail: Option<String>,
+    },
+    InputFile {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        filename: Option<String>,
+    },
+    /// The text of an earlier answer of the model
+    OutputText { text: String },
+    Refusal { refusal: String },
+}
+
+/// An item of the input of a stored response, see [`input_items`]
+///
+/// Items can be sent again with [`input_items`](Parameters::input_items) to replay the input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputItem {
+    Message {
+        #[serde(default, skip_serializing)]
+        id: Option<String>,
+        role: Role,
+        content: Vec<InputContent>,
+    },
+    FunctionCall {
+        #[serde(default, skip_serializing)]
+        id: Option<String>,
+        call_id: String,
+        name: String,
+        /// JSON encoded arguments
+        arguments: String,
+    },
+    /// The result of a function call, returned to the model
+    FunctionCallOutput {
+        #[serde(default, skip_serializing)]
+        id: Option<String>,
+        call_id: String,
+        output: String,
+    },
+    /// An input item this version of the crate doesn't know, left out when replaying the input
+    #[serde(other)]
+    Unknown,
+}
+
+/// An item the model output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputItem {
+    Message {
+        id: String,
+        role: Role,
+        content: Vec<OutputContent>,
+        #[serde(default)]
+        status: Option<String>,
+    },
+    FunctionCall {
+        #[serde(default)]
+        id: Option<String>,
+        call_id: String,
+        name: String,
+        /// JSON encoded arguments
+        arguments: String,
+        #[serde(default)]
+        status: Option<String>,
+    },
+    /// A search made by `web_search_preview`, its results are cited in the message after it
+    WebSearchCall {
+        id: String,
+        #[serde(default)]
+        status: Option<String>,
+    },
+    /// A search made by `file_search`
+    FileSearchCall {
+        id: String,
+        #[serde(default)]
+        status: Option<String>,
+        #[serde(default)]
+        queries: Vec<String>,
+        #[serde(default)]
+        results: Option<Vec<FileSearchResult>>,
+    },
+    /// An action for `computer_use_preview` to carry out, e.g. a click, answered with a
+    /// screenshot in the next request
+    ComputerCall {
+        id: String,
+        call_id: String,
+        action: Value,
+        #[serde(default)]
+        pending_safety_checks: Vec<Value>,
+        #[serde(default)]
+        status: Option<String>,
+    },
+    /// An output item this version of the crate doesn't know
+    #[serde(other)]
+    Unknown,
+}
+
+/// A response of the Responses API
+///
+/// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/object)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Response {
+    pub id: String,
+    #[serde(default)]
+    pub object: String,
+    pub created_at: u64,
+    pub status: ResponseStatus,
+    pub model: String,
+    #[serde(default)]
+    pub output: Vec<OutputItem>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+    /// Why the response failed
+    #[serde(default)]
+    pub error: Option<Value>,
+    /// Why the response is incomplete
+    #[serde(default)]
+    pub incomplete_details: Option<Value>,
+    #[serde(default)]
+    pub background: Option<bool>,
+    /// The response this one continues, see [`previous_response_id`](Parameters::previous_response_id)
+    #[serde(default)]
+    pub previous_response_id: Option<String>,
+    /// How long the request took, not part of the API's response
+    #[serde(skip)]
+    pub timing: Option<Timing>,
+}
+
+impl Response {
+    /// The text of every output message, joined together
+    pub fn output_text(&self) -> String {
+        self.output.iter()
+            .filter_map(|item| match item {
+                OutputItem::Message { content, .. } => Some(content),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|part| match part {
+                OutputContent::OutputText { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The annotations of every output message's text, e.g. the web pages
+    /// `web_search_preview` cited
+    pub fn citations(&self) -> Vec<&Annotation> {
+        self.output.iter()
+            .filter_map(|item| match item {
+                OutputItem::Message { content, .. } => Some(content),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|part| match part {
+                OutputContent::OutputText { annotations, .. } => Some(annotations),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+}
+
+/// An event of a streamed response, see [`Parameters::stream`]
+///
+/// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses-streaming)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ResponseEvent {
+    #[serde(rename = "response.created")]
+    Created { response: Response },
+    #[serde(rename = "response.in_progress")]
+    InProgress { response: Response },
+    /// The last event of a successful response, carrying its output and usage
+    #[serde(rename = "response.completed")]
+    Completed { response: Response },
+    #[serde(rename = "response.failed")]
+    Failed { response: Response },
+    #[serde(rename = "response.incomplete")]
+    Incomplete { response: Response },
+    #[serde(rename = "response.output_item.added")]
+    OutputItemAdded { output_index: usize, item: OutputItem },
+    #[serde(rename = "response.output_item.done")]
+    OutputItemDone { output_index: usize, item: OutputItem },
+    /// The next piece of the text of an output message
+    #[serde(rename = "response.output_text.delta")]
+    OutputTextDelta {
+        item_id: String,
+        output_index: usize,
+        content_index: usize,
+        delta: String,
+    },
+    #[serde(rename = "response.output_text.done")]
+    OutputTextDone {
+        item_id: String,
+        output_index: usize,
+        content_index: usize,
+        text: String,
+    },
+    /// The next piece of the JSON encoded arguments of a function call
+    #[serde(rename = "response.function_call_arguments.delta")]
+    FunctionCallArgumentsDelta {
+        item_id: String,
+        output_index: usize,
+        delta: String,
+    },
+    #[serde(rename = "response.function_call_arguments.done")]
+    FunctionCallArgumentsDone {
+        item_id: String,
+        output_index: usize,
+        arguments: String,
+    },
+    /// The request failed while streaming, the stream ends after this event
+    #[serde(rename = "error")]
+    Error {
+        #[serde(default)]
+        code: Option<String>,
+        message: String,
+        #[serde(default)]
+        param: Option<String>,
+    },
+    /// An event this version of the crate doesn't know
+    #[serde(other)]
+    Unknown,
+}
+
+impl ResponseEvent {
+    /// The next piece of output text this event carries, if any
+    pub fn text_delta(&self) -> Option<&str> {
+        match self {
+            ResponseEvent::OutputTextDelta { delta, .. } => Some(delta),
+            _ => None,
+        }
+    }
+}
+
+/// Stream of events returned by [`Parameters::stream`]
+pub type ResponseStream = EventStream<ResponseEvent>;
+
+/// Available parameters that can be sent with a responses request
+pub struct Parameters<'a> {
+    options: RequestOptions,
+    model: ChatModels,
+    query: Query<'a>,
+}
+
+/// Function to create a request to the Responses API
+///
+/// Call it using [`build`] and add valid [`Parameters`] to the request to build a
+/// responses request and close with `create()`.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{models, responses};
+///
+/// async {
+///     let response = responses::build(models::ChatModels::GPT_4O_MINI)
+///         .instructions("Answer in one sentence.")
+///         .input("Ice cream or cookies?")
+///         .create()
+///         .await
+///         .expect("Error Getting Response");
+///
+///     println!("{}", response.output_text());
+/// };
+/// ```
+pub fn build<'a, M: Into<ChatModels>>(model: M) -> Parameters<'a> {
+    Parameters {
+        options: RequestOptions::default(),
+        model: model.into(),
+        query: Query::new(),
+    }
+}
+
+impl<'a> Parameters<'a> {
+    /// Send the request using the given client instead of one loaded from the environment
+    pub fn client(mut self, client: &Client) -> Self {
+        self.options.client = Some(client.clone());
+        self
+    }
+
+    /// Input items to the model, e.g. the [`input_items`] of a stored response to replay it
+    ///
+    /// [`InputItem::Unknown`] items are left out, their content wasn't kept.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/create#responses-create-input)
+    pub fn input_items(mut self, input: &'a [InputItem]) -> Self {
+        let known: Vec<&InputItem> = input.iter().filter(|item| !matches!(item, InputItem::Unknown)).collect();
+        self.query.push(("input", json!(known)));
+        self
+    }
+
+    /// Text input to the model, e.g. the user's question
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/create#responses-create-input)
+    pub fn input(mut self, input: &'a str) -> Self {
+        self.query.push(("input", json!(input)));
+        self
+    }
+
+    /// A conversation of text messages as input to the model
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/create#responses-create-input)
+    pub fn messages(mut self, input: &'a [ChatMessage]) -> Self {
+        self.query.push(("input", json!(input)));
+        self
+    }
+
+    /// A system or developer message put before the input
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/create#responses-create-instructions)
+    pub fn instructions(mut self, input: &'a str) -> Self {
+        self.query.push(("instructions", json!(input)));
+        self
+    }
+
+    /// The most tokens to generate, including reasoning tokens
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/create#responses-create-max_output_tokens)
+    pub fn max_output_tokens(mut self, input: u32) -> Self {
+        self.query.push(("max_output_tokens", json!(input)));
+        self
+    }
+
+    /// What sampling temperature to use, between 0 and 2
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/create#responses-create-temperature)
+    pub fn temperature(mut self, input: f32) -> Self {
+        self.query.push(("temperature", json!(input)));
+        self
+    }
+
+    /// An alternative to sampling with temperature, only considering the tokens making up
+    /// the top `top_p` probability mass
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/create#responses-create-top_p)
+    pub fn top_p(mut self, input: f32) -> Self {
+        self.query.push(("top_p", json!(input)));
+        self
+    }
+
+    /// Whether the model may call several tools at once
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/create#responses-create-parallel_tool_calls)
+    pub fn parallel_tool_calls(mut self, input: bool) -> Self {
+        self.query.push(("parallel_tool_calls", json!(input)));
+        self
+    }
+
+    /// A unique identifier representing your end-user, which can help
+    /// OpenAI to monitor and detect abuse.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/create#responses-create-user)
+    pub fn user(mut self, input: &'a str) -> Self {
+        self.query.push(("user", json!(input)));
+        self
+    }
+
+    /// Continue the conversation of a stored response, so only the new input has to be sent
+    /// instead of the whole history. See [`ResponseChain`] to keep track of the latest response.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/create#responses-create-previous_response_id)
+    pub fn previous_response_id(mut self, input: &str) -> Self {
+        self.query.push(("previous_response_id", json!(input)));
+        self
+    }
+
+    /// Tools the model may use, see [`ResponseTool`]
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/create#responses-create-tools)
+    pub fn tools(mut self, input: &'a [ResponseTool]) -> Self {
+        self.query.push(("tools", json!(input)));
+        self
+    }
+
+    /// Extra data to include in the response, e.g. `file_search_call.results`
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/create#responses-create-include)
+    pub fn include(mut self, input: &'a [&'a str]) -> Self {
+        self.query.push(("include", json!(input)));
+        self
+    }
+
+    /// The format the model answers in, see [`ResponseFormat`]
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/create#responses-create-text)
+    pub fn response_format(mut self, input: ResponseFormat) -> Self {
+        self.query.push(("text", json!({ "format": input.text_format() })));
+        self
+    }
+
+    /// Whether to store the response so it can be retrieved later, on by default
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/create#responses-create-store)
+    pub fn store(mut self, input: bool) -> Self {
+        self.query.push(("store", json!(input)));
+        self
+    }
+
+    /// Metadata to attach to the response
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/create#responses-create-metadata)
+    pub fn metadata(mut self, input: &'a Metadata) -> Self {
+        self.query.push(("metadata", json!(input)));
+        self
+    }
+
+    /// Run the response in the background. [`create`](Parameters::create) returns straight
+    /// away with a `queued` response, poll it with [`get`] until it's finished or stop it with
+    /// [`cancel`], so long running tasks don't hold a connection open.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/create#responses-create-background)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{models, responses};
+    /// use std::time::Duration;
+    ///
+    /// async {
+    ///     let mut response = responses::build(models::ChatModels::GPT_4O)
+    ///         .input("Write a detailed report on the history of cookies.")
+    ///         .background(true)
+    ///         .create()
+    ///         .await
+    ///         .expect("Error Creating Response");
+    ///
+    ///     while !response.status.is_finished() {
+    ///         tokio::time::sleep(Duration::from_secs(5)).await;
+    ///         response = responses::get(&response.id).await.expect("Error Getting Response");
+    ///     }
+    ///     println!("{}", response.output_text());
+    /// };
+    /// ```
+    pub fn background(mut self, input: bool) -> Self {
+        self.query.push(("background", json!(input)));
+        self
+    }
+
+    /// Mark the request as safe to retry even when the client's retry policy
+    /// is restricted to idempotent requests, see [`RetryScope::IdempotentOnly`](crate::retry::RetryScope::IdempotentOnly)
+    pub fn idempotent(mut self) -> Self {
+        self.options.idempotent = true;
+        self
+    }
+
+    /// Set a parameter this crate doesn't model yet, sent as is in the request body
+    ///
+    /// Replaces the value set for `key` by earlier setters, and is replaced by later ones.
+    /// `model` and `stream` are set when the request is sent, so extra values for them are
+    /// ignored.
+    pub fn extra_param(mut self, key: &'a str, value: Value) -> Self {
+        self.query.push((key, value));
+        self
+    }
+
+    /// Complete the request and send
+    pub async fn create(mut self) -> Result<Response, Error> {
+        let started = Instant::now();
+        self.query.push(("model", json!(self.model.as_str())));
+        moderations::preflight(&self.options, user_input(&self.query).as_ref()).await?;
+
+        let response: Result<Response, Error> = requester::responses(&self.options, &self.query).await;
+
+        match response {
+            Ok(mut t) => {
+                t.timing = Some(Timing::since(started));
+                Ok(t)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Send the request and stream back its events as the response is generated
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_util::StreamExt;
+    /// use oai_rs::{models, responses};
+    /// use oai_rs::responses::ResponseEvent;
+    ///
+    /// async {
+    ///     let mut stream = responses::build(models::ChatModels::GPT_4O_MINI)
+    ///         .input("Ice cream or cookies?")
+    ///         .stream()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     while let Some(event) = stream.next().await {
+    ///         match event.expect("Error Getting Event") {
+    ///             ResponseEvent::OutputTextDelta { delta, .. } => print!("{}", delta),
+    ///             ResponseEvent::Completed { response } => println!("\n{:?}", response.usage),
+    ///             _ => {}
+    ///         }
+    ///     }
+    /// };
+    /// ```
+    pub async fn stream(mut self) -> Result<ResponseStream, Error> {
+        self.query.push(("model", json!(self.model.as_str())));
+        self.query.push(("stream", json!(true)));
+        moderations::preflight(&self.options, user_input(&self.query).as_ref()).await?;
+
+        requester::responses_stream(&self.options, &self.query).await
+    }
+}
+
+/// The text of the input, or of the user messages in it, to run through the moderations endpoint
+fn user_input(query: &Query<'_>) -> Option<Value> {
+    let input = query.get("input")?;
+    if let Some(text) = input.as_str() {
+        return Some(json!(text));
+    }
+    let content: Vec<Value> = input.as_array()?.iter()
+        .filter(|item| item["role"] == "user")
+        .map(|item| match &item["content"] {
+            Value::String(text) => text.clone(),
+            Value::Array(parts) => parts.iter().filter_map(|p| p["text"].as_str()).collect::<Vec<_>>().join("\n"),
+            _ => String::new(),
+        })
+        .filter(|text| !text.is_empty())
+        .map(Value::String)
+        .collect();
+    if content.is_empty() {
+        return None;
+    }
+    Some(json!(content))
+}
+
+/// Keeps the ID of the latest response of a conversation, so each request continues it with
+/// [`previous_response_id`](Parameters::previous_response_id) and the conversation's state lives
+/// server-side instead of being resent every turn
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{models, responses};
+/// use oai_rs::responses::ResponseChain;
+///
+/// async {
+///     let mut chain = ResponseChain::new();
+///
+///     let request = responses::build(models::ChatModels::GPT_4O_MINI).input("Tell me a joke.");
+///     let response = chain.send(request).await.expect("Error Getting Response");
+///     println!("{}", response.output_text());
+///
+///     let request = responses::build(models::ChatModels::GPT_4O_MINI).input("Explain why it's funny.");
+///     let response = chain.send(request).await.expect("Error Getting Response");
+///     println!("{}", response.output_text());
+/// };
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResponseChain {
+    last_id: Option<String>,
+}
+
+impl ResponseChain {
+    /// Start a new conversation
+    pub fn new() -> Self {
+        ResponseChain::default()
+    }
+
+    /// Continue the conversation ending with the stored response `response_id`
+    pub fn resume<S: Into<String>>(response_id: S) -> Self {
+        ResponseChain { last_id: Some(response_id.into()) }
+    }
+
+    /// The ID of the latest response, `None` until the first response
+    pub fn last_id(&self) -> Option<&str> {
+        self.last_id.as_deref()
+    }
+
+    /// Make `response` the latest response of the conversation, for responses created without
+    /// [`send`](ResponseChain::send)
+    pub fn record(&mut self, response: &Response) {
+        self.last_id = Some(response.id.clone());
+    }
+
+    /// Send `parameters` continuing the conversation, and record the response as its latest.
+    /// The conversation is left as it was if the request fails.
+    pub async fn send(&mut self, parameters: Parameters<'_>) -> Result<Response, Error> {
+        let parameters = match &self.last_id {
+            Some(id) => parameters.previous_response_id(id),
+            None => parameters,
+        };
+
+        let response = parameters.create().await?;
+        self.record(&response);
+        Ok(response)
+    }
+}
+
+/// Get a stored response, e.g. to poll a background response
+///
+/// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/get)
+pub async fn get(response_id: &str) -> Result<Response, Error> {
+    get_with(None, response_id).await
+}
+
+pub(crate) async fn get_with(client: Option<&Client>, response_id: &str) -> Result<Response, Error> {
+    let response: Result<Response, Error> = requester::response(&options(client), Method::GET, response_id, None).await;
+
+    match response {
+        Ok(t) => Ok(t),
+        Err(e) => Err(e),
+    }
+}
+
+/// Cancel a background response that hasn't finished yet
+///
+/// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/cancel)
+pub async fn cancel(response_id: &str) -> Result<Response, Error> {
+    cancel_with(None, response_id).await
+}
+
+pub(crate) async fn cancel_with(client: Option<&Client>, response_id: &str) -> Result<Response, Error> {
+    let response: Result<Response, Error> = requester::response(&options(client), Method::POST, response_id, Some("cancel")).await;
+
+    match response {
+        Ok(t) => Ok(t),
+        Err(e) => Err(e),
+    }
+}
+
+/// List the input items of a stored response, newest first unless ordered otherwise, to audit
+/// what the model was sent or replay it
+///
+/// [OpenAI Reference](https://platform.openai.com/docs/api-reference/responses/input-items)
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::responses;
+///
+/// async {
+///     let mut items = Vec::new();
+///     let mut after: Option<String> = None;
+///     loop {
+///         let mut request = responses::input_items("resp_abc123").limit(100);
+///         if let Some(id) = &after {
+///             request = request.after(id);
+///         }
+///         let page = request.list().await.expect("Error Listing Input Items");
+///         items.extend(page.data);
+///         if !page.has_more {
+///             break;
+///         }
+///         after = page.last_id;
+///     }
+///     println!("{:?}", items);
+/// };
+/// ```
+pub fn input_items(response_id: &str) -> InputItemsParameters {
+    InputItemsParameters {
+        options: RequestOptions::default(),
+        response_id: response_id.to_string(),
+        query: Vec::new(),
+    }
+}
+
+/// Parameters of a list of a stored response's input items
+pub struct InputItemsParameters {
+    options: RequestOptions,
+    response_id: String,
+    query: Vec<(String, String)>,
+}
+
+impl InputItemsParameters {
+    /// Send the request with `client` instead of the client loaded from the environment
+    pub fn client(mut self, client: &Client) -> Self {
+        self.options.client = Some(client.clone());
+        self
+    }
+
+    /// Start the page after the ID, the [`last_id`](Page::last_id) of the previous page
+    pub fn after(mut self, id: &str) -> Self {
+        self.query.push((String::from("after"), id.to_string()));
+        self
+    }
+
+    /// End the page before the ID, the [`first_id`](Page::first_id) of the next page
+    pub fn before(mut self, id: &str) -> Self {
+        self.query.push((String::from("before"), id.to_string()));
+        self
+    }
+
+    /// The most items to return, between 1 and 100
+    pub fn limit(mut self, limit: u8) -> Self {
+        self.query.push((String::from("limit"), limit.to_string()));
+        self
+    }
+
+    /// `asc` or `desc`, by the order the items were input in
+    pub fn order(mut self, order: &str) -> Self {
+        self.query.push((String::from("order"), order.to_string()));
+        self
+    }
+
+    /// Get the page of input items
+    pub async fn list(self) -> Result<Page<InputItem>, Error> {
+        let response: Result<Page<InputItem>, Error> = requester::response_input_items(&self.options, &self.response_id, &self.query).await;
+
+        match response {
+            Ok(t) => Ok(t),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+fn options(client: Option<&Client>) -> RequestOptions {
+    RequestOptions {
+        client: client.cloned(),
+        ..RequestOptions::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build, InputItem, ResponseEvent, ResponseStatus, ResponseTool};
+    use crate::chat::Metadata;
+    use crate::models::ChatModels;
+    use crate::schema::ResponseFormat;
+    use crate::testing::{assert_snapshot, golden, CannedResponse, Matcher, TestClient};
+    use crate::usage::UsageTracker;
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn parameters_use_wire_keys() {
+        let test = TestClient::new();
+        let tools = [ResponseTool::web_search()];
+        let include = ["file_search_call.results"];
+        let mut metadata = Metadata::new();
+        metadata.insert("team", "search").unwrap();
+        let _ = build(ChatModels::GPT_4O)
+            .client(&test.client())
+            .input("Tell me a joke")
+            .instructions("Be brief")
+            .max_output_tokens(64)
+            .temperature(0.5)
+            .top_p(0.25)
+            .parallel_tool_calls(false)
+            .user("user-1")
+            .previous_response_id("resp_123")
+            .tools(&tools)
+            .include(&include)
+            .response_format(ResponseFormat::JsonObject)
+            .store(false)
+            .metadata(&metadata)
+            .background(true)
+            .create()
+            .await;
+
+        assert_snapshot(golden("responses"), &test.last_request());
+    }
+
+    #[tokio::test]
+    async fn unknown_input_items_are_not_replayed() {
+        let items: Vec<InputItem> = serde_json::from_value(serde_json::json!([
+            { "type": "message", "id": "msg_1", "role": "user", "content": [{ "type": "input_text", "text": "Hi" }] },
+            { "type": "computer_call_output", "id": "cuo_1", "call_id": "call_1", "output": {} },
+        ])).unwrap();
+        assert!(matches!(items[1], InputItem::Unknown));
+
+        let test = TestClient::new();
+        let _ = build(ChatModels::GPT_4O).client(&test.client()).input_items(&items).create().await;
+
+        let input = &test.last_request().unwrap().body.unwrap()["input"];
+        assert_eq!(input, &serde_json::json!([
+            { "type": "message", "role": "user", "content": [{ "type": "input_text", "text": "Hi" }] },
+        ]));
+    }
+
+    #[tokio::test]
+    async fn streams_events() {
+        let response = |status: &str, usage: serde_json::Value| serde_json::json!({
+            "id": "resp_1", "created_at": 0, "status": status, "model": "gpt-4o", "output": [], "usage": usage,
+        });
+        let test = TestClient::new().on(Matcher::post("responses"), CannedResponse::stream(&[
+            serde_json::json!({ "type": "response.created", "response": response("in_progress", serde_json::Value::Null) }),
+            serde_json::json!({ "type": "response.output_text.delta", "item_id": "msg_1", "output_index": 0, "content_index": 0, "delta": "Cook" }),
+            serde_json::json!({ "type": "response.reasoning_summary_text.delta", "item_id": "rs_1", "delta": "Thinking" }),
+            serde_json::json!({ "type": "response.output_text.delta", "item_id": "msg_1", "output_index": 0, "content_index": 0, "delta": "ies" }),
+            serde_json::json!({ "type": "response.completed", "response": response("completed", serde_json::json!({ "input_tokens": 5, "output_tokens": 2, "total_tokens": 7 })) }),
+        ]));
+        let tracker = UsageTracker::new();
+
+        let events: Vec<ResponseEvent> = build(ChatModels::GPT_4O)
+            .client(&test.client().usage_tracker(tracker.clone()))
+            .input("Ice cream or cookies?")
+            .stream()
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        let text: String = events.iter().filter_map(ResponseEvent::text_delta).collect();
+        assert_eq!(text, "Cookies");
+        assert!(matches!(events[2], ResponseEvent::Unknown));
+        assert!(matches!(&events[4], ResponseEvent::Completed { response } if response.status == ResponseStatus::Completed));
+        assert_eq!(tracker.snapshot().total().completion_tokens, 2);
+        assert_eq!(test.last_request().unwrap().body.unwrap()["stream"], serde_json::json!(true));
+    }
+}
@@ -0,0 +1,201 @@
+use crate::error::Error;
+use crate::transport::TransportError;
+use reqwest::StatusCode;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Called before waiting to retry a request, see [`RetryPolicy::on_retry`]
+pub type RetryHook = Arc<dyn Fn(&RetryEvent<'_>) + Send + Sync>;
+
+/// Why a request is being retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryReason {
+    /// The API responded with a `429` or `5xx` status
+    Status(StatusCode),
+    Timeout,
+    Connect,
+}
+
+/// A request is about to be retried
+#[derive(Debug, Clone, Copy)]
+pub struct RetryEvent<'a> {
+    /// Path of the request, e.g. `/v1/chat/completions`
+    pub path: &'a str,
+    /// The retry about to be made, starting at `1` for the first retry
+    pub attempt: u32,
+    /// How long the request waits before being retried
+    pub wait: Duration,
+    /// Generated once per request and sent with every attempt as the `X-Client-Request-Id`
+    /// header, so the attempts of a request can be grouped in logs
+    pub correlation_id: &'a str,
+    pub reason: RetryReason,
+}
+
+/// Which requests are allowed to be retried automatically
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryScope {
+    /// Retry every request, including non-idempotent `POST` requests
+    ///
+    /// Retrying a completion can generate (and bill for) a second, different response.
+    All,
+    /// Only retry `GET` requests and requests explicitly marked as idempotent, the default
+    IdempotentOnly,
+}
+
+/// Configures how failed requests are retried
+///
+/// Requests that fail with a `429`, a `5xx` status, a timeout or a connection error are
+/// retried up to `max_retries` times, waiting an exponentially increasing amount of time
+/// between attempts. Only requests that are safe to repeat are retried unless the
+/// [`scope`](RetryPolicy::scope) is set to [`RetryScope::All`].
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{Client, retry::{RetryPolicy, RetryScope}};
+///
+/// let client = Client::new(String::from("sk-..."))
+///     .retry_policy(RetryPolicy::default().max_retries(3).scope(RetryScope::All));
+/// ```
+#[derive(Clone)]
+pub struct RetryPolicy {
+    pub(crate) max_retries: u32,
+    pub(crate) initial_backoff: Duration,
+    pub(crate) max_backoff: Duration,
+    pub(crate) scope: RetryScope,
+    pub(crate) on_retry: Option<RetryHook>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+            scope: RetryScope::IdempotentOnly,
+            on_retry: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries
+    pub fn none() -> Self {
+        RetryPolicy::default().max_retries(0)
+    }
+
+    /// The maximum number of times a request is retried after the first attempt
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// The wait before the first retry, doubled after each attempt up to `max`
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Set which requests are allowed to be retried, [`RetryScope::IdempotentOnly`] unless set
+    pub fn scope(mut self, scope: RetryScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Shorthand for `scope(RetryScope::IdempotentOnly)`
+    pub fn idempotent_only(self) -> Self {
+        self.scope(RetryScope::IdempotentOnly)
+    }
+
+    /// Call `hook` every time a request is retried, with the attempt, wait and reason,
+    /// e.g. to log or count how often requests are throttled
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{Client, retry::RetryPolicy};
+    ///
+    /// let client = Client::new(String::from("sk-..."))
+    ///     .retry_policy(RetryPolicy::default().on_retry(|event| {
+    ///         eprintln!("[{}] retry {} of {} in {:?}: {:?}", event.correlation_id, event.attempt, event.path, event.wait, event.reason);
+    ///     }));
+    /// ```
+    pub fn on_retry<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&RetryEvent<'_>) + Send + Sync + 'static
+    {
+        self.on_retry = Some(Arc::new(hook));
+        self
+    }
+
+    pub(crate) fn allows(&self, idempotent: bool) -> bool {
+        self.scope == RetryScope::All || idempotent
+    }
+
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt);
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_retries", &self.max_retries)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("scope", &self.scope)
+            .field("on_retry", &self.on_retry.is_some())
+            .finish()
+    }
+}
+
+/// Why the response should be retried, `None` if it shouldn't
+pub(crate) fn retry_reason(response: &Result<reqwest::Response, TransportError>) -> Option<RetryReason> {
+    match response {
+        Ok(r) if retryable_status(r.status()) => Some(RetryReason::Status(r.status())),
+        Ok(_) => None,
+        Err(e) if e.is_timeout() => Some(RetryReason::Timeout),
+        Err(e) if e.is_connect() => Some(RetryReason::Connect),
+        Err(_) => None,
+    }
+}
+
+/// Why a request that failed with `error` should be retried, the same reasons as
+/// [`retry_reason`] gives for the response the error was made from
+pub(crate) fn error_retry_reason(error: &Error) -> Option<RetryReason> {
+    match error {
+        Error::Status(status) if retryable_status(*status) => Some(RetryReason::Status(*status)),
+        Error::Api(e) if retryable_status(e.status) => Some(RetryReason::Status(e.status)),
+        Error::Timeout => Some(RetryReason::Timeout),
+        Error::Connect => Some(RetryReason::Connect),
+        _ => None,
+    }
+}
+
+fn retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ApiError;
+
+    fn api_error(status: StatusCode) -> Error {
+        Error::Api(ApiError { status, message: String::new(), kind: None, param: None, code: None })
+    }
+
+    #[test]
+    fn errors_are_retried_like_responses() {
+        assert_eq!(error_retry_reason(&api_error(StatusCode::TOO_MANY_REQUESTS)), Some(RetryReason::Status(StatusCode::TOO_MANY_REQUESTS)));
+        assert_eq!(error_retry_reason(&api_error(StatusCode::BAD_GATEWAY)), Some(RetryReason::Status(StatusCode::BAD_GATEWAY)));
+        assert_eq!(error_retry_reason(&Error::Timeout), Some(RetryReason::Timeout));
+        assert_eq!(error_retry_reason(&Error::Connect), Some(RetryReason::Connect));
+        assert_eq!(error_retry_reason(&api_error(StatusCode::BAD_REQUEST)), None);
+        assert_eq!(error_retry_reason(&Error::Status(StatusCode::NOT_FOUND)), None);
+        assert_eq!(error_retry_reason(&Error::MissingApiKey), None);
+    }
+}
@@ -0,0 +1,297 @@
+use reqwest::header::HeaderMap;
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// The pause after a `429` when the response doesn't say how long to wait
+const DEFAULT_PAUSE: Duration = Duration::from_secs(1);
+
+/// The longest pause a response can ask for, longer waits are cut short
+const MAX_PAUSE: Duration = Duration::from_secs(60);
+
+/// Dispatches requests in the order they were submitted, with a limit on how many are in flight
+///
+/// Every request takes a ticket when it is submitted and waits until it holds the lowest
+/// ticket in the queue and a slot is free. With
+/// [`requeue_rate_limited`](Scheduler::requeue_rate_limited) a request that is rate limited
+/// with a `429` pauses dispatching for every request on the client, gives up its slot and
+/// goes back into the queue with its original ticket, so once the pause is over it is sent
+/// again ahead of every request submitted after it. Requeued attempts don't count towards
+/// the client's [`RetryPolicy`](crate::retry::RetryPolicy).
+///
+/// The scheduler is shared between all clones of the [`Client`](crate::Client) it is set on.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{Client, scheduler::Scheduler};
+///
+/// let client = Client::new(String::from("sk-..."))
+///     .scheduler(Scheduler::new(8).requeue_rate_limited(10));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    max_in_flight: usize,
+    queue: Arc<Mutex<Queue>>,
+    /// Woken whenever a slot frees up or the queue changes
+    changed: Arc<Notify>,
+    pub(crate) max_requeues: u32,
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    next_ticket: u64,
+    /// Tickets of the requests waiting to be sent, dispatched lowest first
+    waiting: BTreeSet<u64>,
+    in_flight: usize,
+    paused_until: Option<Instant>,
+}
+
+impl Scheduler {
+    /// Allow up to `max_in_flight` requests to be sent at once
+    pub fn new(max_in_flight: usize) -> Self {
+        Scheduler {
+            max_in_flight: max_in_flight.max(1),
+            queue: Arc::new(Mutex::new(Queue::default())),
+            changed: Arc::new(Notify::new()),
+            max_requeues: 0,
+        }
+    }
+
+    /// Put rate limited requests back in the queue in their original position, and send
+    /// them again once the pause they cause is over, at most `max_requeues` times each
+    pub fn requeue_rate_limited(mut self, max_requeues: u32) -> Self {
+        self.max_requeues = max_requeues;
+        self
+    }
+
+    /// Queue a request behind every request submitted before it
+    pub(crate) fn submit(&self) -> Ticket {
+        let mut queue = self.queue.lock().unwrap();
+        let id = queue.next_ticket;
+        queue.next_ticket += 1;
+        queue.waiting.insert(id);
+        Ticket { scheduler: self.clone(), id, in_flight: false }
+    }
+
+    /// Wait until dispatching isn't paused
+    pub(crate) async fn ready(&self) {
+        while let Some(until) = self.paused_until() {
+            tokio::time::sleep_until(until.into()).await;
+        }
+    }
+
+    /// When the current pause ends, `None` if dispatching isn't paused
+    fn paused_until(&self) -> Option<Instant> {
+        let paused_until = self.queue.lock().unwrap().paused_until;
+        paused_until.filter(|until| *until > Instant::now())
+    }
+
+    /// Pause dispatching for as long as the rate limited response asks, extending any current pause
+    fn pause(&self, headers: &HeaderMap) {
+        let pause = retry_after(headers).unwrap_or(DEFAULT_PAUSE).min(MAX_PAUSE);
+        let now = Instant::now();
+        let until = now.checked_add(pause).unwrap_or(now);
+        let mut queue = self.queue.lock().unwrap();
+        if queue.paused_until.is_none_or(|current| current < until) {
+            queue.paused_until = Some(until);
+        }
+    }
+}
+
+/// A request's place in the [`Scheduler`] queue, leaving the queue or freeing its slot when dropped
+#[derive(Debug)]
+pub(crate) struct Ticket {
+    scheduler: Scheduler,
+    id: u64,
+    in_flight: bool,
+}
+
+impl Ticket {
+    /// Wait until this is the first request in the queue, dispatching isn't paused and a slot
+    /// is free, then take the slot
+    pub(crate) async fn dispatch(&mut self) {
+        if self.in_flight {
+            return;
+        }
+        loop {
+            // Registered before checking the queue, so a change made right after isn't missed
+            let changed = self.scheduler.changed.notified();
+            if let Some(until) = self.scheduler.paused_until() {
+                tokio::time::sleep_until(until.into()).await;
+                continue;
+            }
+            {
+                let mut queue = self.scheduler.queue.lock().unwrap();
+                if queue.waiting.first() == Some(&self.id) && queue.in_flight < self.scheduler.max_in_flight {
+                    queue.waiting.remove(&self.id);
+                    queue.in_flight += 1;
+                    self.in_flight = true;
+                    drop(queue);
+                    // The next request in the queue may be able to go as well
+                    self.scheduler.changed.notify_waiters();
+                    return;
+                }
+            }
+            changed.await;
+        }
+    }
+
+    /// Give up the slot after a `429`, pausing dispatching as the response asks, and go back
+    /// into the queue in the original position
+    pub(crate) fn requeue(&mut self, headers: &HeaderMap) {
+        self.scheduler.pause(headers);
+        {
+            let mut queue = self.scheduler.queue.lock().unwrap();
+            if self.in_flight {
+                queue.in_flight -= 1;
+                self.in_flight = false;
+            }
+            queue.waiting.insert(self.id);
+        }
+        self.scheduler.changed.notify_waiters();
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        {
+            let mut queue = self.scheduler.queue.lock().unwrap();
+            if self.in_flight {
+                queue.in_flight -= 1;
+            } else {
+                queue.waiting.remove(&self.id);
+            }
+        }
+        self.scheduler.changed.notify_waiters();
+    }
+}
+
+/// How long a response asks to wait before retrying, from `retry-after-ms` or `retry-after`,
+/// ignoring values that aren't a finite number of seconds
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| {
+        let value = headers.get(name)?.to_str().ok()?.trim().parse::<f64>().ok()?;
+        value.is_finite().then_some(value.max(0.0))
+    };
+    let secs = match header("retry-after-ms") {
+        Some(ms) => ms / 1000.0,
+        None => header("retry-after")?,
+    };
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{retry_after, Scheduler, MAX_PAUSE};
+    use crate::models::EmbeddingModels;
+    use crate::retry::RetryPolicy;
+    use crate::transport::{Transport, TransportError, TransportFuture};
+    use crate::{embeddings, Client};
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use reqwest::{Request, Response};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    fn headers(name: &'static str, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn retry_after_reads_seconds_and_milliseconds() {
+        assert_eq!(retry_after(&headers("retry-after", "2")), Some(Duration::from_secs(2)));
+        assert_eq!(retry_after(&headers("retry-after-ms", "250")), Some(Duration::from_millis(250)));
+        assert_eq!(retry_after(&headers("retry-after", "-3")), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn retry_after_ignores_values_that_are_not_durations() {
+        for value in ["inf", "NaN", "1e300", "soon"] {
+            assert_eq!(retry_after(&headers("retry-after", value)), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn pause_is_capped() {
+        let scheduler = Scheduler::new(1);
+        scheduler.pause(&headers("retry-after", "1e20"));
+        let until = scheduler.paused_until().unwrap();
+        assert!(until <= Instant::now() + MAX_PAUSE);
+    }
+
+    /// Rate limits the first attempt of the requests whose `input` is in `limited`, answers the
+    /// rest after a moment, and records the `input` of every request in the order they were sent
+    #[derive(Clone)]
+    struct RateLimiting {
+        limited: Arc<Mutex<Vec<&'static str>>>,
+        sent: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Transport for RateLimiting {
+        fn execute(&self, request: Request) -> TransportFuture {
+            let body: serde_json::Value = serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+            let input = body["input"].as_str().unwrap().to_string();
+            let mut limited = self.limited.lock().unwrap();
+            let response = match limited.iter().position(|l| *l == input) {
+                Some(i) => {
+                    limited.remove(i);
+                    http::Response::builder()
+                        .status(429)
+                        .header("retry-after-ms", "20")
+                        .body(r#"{"error": {"message": "Rate limit reached", "type": "requests"}}"#)
+                }
+                None => http::Response::builder()
+                    .status(200)
+                    .body(r#"{"object": "list", "data": [], "model": "text-embedding-3-small", "usage": null}"#),
+            };
+            self.sent.lock().unwrap().push(input);
+            let response = Response::from(response.unwrap());
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                Ok::<_, TransportError>(response)
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn requeued_requests_keep_their_place_in_the_queue() {
+        let transport = RateLimiting { limited: Arc::new(Mutex::new(vec!["first", "second"])), sent: Arc::default() };
+        let client = Client::new(String::from("sk-test"))
+            .retry_policy(RetryPolicy::none())
+            .transport(transport.clone())
+            .scheduler(Scheduler::new(2).requeue_rate_limited(1));
+
+        let requests = ["first", "second", "third", "fourth"].map(|input| {
+            let client = client.clone();
+            async move {
+                embeddings::build(EmbeddingModels::TEXT_EMBEDDING_3_SMALL)
+                    .client(&client)
+                    .input(input)
+                    .embed()
+                    .await
+            }
+        });
+        for result in futures_util::future::join_all(requests).await {
+            assert!(result.is_ok(), "{:?}", result);
+        }
+
+        // The first two are rate limited and sent again before the two submitted after them
+        assert_eq!(*transport.sent.lock().unwrap(), ["first", "second", "first", "second", "third", "fourth"]);
+    }
+
+    #[tokio::test]
+    async fn dropped_tickets_leave_the_queue() {
+        let scheduler = Scheduler::new(1);
+        let mut first = scheduler.submit();
+        let second = scheduler.submit();
+        let mut third = scheduler.submit();
+        first.dispatch().await;
+        drop(second);
+        drop(first);
+
+        tokio::time::timeout(Duration::from_secs(1), third.dispatch()).await.expect("third is dispatched");
+    }
+}
@@ -0,0 +1,311 @@
+use serde::{Serialize, Serializer};
+use serde_json::{json, Map, Value};
+
+/// Builds a JSON schema, for tool parameters and structured outputs
+///
+/// Properties added with [`property`](Schema::property) are required, properties added with
+/// [`optional_property`](Schema::optional_property) are not.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::schema::Schema;
+/// use oai_rs::tools::ToolDefinition;
+///
+/// let parameters = Schema::object()
+///     .property("city", Schema::string().description("The city to get the weather for"))
+///     .optional_property("unit", Schema::string().enumeration(["celsius", "fahrenheit"]));
+///
+/// let tool = ToolDefinition::function("get_weather", Some(String::from("Get the current weather")), parameters);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema(Map<String, Value>);
+
+impl Schema {
+    fn typed(kind: &str) -> Self {
+        let mut schema = Map::new();
+        schema.insert(String::from("type"), Value::from(kind));
+        Schema(schema)
+    }
+
+    pub fn string() -> Self {
+        Schema::typed("string")
+    }
+
+    pub fn integer() -> Self {
+        Schema::typed("integer")
+    }
+
+    pub fn number() -> Self {
+        Schema::typed("number")
+    }
+
+    pub fn boolean() -> Self {
+        Schema::typed("boolean")
+    }
+
+    /// An array with every item matching `items`
+    pub fn array(items: Schema) -> Self {
+        let mut schema = Schema::typed("array");
+        schema.0.insert(String::from("items"), items.into());
+        schema
+    }
+
+    /// An object without any properties, add them with [`property`](Schema::property)
+    pub fn object() -> Self {
+        let mut schema = Schema::typed("object");
+        schema.0.insert(String::from("properties"), Value::Object(Map::new()));
+        schema.0.insert(String::from("required"), Value::Array(Vec::new()));
+        schema
+    }
+
+    /// Describe what the value is for
+    pub fn description(mut self, description: &str) -> Self {
+        self.0.insert(String::from("description"), Value::from(description));
+        self
+    }
+
+    /// Restrict the value to one of `values`
+    pub fn enumeration<I, V>(mut self, values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Value>
+    {
+        let values: Vec<Value> = values.into_iter().map(Into::into).collect();
+        self.0.insert(String::from("enum"), Value::Array(values));
+        self
+    }
+
+    /// Add a required property to an object
+    pub fn property(self, name: &str, schema: Schema) -> Self {
+        self.insert_property(name, schema, true)
+    }
+
+    /// Add a property to an object that may be left out
+    ///
+    /// Structured outputs in [strict](ResponseFormat::strict) mode need every property to be
+    /// required, so optional properties are sent there as required but nullable instead, and
+    /// are `null` when the model leaves them out.
+    pub fn optional_property(self, name: &str, schema: Schema) -> Self {
+        self.insert_property(name, schema, false)
+    }
+
+    /// Whether an object may have properties that weren't added to the schema
+    pub fn additional_properties(mut self, allowed: bool) -> Self {
+        self.0.insert(String::from("additionalProperties"), Value::from(allowed));
+        self
+    }
+
+    fn insert_property(mut self, name: &str, schema: Schema, required: bool) -> Self {
+        if let Some(Value::Object(properties)) = self.0.get_mut("properties") {
+            properties.insert(String::from(name), schema.into());
+        }
+        if required {
+            if let Some(Value::Array(names)) = self.0.get_mut("required") {
+                names.push(Value::from(name));
+            }
+        }
+        self
+    }
+}
+
+/// `schema` with the optional properties of its objects made required and nullable, the form
+/// strict structured outputs accept
+fn strict_form(schema: &Value) -> Value {
+    let Value::Object(schema) = schema else {
+        return schema.clone();
+    };
+    let mut strict = schema.clone();
+    if let Some(items) = strict.get_mut("items") {
+        *items = strict_form(items);
+    }
+    let required: Vec<Value> = match strict.get("required") {
+        Some(Value::Array(required)) => required.clone(),
+        _ => Vec::new(),
+    };
+    let Some(Value::Object(properties)) = strict.get_mut("properties") else {
+        return Value::Object(strict);
+    };
+    let mut all = required.clone();
+    for (name, property) in properties.iter_mut() {
+        *property = strict_form(property);
+        if !required.iter().any(|r| r.as_str() == Some(name.as_str())) {
+            *property = nullable(property);
+            all.push(Value::from(name.as_str()));
+        }
+    }
+    strict.insert(String::from("required"), Value::Array(all));
+    Value::Object(strict)
+}
+
+/// `schema` also accepting `null`
+fn nullable(schema: &Value) -> Value {
+    let mut nullable = match schema {
+        Value::Object(schema) => schema.clone(),
+        _ => return schema.clone(),
+    };
+    match nullable.get_mut("type") {
+        Some(Value::String(kind)) => {
+            let kind = Value::from(kind.as_str());
+            nullable.insert(String::from("type"), json!([kind, "null"]));
+        }
+        Some(Value::Array(kinds)) if !kinds.contains(&Value::from("null")) => kinds.push(Value::from("null")),
+        Some(_) => {}
+        None => return json!({ "anyOf": [schema, { "type": "null" }] }),
+    }
+    if let Some(Value::Array(values)) = nullable.get_mut("enum") {
+        if !values.contains(&Value::Null) {
+            values.push(Value::Null);
+        }
+    }
+    Value::Object(nullable)
+}
+
+impl From<Schema> for Value {
+    fn from(schema: Schema) -> Self {
+        Value::Object(schema.0)
+    }
+}
+
+impl Serialize for Schema {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+/// The format a model answers in, shared by the endpoints taking a `response_format`
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::schema::{ResponseFormat, Schema};
+///
+/// let schema = Schema::object()
+///     .property("name", Schema::string())
+///     .property("calories", Schema::integer())
+///     .additional_properties(false);
+/// let format = ResponseFormat::json_schema("dessert", schema);
+///
+/// assert_eq!(serde_json::to_value(&format).unwrap()["json_schema"]["strict"], true);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResponseFormat {
+    /// Plain text, the default
+    Text,
+    /// Any valid JSON object, the prompt has to ask for JSON as well
+    JsonObject,
+    /// JSON matching `schema`, always when `strict`, using structured outputs
+    JsonSchema { name: String, schema: Schema, strict: bool },
+}
+
+impl ResponseFormat {
+    /// JSON strictly matching `schema`, named `name`, see [`strict`](ResponseFormat::strict)
+    pub fn json_schema<S: Into<String>>(name: S, schema: Schema) -> Self {
+        ResponseFormat::JsonSchema { name: name.into(), schema, strict: true }
+    }
+
+    /// Whether the answer always matches the schema, `true` unless set
+    ///
+    /// Strict mode needs every property of the schema to be required, so properties added
+    /// with [`optional_property`](Schema::optional_property) are sent as required but
+    /// nullable. Has no effect on formats without a schema.
+    pub fn strict(self, strict: bool) -> Self {
+        match self {
+            ResponseFormat::JsonSchema { name, schema, .. } => ResponseFormat::JsonSchema { name, schema, strict },
+            format => format,
+        }
+    }
+
+    /// The schema as sent, in its strict form when `strict`
+    fn sent_schema(schema: &Schema, strict: bool) -> Value {
+        let schema = Value::Object(schema.0.clone());
+        if strict {
+            strict_form(&schema)
+        } else {
+            schema
+        }
+    }
+
+    /// The format in the shape of the Responses API's `text.format`, which has the fields of
+    /// `json_schema` inline
+    pub(crate) fn text_format(&self) -> Value {
+        match self {
+            ResponseFormat::JsonSchema { name, schema, strict } => json!({
+                "type": "json_schema",
+                "name": name,
+                "schema": ResponseFormat::sent_schema(schema, *strict),
+                "strict": strict,
+            }),
+            format => json!(format),
+        }
+    }
+}
+
+impl Serialize for ResponseFormat {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        let format = match self {
+            ResponseFormat::Text => json!({ "type": "text" }),
+            ResponseFormat::JsonObject => json!({ "type": "json_object" }),
+            ResponseFormat::JsonSchema { name, schema, strict } => json!({
+                "type": "json_schema",
+                "json_schema": { "name": name, "schema": ResponseFormat::sent_schema(schema, *strict), "strict": strict },
+            }),
+        };
+        format.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ResponseFormat, Schema};
+    use serde_json::json;
+
+    fn dessert() -> Schema {
+        Schema::object()
+            .property("name", Schema::string())
+            .optional_property("calories", Schema::integer())
+            .optional_property("kind", Schema::string().enumeration(["cake", "cookie"]))
+            .optional_property("toppings", Schema::array(Schema::object().optional_property("name", Schema::string())))
+            .additional_properties(false)
+    }
+
+    #[test]
+    fn strict_schemas_make_optional_properties_nullable() {
+        let format = serde_json::to_value(ResponseFormat::json_schema("dessert", dessert())).unwrap();
+
+        assert_eq!(format["json_schema"]["strict"], json!(true));
+        assert_eq!(format["json_schema"]["schema"], json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "calories": { "type": ["integer", "null"] },
+                "kind": { "type": ["string", "null"], "enum": ["cake", "cookie", null] },
+                "toppings": {
+                    "type": ["array", "null"],
+                    "items": {
+                        "type": "object",
+                        "properties": { "name": { "type": ["string", "null"] } },
+                        "required": ["name"]
+                    }
+                }
+            },
+            "required": ["name", "calories", "kind", "toppings"],
+            "additionalProperties": false
+        }));
+    }
+
+    #[test]
+    fn non_strict_schemas_are_sent_as_built() {
+        let format = ResponseFormat::json_schema("dessert", dessert()).strict(false);
+
+        assert_eq!(serde_json::to_value(&format).unwrap()["json_schema"]["schema"], json!(dessert()));
+        assert_eq!(format.text_format()["strict"], json!(false));
+        assert_eq!(format.text_format()["schema"]["required"], json!(["name"]));
+    }
+}
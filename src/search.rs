@@ -0,0 +1,253 @@
+use crate::client::Client;
+use crate::embedding_store::cosine_similarity;
+use crate::embeddings;
+use crate::error::Error;
+use crate::models::EmbeddingModels;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+/// The most inputs sent in a single embeddings request
+const BATCH_SIZE: usize = 2048;
+
+/// The most embeddings a [`Ranker`] keeps unless set with [`Ranker::max_cached`]
+const MAX_CACHED: usize = 10_000;
+
+/// A document along with its similarity to the query
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedDocument<'a> {
+    /// Position of the document in the documents that were ranked
+    pub index: usize,
+    pub document: &'a str,
+    pub score: f32,
+}
+
+/// Ranks documents by their similarity to a query, keeping document embeddings between calls
+///
+/// Only documents that haven't been embedded before are sent to the API, in batches of up
+/// to 2048 inputs, so ranking the same documents against many queries only embeds them once.
+/// At most 10,000 embeddings are kept unless set with [`max_cached`](Ranker::max_cached),
+/// the least recently ranked are dropped first.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{models, search::Ranker};
+///
+/// async {
+///     let documents = vec!["Chocolate chip cookies", "Vanilla ice cream", "Rust programming"];
+///     let mut ranker = Ranker::new(models::EmbeddingModels::TEXT_EMBEDDING_ADA_002);
+///
+///     let ranked = ranker.rank("Something sweet", &documents).await.expect("Error Getting Response");
+///     for result in ranked {
+///         println!("{:.3} {}", result.score, result.document);
+///     }
+/// };
+/// ```
+pub struct Ranker {
+    client: Option<Client>,
+    model: EmbeddingModels,
+    max_cached: usize,
+    /// Embeddings by text, along with when they were last ranked
+    cache: HashMap<String, (Vec<f32>, u64)>,
+    /// Cached texts by when they were last ranked, least recently ranked first
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl Ranker {
+    pub fn new<M: Into<EmbeddingModels>>(model: M) -> Self {
+        Ranker {
+            client: None,
+            model: model.into(),
+            max_cached: MAX_CACHED,
+            cache: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    /// Send the requests using the given client instead of one loaded from the environment
+    pub fn client(mut self, client: &Client) -> Self {
+        self.client = Some(client.clone());
+        self
+    }
+
+    /// Keep at most `max_cached` embeddings between calls, dropping the least recently
+    /// ranked first. Embeddings needed by a call are kept until it returns.
+    pub fn max_cached(mut self, max_cached: usize) -> Self {
+        self.max_cached = max_cached;
+        self
+    }
+
+    /// The number of embeddings kept for further calls
+    pub fn cached(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Return the documents sorted by similarity to `query`, most similar first
+    pub async fn rank<'a>(&mut self, query: &str, documents: &[&'a str]) -> Result<Vec<RankedDocument<'a>>, Error> {
+        let mut missing: Vec<&str> = Vec::new();
+        for text in std::iter::once(&query).chain(documents.iter()) {
+            if !self.touch(text) && !missing.contains(text) {
+                missing.push(text);
+            }
+        }
+
+        if let Err(e) = self.embed(&missing).await {
+            self.evict();
+            return Err(e);
+        }
+
+        let embedding = |text: &str| &self.cache[text].0;
+        let query_embedding = embedding(query);
+        let mut ranked = Vec::with_capacity(documents.len());
+        for (index, document) in documents.iter().enumerate() {
+            ranked.push(RankedDocument {
+                index,
+                document,
+                score: cosine_similarity(query_embedding, embedding(document)),
+            });
+        }
+        ranked.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+        self.evict();
+        Ok(ranked)
+    }
+
+    /// Mark the cached embedding of `text` as just ranked, `false` if it isn't cached
+    fn touch(&mut self, text: &str) -> bool {
+        self.tick += 1;
+        let Some((_, used)) = self.cache.get_mut(text) else {
+            return false;
+        };
+        let previous = std::mem::replace(used, self.tick);
+        self.recency.remove(&previous);
+        self.recency.insert(self.tick, text.to_string());
+        true
+    }
+
+    fn insert(&mut self, text: &str, embedding: Vec<f32>) {
+        self.tick += 1;
+        if let Some((_, previous)) = self.cache.insert(text.to_string(), (embedding, self.tick)) {
+            self.recency.remove(&previous);
+        }
+        self.recency.insert(self.tick, text.to_string());
+    }
+
+    /// Embed `texts` and cache their embeddings
+    async fn embed(&mut self, texts: &[&str]) -> Result<(), Error> {
+        for batch in texts.chunks(BATCH_SIZE) {
+            let batch = batch.to_vec();
+            let mut request = embeddings::build(self.model.clone()).inputs(&batch);
+            if let Some(client) = &self.client {
+                request = request.client(client);
+            }
+            let response = request.embed().await?;
+            for embedding in response.data {
+                if let Some(text) = batch.get(embedding.index) {
+                    self.insert(text, embedding.embedding);
+                }
+            }
+            let received = batch.iter().filter(|text| self.cache.contains_key(**text)).count();
+            if received < batch.len() {
+                return Err(Error::MissingEmbeddings { expected: batch.len(), received });
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop the least recently ranked embeddings until at most `max_cached` are left
+    fn evict(&mut self) {
+        while self.cache.len() > self.max_cached {
+            let Some((_, text)) = self.recency.pop_first() else {
+                return;
+            };
+            self.cache.remove(&text);
+        }
+    }
+}
+
+/// Return the documents sorted by similarity to `query`, most similar first
+///
+/// Shorthand for ranking once with a new [`Ranker`], use a [`Ranker`] directly to keep the
+/// document embeddings around for further queries.
+pub async fn rank<'a, M: Into<EmbeddingModels>>(query: &str, documents: &[&'a str], model: M) -> Result<Vec<RankedDocument<'a>>, Error> {
+    Ranker::new(model).rank(query, documents).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ranker, BATCH_SIZE};
+    use crate::error::Error;
+    use crate::models::EmbeddingModels;
+    use crate::testing::{CannedResponse, Matcher, TestClient};
+    use serde_json::{json, Value};
+
+    fn embeddings(vectors: &[[f32; 2]]) -> CannedResponse {
+        let data: Vec<Value> = vectors.iter().enumerate()
+            .map(|(index, vector)| json!({ "object": "embedding", "embedding": vector, "index": index }))
+            .collect();
+        CannedResponse::json(&json!({ "object": "list", "data": data, "model": "text-embedding-3-small", "usage": null }))
+    }
+
+    fn inputs(test: &TestClient) -> Vec<Value> {
+        test.requests().into_iter().map(|r| r.body.unwrap()["input"].clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn documents_are_embedded_once_across_calls() {
+        let test = TestClient::new()
+            .on(Matcher::post("embeddings").body("/input", json!(["sweet", "rust", "cookies"])), embeddings(&[[1.0, 0.0], [0.0, 1.0], [0.9, 0.1]]))
+            .on(Matcher::post("embeddings").body("/input", json!(["savoury"])), embeddings(&[[0.0, 1.0]]));
+        let mut ranker = Ranker::new(EmbeddingModels::TEXT_EMBEDDING_3_SMALL).client(&test.client());
+
+        let ranked = ranker.rank("sweet", &["rust", "cookies", "cookies"]).await.unwrap();
+        assert_eq!(ranked.iter().map(|r| r.index).collect::<Vec<_>>(), [1, 2, 0]);
+        let ranked = ranker.rank("savoury", &["cookies", "rust"]).await.unwrap();
+        assert_eq!(ranked[0].document, "rust");
+
+        assert_eq!(inputs(&test), [json!(["sweet", "rust", "cookies"]), json!(["savoury"])]);
+        assert_eq!(ranker.cached(), 4);
+    }
+
+    #[tokio::test]
+    async fn documents_are_embedded_in_batches() {
+        let test = TestClient::new().on(Matcher::post("embeddings"), embeddings(&vec![[1.0, 0.0]; BATCH_SIZE]));
+        let documents: Vec<String> = (0..BATCH_SIZE).map(|i| format!("document {}", i)).collect();
+        let documents: Vec<&str> = documents.iter().map(String::as_str).collect();
+
+        let ranked = Ranker::new(EmbeddingModels::TEXT_EMBEDDING_3_SMALL)
+            .client(&test.client())
+            .rank("query", &documents)
+            .await
+            .unwrap();
+
+        assert_eq!(ranked.len(), BATCH_SIZE);
+        let sizes: Vec<usize> = inputs(&test).iter().map(|input| input.as_array().unwrap().len()).collect();
+        assert_eq!(sizes, [BATCH_SIZE, 1]);
+    }
+
+    #[tokio::test]
+    async fn least_recently_ranked_embeddings_are_dropped() {
+        let test = TestClient::new().on(Matcher::post("embeddings"), embeddings(&[[1.0, 0.0], [0.0, 1.0]]));
+        let mut ranker = Ranker::new(EmbeddingModels::TEXT_EMBEDDING_3_SMALL).client(&test.client()).max_cached(2);
+
+        ranker.rank("query", &["cookies"]).await.unwrap();
+        ranker.rank("query", &["rust"]).await.unwrap();
+        assert_eq!(ranker.cached(), 2);
+        ranker.rank("query", &["rust", "cookies"]).await.unwrap();
+
+        assert_eq!(inputs(&test), [json!(["query", "cookies"]), json!(["rust"]), json!(["cookies"])]);
+    }
+
+    #[tokio::test]
+    async fn short_responses_report_how_many_embeddings_came_back() {
+        let test = TestClient::new().on(Matcher::post("embeddings"), embeddings(&[[1.0, 0.0]]));
+
+        let result = Ranker::new(EmbeddingModels::TEXT_EMBEDDING_3_SMALL)
+            .client(&test.client())
+            .rank("sweet", &["cookies", "rust"])
+            .await;
+
+        assert!(matches!(result, Err(Error::MissingEmbeddings { expected: 3, received: 1 })), "{:?}", result);
+    }
+}
@@ -0,0 +1,82 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::requester::{self, RequestOptions};
+use reqwest::Method;
+use serde_json::Value;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower_service::Service;
+
+/// A request to any endpoint of the API, used with the [`Service`] implementation of [`Client`]
+///
+/// The `path` is relative to the client's base URL, e.g. `completions` or `models/text-davinci-003`.
+#[derive(Debug, Clone)]
+pub struct OpenAiRequest {
+    pub method: Method,
+    pub path: String,
+    pub body: Option<Value>,
+    /// Whether the request is safe to retry, see [`RetryScope::IdempotentOnly`](crate::retry::RetryScope::IdempotentOnly)
+    pub idempotent: bool,
+}
+
+impl OpenAiRequest {
+    /// A `GET` request for the given path
+    pub fn get(path: String) -> Self {
+        OpenAiRequest {
+            method: Method::GET,
+            path,
+            body: None,
+            idempotent: true,
+        }
+    }
+
+    /// A `POST` request for the given path with a JSON body
+    pub fn post(path: String, body: Value) -> Self {
+        OpenAiRequest {
+            method: Method::POST,
+            path,
+            body: Some(body),
+            idempotent: false,
+        }
+    }
+}
+
+/// Allows the client to be wrapped in `tower` middleware, the response is the JSON body
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{service::OpenAiRequest, Client};
+/// use serde_json::json;
+/// use tower_service::Service;
+///
+/// async {
+///     let mut client = Client::from_env().expect("Missing configuration");
+///     let completion = client.call(OpenAiRequest::post(
+///         String::from("completions"),
+///         json!({ "model": "text-davinci-003", "prompt": "Ice cream or cookies?" }),
+///     )).await;
+///
+///     println!("{:?}", completion);
+/// };
+/// ```
+impl Service<OpenAiRequest> for Client {
+    type Response = Value;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Value, Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: OpenAiRequest) -> Self::Future {
+        let options = RequestOptions {
+            client: Some(self.clone()),
+            idempotent: request.idempotent,
+        };
+        Box::pin(async move {
+            requester::raw(&options, request.method, request.path, request.body).await
+        })
+    }
+}
@@ -0,0 +1,813 @@
+use crate::client::Timing;
+use crate::error::Error;
+use crate::requester::{Query, RequestOptions, UsageSink};
+#[cfg(feature = "tokenizer")]
+use crate::tokenizer::Tokenizer;
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_util::StreamExt;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::{sleep, Instant, Sleep};
+
+/// When text written from a stream is flushed to the writer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flush {
+    /// Flush after the text of every chunk is written, e.g. for interactive output
+    EveryChunk,
+    /// Only flush once the stream has ended
+    OnFinish,
+}
+
+type ByteStream = Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>> + Send>>;
+
+/// A stream of server-sent events from a streaming endpoint
+///
+/// Implements [`futures_core::Stream`] yielding each chunk deserialized as `T`, so it can be
+/// used with the `StreamExt` combinators. The stream ends once the API sends `[DONE]`, a
+/// transport error is yielded as an `Err` item and ends the stream.
+///
+/// If the client has a [stream idle timeout](crate::Client::stream_idle_timeout) set and no
+/// data is received for that long, [`Error::StreamIdleTimeout`] is yielded and the stream ends.
+///
+/// Dropping the stream closes the connection straight away, so the API stops generating
+/// the rest of the response. Use [`cancel`](EventStream::cancel) to do the same while
+/// keeping the stream around.
+pub struct EventStream<T> {
+    inner: ByteStream,
+    parser: SseParser,
+    idle_timeout: Option<(Duration, Pin<Box<Sleep>>)>,
+    pending: VecDeque<Result<T, Error>>,
+    done: bool,
+    /// When the request was sent, and how long after that the first item and the end arrived
+    started: Instant,
+    first_item: Option<Duration>,
+    finished: Option<Duration>,
+    /// Where the usage reported by chunks is recorded
+    usage: Option<UsageSink>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> EventStream<T>
+where
+    T: DeserializeOwned
+{
+    pub(crate) fn new(response: reqwest::Response, idle_timeout: Option<Duration>, started: Instant) -> Self {
+        EventStream::from_bytes(Box::pin(response.bytes_stream()), idle_timeout, started)
+    }
+
+    fn from_bytes(inner: ByteStream, idle_timeout: Option<Duration>, started: Instant) -> Self {
+        EventStream {
+            inner,
+            parser: SseParser::default(),
+            idle_timeout: idle_timeout.map(|timeout| (timeout, Box::pin(sleep(timeout)))),
+            pending: VecDeque::new(),
+            done: false,
+            started,
+            first_item: None,
+            finished: None,
+            usage: None,
+            marker: PhantomData,
+        }
+    }
+
+    /// Record the usage reported by chunks, e.g. the last chunk of a stream sent with `include_usage`
+    pub(crate) fn record_usage(mut self, usage: UsageSink) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Stop the stream and close the connection immediately
+    ///
+    /// Chunks that were received but not yet yielded are discarded and the stream ends,
+    /// the next poll returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_util::StreamExt;
+    /// use oai_rs::{completions, models};
+    ///
+    /// async {
+    ///     let mut stream = completions::build(models::CompletionModels::TEXT_DAVINCI_003)
+    ///         .prompt("Write a very long story about cookies")
+    ///         .stream()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     let mut text = String::new();
+    ///     while let Some(Ok(chunk)) = stream.next().await {
+    ///         text.push_str(&chunk.choices[0].text);
+    ///         if text.len() > 100 {
+    ///             stream.cancel();
+    ///         }
+    ///     }
+    /// };
+    /// ```
+    pub fn cancel(&mut self) {
+        self.pending.clear();
+        self.close();
+    }
+
+    /// How long the stream took so far, from sending the request until it ended, and how long
+    /// until its first item arrived
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use futures_util::StreamExt;
+    /// use oai_rs::{completions, models};
+    ///
+    /// async {
+    ///     let mut stream = completions::build(models::CompletionModels::TEXT_DAVINCI_003)
+    ///         .prompt("Ice cream or cookies?")
+    ///         .stream()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     while let Some(chunk) = stream.next().await {
+    ///         print!("{}", chunk.expect("Error Getting Chunk").choices[0].text);
+    ///     }
+    ///     let timing = stream.timing();
+    ///     println!("\nfirst token after {:?}, done after {:?}", timing.time_to_first_token, timing.elapsed);
+    /// };
+    /// ```
+    pub fn timing(&self) -> Timing {
+        Timing {
+            elapsed: self.finished.unwrap_or_else(|| self.started.elapsed()),
+            time_to_first_token: self.first_item,
+        }
+    }
+
+    /// End the stream and drop the response body, which closes the connection
+    fn close(&mut self) {
+        self.finish();
+        self.done = true;
+        self.inner = Box::pin(futures_util::stream::empty());
+        self.idle_timeout = None;
+    }
+
+    /// Record when the stream ended, the first time it does
+    fn finish(&mut self) {
+        if self.finished.is_none() {
+            self.finished = Some(self.started.elapsed());
+        }
+    }
+
+    /// Queue the data of each event, stopping at the `[DONE]` terminator
+    fn push_events(&mut self, events: Vec<String>) {
+        for data in events {
+            if data == "[DONE]" {
+                self.done = true;
+                return;
+            }
+            let item = match &self.usage {
+                Some(usage) => usage.parse(&data),
+                None => serde_json::from_str::<T>(&data).map_err(|e| Error::deserialize(e, &data)),
+            };
+            self.pending.push_back(item);
+        }
+    }
+}
+
+// The buffered items are never pinned, only moved out of the queue
+impl<T> Unpin for EventStream<T> {}
+
+impl<T> Stream for EventStream<T>
+where
+    T: DeserializeOwned
+{
+    type Item = Result<T, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                if self.first_item.is_none() {
+                    self.first_item = Some(self.started.elapsed());
+                }
+                return Poll::Ready(Some(item));
+            }
+            if self.done {
+                self.finish();
+                return Poll::Ready(None);
+            }
+            match self.inner.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(bytes))) => {
+                    if let Some((timeout, timer)) = &mut self.idle_timeout {
+                        let deadline = Instant::now() + *timeout;
+                        timer.as_mut().reset(deadline);
+                    }
+                    let events = self.parser.feed(&bytes);
+                    self.push_events(events);
+                    if self.done {
+                        self.close();
+                    }
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    self.close();
+                    return Poll::Ready(Some(Err(Error::from_reqwest(e))));
+                }
+                Poll::Ready(None) => {
+                    let events = self.parser.finish();
+                    self.push_events(events);
+                    self.done = true;
+                }
+                Poll::Pending => {
+                    if let Some((_, timer)) = &mut self.idle_timeout {
+                        if timer.as_mut().poll(cx).is_ready() {
+                            self.close();
+                            return Poll::Ready(Some(Err(Error::StreamIdleTimeout)));
+                        }
+                    }
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
+
+/// A streamed chunk whose request can be sent again to carry on from the text received so far
+pub(crate) trait Resume: DeserializeOwned + Sized {
+    /// The body parameter resent with the text received so far, e.g. the prompt
+    const RESUMED: &'static str;
+
+    /// Append the text of the chunk's choices to `text`
+    fn push_text(&self, text: &mut String);
+
+    /// The value of [`RESUMED`](Resume::RESUMED) carrying on after `received`
+    fn resumed(original: &Value, received: &str) -> Value;
+
+    /// Send the resumed request
+    fn stream<'f>(options: &'f RequestOptions, query: &'f Query<'_>) -> impl Future<Output = Result<EventStream<Self>, Error>> + Send + 'f;
+}
+
+/// A stream that sends the request again to carry on from the text received so far when
+/// the connection drops part way through
+pub(crate) struct ResumableStream<'a, T> {
+    inner: EventStream<T>,
+    options: RequestOptions,
+    query: Query<'a>,
+    /// The value of the resent parameter as first sent
+    original: Value,
+    /// The text of the choice received so far
+    text: String,
+    /// The key and value of the request's output token limit, if it has one
+    limit: Option<(&'static str, u64)>,
+    #[cfg(feature = "tokenizer")]
+    tokenizer: Option<Tokenizer>,
+    attempts: u32,
+    started: std::time::Instant,
+    /// When the first chunk arrived after starting, across resumed streams
+    first_chunk: Option<Duration>,
+}
+
+impl<'a, T: Resume> ResumableStream<'a, T> {
+    /// Resume `inner`, sent for `model` at `started`, by resending `query` with the value
+    /// the resent parameter was first sent with, up to the given number of times
+    pub(crate) fn new(
+        inner: EventStream<T>,
+        options: RequestOptions,
+        query: Query<'a>,
+        resume: Option<(Value, u32)>,
+        limit: Option<(&'static str, u64)>,
+        model: &str,
+        started: std::time::Instant,
+    ) -> Self {
+        #[cfg(not(feature = "tokenizer"))]
+        let _ = model;
+        let (original, attempts) = resume.unwrap_or_default();
+        ResumableStream {
+            inner,
+            options,
+            query,
+            original,
+            text: String::new(),
+            limit,
+            #[cfg(feature = "tokenizer")]
+            tokenizer: Tokenizer::for_model(model).ok(),
+            attempts,
+            started,
+            first_chunk: None,
+        }
+    }
+
+    pub(crate) async fn next(&mut self) -> Option<Result<T, Error>> {
+        loop {
+            match self.inner.next().await {
+                Some(Ok(chunk)) => {
+                    self.first_chunk.get_or_insert_with(|| self.started.elapsed());
+                    chunk.push_text(&mut self.text);
+                    return Some(Ok(chunk));
+                }
+                Some(Err(e)) if self.attempts > 0 && matches!(e, Error::Interrupted(_) | Error::Timeout | Error::StreamIdleTimeout) => {
+                    self.attempts -= 1;
+                    if let Some((key, limit)) = self.limit {
+                        if let Some(received) = self.received_tokens() {
+                            let remaining = limit.saturating_sub(received);
+                            if remaining == 0 {
+                                return None;
+                            }
+                            self.query.push((key, json!(remaining)));
+                        }
+                    }
+                    self.query.push((T::RESUMED, T::resumed(&self.original, &self.text)));
+                    match T::stream(&self.options, &self.query).await {
+                        Ok(stream) => self.inner = stream,
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                item => return item,
+            }
+        }
+    }
+
+    /// The number of tokens received so far, if they can be counted
+    fn received_tokens(&self) -> Option<u64> {
+        #[cfg(feature = "tokenizer")]
+        if let Some(tokenizer) = &self.tokenizer {
+            return Some(tokenizer.count(&self.text) as u64);
+        }
+        None
+    }
+
+    pub(crate) fn cancel(&mut self) {
+        self.inner.cancel();
+    }
+
+    pub(crate) fn timing(&self) -> Timing {
+        Timing {
+            elapsed: self.started.elapsed(),
+            time_to_first_token: self.first_chunk,
+        }
+    }
+}
+
+/// Best-effort parser for JSON that is still being streamed, e.g. the text of a structured
+/// output or JSON mode answer, to render results before the whole value has arrived
+///
+/// Feed it the text deltas of a [chat stream](crate::chat::Parameters::stream), or of the
+/// [`OutputTextDelta`](crate::responses::ResponseEvent::OutputTextDelta) events of a
+/// [streamed response](crate::responses::Parameters::stream).
+/// [`ChatAccumulator::partial`](crate::chat::ChatAccumulator::partial) parses the answer of a
+/// chat stream the same way.
+///
+/// The text so far is completed into valid JSON by closing any open strings, arrays and
+/// objects, and dropping whatever can't be completed yet: a key without its value, a
+/// dangling `,` or a number or literal that may not be finished. Strings that are still
+/// being streamed are included with the text received so far.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::stream::PartialJson;
+/// use serde::Deserialize;
+/// use serde_json::json;
+///
+/// #[derive(Deserialize)]
+/// struct Answer {
+///     title: Option<String>,
+///     #[serde(default)]
+///     steps: Vec<String>,
+/// }
+///
+/// let mut json = PartialJson::new();
+/// json.push(r#"{"title": "Cook"#);
+/// assert_eq!(json.value(), Some(json!({ "title": "Cook" })));
+///
+/// json.push(r#"ies", "steps": ["Preheat", "Mi"#);
+/// let answer: Answer = json.parse().unwrap();
+/// assert_eq!(answer.title.as_deref(), Some("Cookies"));
+/// assert_eq!(answer.steps, ["Preheat", "Mi"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PartialJson {
+    text: String,
+}
+
+impl PartialJson {
+    /// A parser that has received no text yet
+    pub fn new() -> Self {
+        PartialJson::default()
+    }
+
+    /// Append the next piece of text, e.g. the text of a streamed chunk
+    pub fn push(&mut self, delta: &str) {
+        self.text.push_str(delta);
+    }
+
+    /// All text received so far
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The value so far, `None` before any of it can be completed or if the text isn't JSON
+    pub fn value(&self) -> Option<serde_json::Value> {
+        partial_value(&self.text)
+    }
+
+    /// The value so far deserialized as `T`, `None` if it can't be yet
+    ///
+    /// Fields that haven't arrived are missing, so `T` should make them optional or default.
+    pub fn parse<T: DeserializeOwned>(&self) -> Option<T> {
+        serde_json::from_value(self.value()?).ok()
+    }
+}
+
+/// The value of the JSON streamed so far in `text`, see [`PartialJson`]
+pub(crate) fn partial_value(text: &str) -> Option<serde_json::Value> {
+    serde_json::from_str(&complete_json(text)?).ok()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum JsonFrame {
+    /// An object, and whether the next string in it is a key
+    Object { expects_key: bool },
+    Array,
+}
+
+/// The longest prefix of `text` that can be made into valid JSON, with what has to be
+/// appended to it to do so
+fn complete_json(text: &str) -> Option<String> {
+    let closers = |stack: &[JsonFrame]| -> String {
+        stack.iter().rev().map(|frame| match frame {
+            JsonFrame::Object { .. } => '}',
+            JsonFrame::Array => ']',
+        }).collect()
+    };
+
+    let mut stack: Vec<JsonFrame> = Vec::new();
+    let mut best: Option<(usize, String)> = None;
+    // Whether a string is being read, and whether it's a key
+    let mut string: Option<bool> = None;
+    let mut escape = 0;
+    let mut scalar: Option<usize> = None;
+    let mut done = false;
+
+    for (i, c) in text.char_indices() {
+        if let Some(is_key) = string {
+            if escape > 0 {
+                escape = if escape == 1 && c == 'u' { 4 } else { escape - 1 };
+            } else if c == '\\' {
+                escape = 1;
+            } else if c == '"' {
+                string = None;
+                if is_key {
+                    continue;
+                }
+                best = Some((i + 1, closers(&stack)));
+                done = stack.is_empty();
+                continue;
+            }
+            if !is_key && escape == 0 {
+                let end = i + c.len_utf8();
+                best = Some((end, format!("\"{}", closers(&stack))));
+            }
+            continue;
+        }
+        if let Some(start) = scalar {
+            if !matches!(c, ',' | ']' | '}') && !c.is_whitespace() {
+                continue;
+            }
+            serde_json::from_str::<serde_json::Value>(&text[start..i]).ok()?;
+            scalar = None;
+            best = Some((i, closers(&stack)));
+            done = stack.is_empty();
+        }
+        if c.is_whitespace() {
+            continue;
+        }
+        if done {
+            return None;
+        }
+        match c {
+            '{' | '[' => {
+                stack.push(if c == '{' { JsonFrame::Object { expects_key: true } } else { JsonFrame::Array });
+                best = Some((i + 1, closers(&stack)));
+            }
+            '}' | ']' => {
+                match (stack.pop(), c) {
+                    (Some(JsonFrame::Object { .. }), '}') | (Some(JsonFrame::Array), ']') => {}
+                    _ => return None,
+                }
+                best = Some((i + 1, closers(&stack)));
+                done = stack.is_empty();
+            }
+            ',' => {
+                if let Some(JsonFrame::Object { expects_key }) = stack.last_mut() {
+                    *expects_key = true;
+                }
+            }
+            ':' => {
+                if let Some(JsonFrame::Object { expects_key }) = stack.last_mut() {
+                    *expects_key = false;
+                }
+            }
+            '"' => {
+                let is_key = matches!(stack.last(), Some(JsonFrame::Object { expects_key: true }));
+                string = Some(is_key);
+                if !is_key {
+                    best = Some((i + 1, format!("\"{}", closers(&stack))));
+                }
+            }
+            _ => scalar = Some(i),
+        }
+    }
+    if let Some(start) = scalar {
+        let literal = &text[start..];
+        if matches!(literal, "true" | "false" | "null") {
+            best = Some((text.len(), closers(&stack)));
+        }
+    }
+
+    best.map(|(end, suffix)| format!("{}{}", &text[..end], suffix))
+}
+
+/// Incremental parser for the `text/event-stream` format
+///
+/// Bytes can be fed in arbitrary pieces, events split across reads are buffered until
+/// complete. Lines may end in `\n`, `\r\n` or `\r`, comment lines (e.g. `: keep-alive`)
+/// and fields other than `data` are ignored, and multiple `data` lines in one event are
+/// joined with `\n`.
+#[derive(Debug, Default)]
+pub(crate) struct SseParser {
+    buffer: Vec<u8>,
+    data: Option<String>,
+}
+
+impl SseParser {
+    /// Feed the next bytes read, returning the data of every event completed by them
+    pub(crate) fn feed(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut events = Vec::new();
+        let mut start = 0;
+        while let Some(offset) = self.buffer[start..].iter().position(|b| *b == b'\n' || *b == b'\r') {
+            let end = start + offset;
+            // A trailing `\r` may be the first half of a `\r\n` split across reads
+            if self.buffer[end] == b'\r' && end + 1 == self.buffer.len() {
+                break;
+            }
+            let line = String::from_utf8_lossy(&self.buffer[start..end]).into_owned();
+            start = end + 1;
+            if self.buffer[end] == b'\r' && self.buffer[start] == b'\n' {
+                start += 1;
+            }
+            if let Some(data) = self.process_line(&line) {
+                events.push(data);
+            }
+        }
+        self.buffer.drain(..start);
+        events
+    }
+
+    /// Flush an event left unterminated when the connection closed
+    pub(crate) fn finish(&mut self) -> Vec<String> {
+        let mut events = Vec::new();
+        if !self.buffer.is_empty() {
+            let line = String::from_utf8_lossy(&self.buffer).trim_end_matches('\r').to_string();
+            self.buffer.clear();
+            if let Some(data) = self.process_line(&line) {
+                events.push(data);
+            }
+        }
+        events.extend(self.data.take());
+        events
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<String> {
+        if line.is_empty() {
+            return self.data.take();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        if field == "data" {
+            match &mut self.data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_string()),
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{complete_json, EventStream, SseParser};
+    use bytes::Bytes;
+    use futures_core::Stream;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+
+    // Captured from a streamed `/v1/completions` request
+    const COMPLETION_TRAFFIC: &str = concat!(
+        "data: {\"id\":\"cmpl-6aVvyAMa6SJSfSkP7B6hnKhs5KdMW\",\"object\":\"text_completion\",\"created\":1674055386,\"choices\":[{\"text\":\"\\n\\n\",\"index\":0,\"logprobs\":null,\"finish_reason\":null}],\"model\":\"text-davinci-003\"}\n",
+        "\n",
+        "data: {\"id\":\"cmpl-6aVvyAMa6SJSfSkP7B6hnKhs5KdMW\",\"object\":\"text_completion\",\"created\":1674055386,\"choices\":[{\"text\":\"Cookies\",\"index\":0,\"logprobs\":null,\"finish_reason\":null}],\"model\":\"text-davinci-003\"}\n",
+        "\n",
+        ": keep-alive\n",
+        "\n",
+        "data: {\"id\":\"cmpl-6aVvyAMa6SJSfSkP7B6hnKhs5KdMW\",\"object\":\"text_completion\",\"created\":1674055386,\"choices\":[{\"text\":\"!\",\"index\":0,\"logprobs\":null,\"finish_reason\":\"stop\"}],\"model\":\"text-davinci-003\"}\n",
+        "\n",
+        "data: [DONE]\n",
+        "\n",
+    );
+
+    /// A response body that never ends, recording when it's dropped
+    struct OpenBody {
+        chunks: Vec<Bytes>,
+        dropped: Arc<AtomicBool>,
+    }
+
+    impl Stream for OpenBody {
+        type Item = reqwest::Result<Bytes>;
+
+        fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            match self.chunks.pop() {
+                Some(chunk) => Poll::Ready(Some(Ok(chunk))),
+                None => Poll::Pending,
+            }
+        }
+    }
+
+    impl Drop for OpenBody {
+        fn drop(&mut self) {
+            self.dropped.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn open_stream() -> (EventStream<serde_json::Value>, Arc<AtomicBool>) {
+        let dropped = Arc::new(AtomicBool::new(false));
+        let body = OpenBody {
+            chunks: vec![Bytes::from_static(b"data: {\"n\":1}\n\ndata: {\"n\":2}\n\n")],
+            dropped: dropped.clone(),
+        };
+        (EventStream::from_bytes(Box::pin(body), None, tokio::time::Instant::now()), dropped)
+    }
+
+    fn poll(stream: &mut EventStream<serde_json::Value>) -> Poll<Option<serde_json::Value>> {
+        let waker = futures_util::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        Pin::new(stream).poll_next(&mut cx).map(|item| item.map(|r| r.unwrap()))
+    }
+
+    #[test]
+    fn cancel_closes_connection_immediately() {
+        let (mut stream, dropped) = open_stream();
+        assert_eq!(poll(&mut stream), Poll::Ready(Some(serde_json::json!({"n": 1}))));
+        assert!(!dropped.load(Ordering::SeqCst));
+
+        stream.cancel();
+        assert!(dropped.load(Ordering::SeqCst));
+        assert_eq!(poll(&mut stream), Poll::Ready(None));
+    }
+
+    #[test]
+    fn drop_closes_connection() {
+        let (mut stream, dropped) = open_stream();
+        assert!(poll(&mut stream).is_ready());
+        drop(stream);
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+
+    fn feed_in_pieces(input: &[u8], size: usize) -> Vec<String> {
+        let mut parser = SseParser::default();
+        let mut events = Vec::new();
+        for piece in input.chunks(size) {
+            events.extend(parser.feed(piece));
+        }
+        events.extend(parser.finish());
+        events
+    }
+
+    #[test]
+    fn parses_captured_traffic() {
+        let events = feed_in_pieces(COMPLETION_TRAFFIC.as_bytes(), COMPLETION_TRAFFIC.len());
+        assert_eq!(events.len(), 4);
+        assert!(events[1].contains("\"text\":\"Cookies\""));
+        assert_eq!(events[3], "[DONE]");
+    }
+
+    #[test]
+    fn handles_events_split_across_reads() {
+        let whole = feed_in_pieces(COMPLETION_TRAFFIC.as_bytes(), COMPLETION_TRAFFIC.len());
+        for size in [1, 2, 3, 7, 64] {
+            assert_eq!(feed_in_pieces(COMPLETION_TRAFFIC.as_bytes(), size), whole);
+        }
+    }
+
+    #[test]
+    fn handles_crlf_and_cr_line_endings() {
+        let crlf = COMPLETION_TRAFFIC.replace('\n', "\r\n");
+        let cr = COMPLETION_TRAFFIC.replace('\n', "\r");
+        let whole = feed_in_pieces(COMPLETION_TRAFFIC.as_bytes(), COMPLETION_TRAFFIC.len());
+        for size in [1, 5, crlf.len()] {
+            assert_eq!(feed_in_pieces(crlf.as_bytes(), size), whole);
+            assert_eq!(feed_in_pieces(cr.as_bytes(), size), whole);
+        }
+    }
+
+    #[test]
+    fn ignores_comments_and_other_fields() {
+        let input = ": keep-alive\n\nevent: completion\nid: 1\nretry: 1000\ndata: {}\n\n:\n\n";
+        assert_eq!(feed_in_pieces(input.as_bytes(), 3), vec!["{}"]);
+    }
+
+    #[test]
+    fn joins_multi_line_data() {
+        let input = "data: {\"a\":\ndata:1}\n\n";
+        assert_eq!(feed_in_pieces(input.as_bytes(), 4), vec!["{\"a\":\n1}"]);
+    }
+
+    #[test]
+    fn keeps_multi_byte_characters_split_across_reads() {
+        let input = "data: {\"text\":\"caf\u{e9} \u{1f36a}\"}\n\n";
+        assert_eq!(feed_in_pieces(input.as_bytes(), 1), vec!["{\"text\":\"caf\u{e9} \u{1f36a}\"}"]);
+    }
+
+    #[test]
+    fn flushes_unterminated_event_on_finish() {
+        assert_eq!(feed_in_pieces(b"data: [DONE]", 5), vec!["[DONE]"]);
+    }
+
+    #[test]
+    fn completes_open_containers_and_strings() {
+        assert_eq!(complete_json(""), None);
+        assert_eq!(complete_json("{"), Some(String::from("{}")));
+        assert_eq!(complete_json(r#"{"title": "Coo"#), Some(String::from(r#"{"title": "Coo"}"#)));
+        assert_eq!(complete_json(r#"["a", "b"#), Some(String::from(r#"["a", "b"]"#)));
+        assert_eq!(complete_json(r#"{"a": {"b": ["#), Some(String::from(r#"{"a": {"b": []}}"#)));
+    }
+
+    #[test]
+    fn drops_keys_without_values() {
+        assert_eq!(complete_json(r#"{"a": 1, "ti"#), Some(String::from(r#"{"a": 1}"#)));
+        assert_eq!(complete_json(r#"{"a": 1, "title""#), Some(String::from(r#"{"a": 1}"#)));
+        assert_eq!(complete_json(r#"{"a": 1, "title":"#), Some(String::from(r#"{"a": 1}"#)));
+        assert_eq!(complete_json(r#"{"a\"b": "#), Some(String::from("{}")));
+    }
+
+    #[test]
+    fn never_cuts_inside_escapes() {
+        assert_eq!(complete_json(r#"["line\"#), Some(String::from(r#"["line"]"#)));
+        assert_eq!(complete_json(r#"["line\n"#), Some(String::from(r#"["line\n"]"#)));
+        assert_eq!(complete_json(r#"["say \"hi"#), Some(String::from(r#"["say \"hi"]"#)));
+        for cut in [r#"["caf\u"#, r#"["caf\u00"#, r#"["caf\u00e"#] {
+            assert_eq!(complete_json(cut), Some(String::from(r#"["caf"]"#)));
+        }
+        assert_eq!(complete_json(r#"["caf\u00e9"#), Some(String::from(r#"["caf\u00e9"]"#)));
+    }
+
+    #[test]
+    fn drops_trailing_commas() {
+        assert_eq!(complete_json("[1, 2,"), Some(String::from("[1, 2]")));
+        assert_eq!(complete_json(r#"{"a": "x", "#), Some(String::from(r#"{"a": "x"}"#)));
+    }
+
+    #[test]
+    fn drops_numbers_and_literals_that_may_not_be_finished() {
+        assert_eq!(complete_json(r#"{"n": 12"#), Some(String::from("{}")));
+        assert_eq!(complete_json(r#"{"n": 12,"#), Some(String::from(r#"{"n": 12}"#)));
+        assert_eq!(complete_json("[1.5e"), Some(String::from("[]")));
+        assert_eq!(complete_json(r#"{"ok": tru"#), Some(String::from("{}")));
+        assert_eq!(complete_json(r#"{"ok": true"#), Some(String::from(r#"{"ok": true}"#)));
+        assert_eq!(complete_json("[null"), Some(String::from("[null]")));
+        assert_eq!(complete_json("12"), None);
+    }
+
+    #[test]
+    fn completes_nested_arrays() {
+        assert_eq!(complete_json("[[1, 2], [3"), Some(String::from("[[1, 2], []]")));
+        assert_eq!(complete_json("[[1, 2], [3,"), Some(String::from("[[1, 2], [3]]")));
+        assert_eq!(complete_json(r#"[[["deep"#), Some(String::from(r#"[[["deep"]]]"#)));
+    }
+
+    #[test]
+    fn rejects_data_after_a_complete_value() {
+        assert_eq!(complete_json(r#"{"a": 1} "#), Some(String::from(r#"{"a": 1}"#)));
+        assert_eq!(complete_json(r#"{"a": 1} x"#), None);
+        assert_eq!(complete_json("[] []"), None);
+        assert_eq!(complete_json(r#""done" 1"#), None);
+    }
+
+    #[test]
+    fn rejects_mismatched_brackets_and_invalid_scalars() {
+        assert_eq!(complete_json("[1}"), None);
+        assert_eq!(complete_json("[-,"), None);
+    }
+}
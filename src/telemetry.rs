@@ -0,0 +1,152 @@
+//! Reports requests to `tracing` when the `tracing` feature is enabled, and as OpenTelemetry
+//! metrics when the `opentelemetry` feature is enabled and a meter is set on the client
+#[cfg(feature = "opentelemetry")]
+use crate::error::Error;
+use crate::usage::Usage;
+use reqwest::{Method, StatusCode};
+use std::future::Future;
+
+/// Run a request's future inside a span named `oai_rs.request`
+///
+/// The span records the `method`, `endpoint` and `correlation_id` up front, and the `model`, `status`,
+/// `retries`, `prompt_tokens`, `cached_tokens`, `completion_tokens` and `cost_usd` fields as they become known.
+#[cfg(feature = "tracing")]
+pub(crate) fn instrument<F: Future>(future: F, method: &Method, path: &str, correlation_id: &str) -> impl Future<Output = F::Output> {
+    use tracing::field::Empty;
+    use tracing::Instrument;
+
+    let span = tracing::info_span!(
+        "oai_rs.request",
+        method = %method,
+        endpoint = %path,
+        correlation_id = %correlation_id,
+        model = Empty,
+        status = Empty,
+        retries = Empty,
+        prompt_tokens = Empty,
+        cached_tokens = Empty,
+        completion_tokens = Empty,
+        cost_usd = Empty,
+    );
+    future.instrument(span)
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn instrument<F: Future>(future: F, _method: &Method, _path: &str, _correlation_id: &str) -> impl Future<Output = F::Output> {
+    future
+}
+
+/// Record the final status of a request and how many times it was retried
+pub(crate) fn record_response(status: Option<StatusCode>, retries: u32) {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::Span::current();
+        if let Some(status) = status {
+            span.record("status", status.as_u16());
+        }
+        span.record("retries", retries);
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = (status, retries);
+}
+
+/// Record the model a response came from and the usage it reported
+pub(crate) fn record_usage(model: Option<&str>, usage: Option<&Usage>) {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::Span::current();
+        if let Some(model) = model {
+            span.record("model", model);
+        }
+        if let Some(usage) = usage {
+            span.record("prompt_tokens", usage.prompt_tokens);
+            span.record("cached_tokens", usage.cached_tokens());
+            span.record("completion_tokens", usage.completion_tokens);
+            if let Some(cost) = model.and_then(|m| usage.estimated_cost(m)) {
+                span.record("cost_usd", cost);
+            }
+        }
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = (model, usage);
+}
+
+/// Whether responses have to be parsed for their model and usage even when nothing else uses them
+pub(crate) const RECORDS_USAGE: bool = cfg!(feature = "tracing");
+
+/// Instruments recording requests to an OpenTelemetry meter, see [`Client::meter`](crate::Client::meter)
+#[cfg(feature = "opentelemetry")]
+#[derive(Clone)]
+pub(crate) struct Metrics {
+    requests: opentelemetry::metrics::Counter<u64>,
+    errors: opentelemetry::metrics::Counter<u64>,
+    duration: opentelemetry::metrics::Histogram<f64>,
+    tokens: opentelemetry::metrics::Counter<u64>,
+}
+
+#[cfg(feature = "opentelemetry")]
+impl Metrics {
+    pub(crate) fn new(meter: &opentelemetry::metrics::Meter) -> Self {
+        Metrics {
+            requests: meter.u64_counter("oai_rs.requests")
+                .with_description("Requests made, counted once however many times they were retried")
+                .build(),
+            errors: meter.u64_counter("oai_rs.errors")
+                .with_description("Requests that failed, by error class")
+                .build(),
+            duration: meter.f64_histogram("oai_rs.request.duration")
+                .with_description("Time until a request's response was handled, including any retries")
+                .with_unit("s")
+                .build(),
+            tokens: meter.u64_counter("oai_rs.tokens")
+                .with_description("Tokens used, by model and token type")
+                .build(),
+        }
+    }
+
+    /// Record a finished request to `path`, and its error class if it failed
+    pub(crate) fn record_request<T>(&self, path: &str, result: &Result<T, Error>, elapsed: std::time::Duration) {
+        use opentelemetry::KeyValue;
+
+        let endpoint = [KeyValue::new("endpoint", path.to_string())];
+        self.requests.add(1, &endpoint);
+        self.duration.record(elapsed.as_secs_f64(), &endpoint);
+        if let (Err(e), [endpoint]) = (result, endpoint) {
+            self.errors.add(1, &[endpoint, KeyValue::new("error.class", error_class(e))]);
+        }
+    }
+
+    /// Record the tokens a response from `model` reported using
+    pub(crate) fn record_tokens(&self, model: Option<&str>, usage: &Usage) {
+        use opentelemetry::KeyValue;
+
+        let model = KeyValue::new("model", model.unwrap_or("unknown").to_string());
+        let prompt = usage.prompt_tokens.max(0) as u64;
+        let completion = usage.completion_tokens.max(0) as u64;
+        let cached = usage.cached_tokens().max(0) as u64;
+        self.tokens.add(prompt, &[model.clone(), KeyValue::new("token.type", "prompt")]);
+        self.tokens.add(cached, &[model.clone(), KeyValue::new("token.type", "cached")]);
+        self.tokens.add(completion, &[model, KeyValue::new("token.type", "completion")]);
+    }
+}
+
+/// The class of an error, low-cardinality enough for a metric attribute
+#[cfg(feature = "opentelemetry")]
+fn error_class(error: &Error) -> &'static str {
+    match error {
+        Error::Api(_) | Error::Status(_) => match error.status() {
+            Some(StatusCode::TOO_MANY_REQUESTS) => "rate_limited",
+            Some(status) if status.is_server_error() => "server",
+            _ => "client",
+        },
+        Error::Request(_) => "transport",
+        Error::Timeout => "timeout",
+        Error::Connect => "connect",
+        Error::CircuitOpen => "circuit_open",
+        Error::StreamIdleTimeout => "stream_timeout",
+        Error::Interrupted(_) => "interrupted",
+        Error::Deserialize { .. } => "deserialize",
+        Error::BudgetExceeded => "budget_exceeded",
+        _ => "other",
+    }
+}
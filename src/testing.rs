@@ -0,0 +1,670 @@
+//! Deterministic clients for testing code built on this crate, without a network
+//!
+//! A [`TestClient`] answers requests with canned responses picked by [`Matcher`]s, so unit
+//! tests can exercise success, error and streaming paths the same way every run. A
+//! [`FaultInjector`] wraps a transport and fails a share of its requests, to test retries
+//! and circuit breaking. [`assert_snapshot`] compares the requests code builds against
+//! golden files, so changes to their shape show up as failing tests.
+use crate::client::Client;
+use crate::retry::RetryPolicy;
+use crate::transport::{Transport, TransportError, TransportFuture};
+use reqwest::{Body, Method, Request, Response, StatusCode};
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// The base URL of clients created by a [`TestClient`], requests never leave the process
+const TEST_BASE_URL: &str = "https://oai-rs.test/v1";
+
+/// Which requests a canned response answers
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::testing::Matcher;
+/// use serde_json::json;
+///
+/// let matcher = Matcher::post("chat/completions").body("/model", json!("gpt-4o-mini"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    method: Option<Method>,
+    path: Option<String>,
+    body: Vec<(String, Value)>,
+}
+
+impl Matcher {
+    /// Match every request
+    pub fn any() -> Self {
+        Matcher {
+            method: None,
+            path: None,
+            body: Vec::new(),
+        }
+    }
+
+    /// Match requests with the given method to the given endpoint path, e.g. `embeddings`
+    pub fn new(method: Method, path: &str) -> Self {
+        Matcher {
+            method: Some(method),
+            path: Some(path.trim_matches('/').to_string()),
+            body: Vec::new(),
+        }
+    }
+
+    /// Match `GET` requests to the given endpoint path
+    pub fn get(path: &str) -> Self {
+        Matcher::new(Method::GET, path)
+    }
+
+    /// Match `POST` requests to the given endpoint path
+    pub fn post(path: &str) -> Self {
+        Matcher::new(Method::POST, path)
+    }
+
+    /// Only match requests whose JSON body has `value` at the JSON pointer `pointer`,
+    /// e.g. `/model` or `/messages/0/content`
+    pub fn body(mut self, pointer: &str, value: Value) -> Self {
+        self.body.push((pointer.to_string(), value));
+        self
+    }
+
+    fn matches(&self, request: &RecordedRequest) -> bool {
+        self.method.as_ref().is_none_or(|m| *m == request.method)
+            && self.path.as_ref().is_none_or(|p| *p == request.path)
+            && self.body.iter().all(|(pointer, value)| {
+                request.body.as_ref().and_then(|b| b.pointer(pointer)) == Some(value)
+            })
+    }
+}
+
+/// A fixed response returned for matching requests
+#[derive(Debug, Clone)]
+pub struct CannedResponse {
+    status: StatusCode,
+    content_type: &'static str,
+    body: String,
+    /// Whether the connection drops once the body is sent
+    interrupted: bool,
+}
+
+impl CannedResponse {
+    /// A `200 OK` response with `body` serialized as JSON, e.g. a
+    /// [`ChatCompletion`](crate::chat::ChatCompletion)
+    pub fn json<T: Serialize>(body: &T) -> Self {
+        CannedResponse {
+            status: StatusCode::OK,
+            content_type: "application/json",
+            body: serde_json::to_string(body).expect("canned responses serialize to JSON"),
+            interrupted: false,
+        }
+    }
+
+    /// An error response in the API's format, returned as [`Error::Api`](crate::Error::Api)
+    pub fn error(status: StatusCode, message: &str) -> Self {
+        CannedResponse {
+            status,
+            content_type: "application/json",
+            body: json!({
+                "error": { "message": message, "type": "invalid_request_error", "param": null, "code": null }
+            }).to_string(),
+            interrupted: false,
+        }
+    }
+
+    /// A streamed response sending each of `chunks` as an event, followed by `[DONE]`
+    pub fn stream<T: Serialize>(chunks: &[T]) -> Self {
+        let mut response = CannedResponse::interrupted_stream(chunks);
+        response.body.push_str("data: [DONE]\n\n");
+        response.interrupted = false;
+        response
+    }
+
+    /// A streamed response sending each of `chunks` as an event, after which the connection
+    /// drops before `[DONE]`, yielded by the stream as [`Error::Interrupted`](crate::Error::Interrupted)
+    pub fn interrupted_stream<T: Serialize>(chunks: &[T]) -> Self {
+        let mut body = String::new();
+        for chunk in chunks.iter() {
+            let data = serde_json::to_string(chunk).expect("canned responses serialize to JSON");
+            body.push_str(&format!("data: {}\n\n", data));
+        }
+        CannedResponse {
+            status: StatusCode::OK,
+            content_type: "text/event-stream",
+            body,
+            interrupted: true,
+        }
+    }
+
+    /// A response with the given status and raw body, e.g. to test malformed responses
+    pub fn raw(status: StatusCode, body: &str) -> Self {
+        CannedResponse {
+            status,
+            content_type: "application/json",
+            body: body.to_string(),
+            interrupted: false,
+        }
+    }
+
+    fn response(&self) -> Response {
+        let body = if self.interrupted {
+            let dropped = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "connection dropped (canned)");
+            Body::wrap_stream(futures_util::stream::iter([Ok(self.body.clone()), Err(dropped)]))
+        } else {
+            Body::from(self.body.clone())
+        };
+        let response = http::Response::builder()
+            .status(self.status)
+            .header("content-type", self.content_type)
+            .body(body)
+            .expect("canned responses are valid");
+        Response::from(response)
+    }
+}
+
+/// A request received by a [`TestClient`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedRequest {
+    pub method: Method,
+    /// The endpoint path, e.g. `chat/completions`
+    pub path: String,
+    /// The JSON body, `None` for requests without one or with a multipart body
+    pub body: Option<Value>,
+}
+
+impl Serialize for RecordedRequest {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("method", self.method.as_str())?;
+        map.serialize_entry("path", &self.path)?;
+        map.serialize_entry("body", &self.body)?;
+        map.end()
+    }
+}
+
+/// Answers requests with canned responses, without a network
+///
+/// Responses are picked by the first [`Matcher`] that matches the request, in the order
+/// they were added, and can answer any number of requests. Requests nothing matches are
+/// answered with `404 Not Found`. Every request is recorded and can be checked afterwards
+/// with [`requests`](TestClient::requests).
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::testing::{CannedResponse, Matcher, TestClient};
+/// use oai_rs::{chat::{self, ChatMessage}, models};
+/// use reqwest::StatusCode;
+/// use serde_json::json;
+///
+/// async {
+///     let test = TestClient::new()
+///         .on(Matcher::post("chat/completions"), CannedResponse::json(&json!({
+///             "model": "gpt-4o-mini",
+///             "system_fingerprint": null,
+///             "choices": [{
+///                 "index": 0,
+///                 "message": { "role": "assistant", "content": "Cookies." },
+///                 "finish_reason": "stop"
+///             }],
+///             "usage": null
+///         })))
+///         .on(Matcher::post("embeddings"), CannedResponse::error(StatusCode::TOO_MANY_REQUESTS, "Rate limit reached"));
+///
+///     let messages = vec![ChatMessage::user("Ice cream or cookies?")];
+///     let completion = chat::build(models::ChatModels::GPT_4O_MINI)
+///         .client(&test.client())
+///         .messages(&messages)
+///         .complete()
+///         .await
+///         .expect("Error Getting Response");
+///
+///     assert_eq!(completion.choices[0].message.text(), "Cookies.");
+///     assert_eq!(test.requests()[0].path, "chat/completions");
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TestClient {
+    routes: Arc<Mutex<Vec<(Matcher, CannedResponse)>>>,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl TestClient {
+    /// A test client without any canned responses
+    pub fn new() -> Self {
+        TestClient::default()
+    }
+
+    /// Answer requests matching `matcher` with `response`
+    pub fn on(self, matcher: Matcher, response: CannedResponse) -> Self {
+        self.routes.lock().unwrap().push((matcher, response));
+        self
+    }
+
+    /// A client whose requests are answered by this test client, retries are turned off so
+    /// every request is answered exactly once
+    pub fn client(&self) -> Client {
+        Client::new(String::from("sk-test"))
+            .base_url(String::from(TEST_BASE_URL))
+            .retry_policy(RetryPolicy::none())
+            .transport(self.clone())
+    }
+
+    /// Every request received so far, in the order they were received
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// The most recent request received, `None` before the first one
+    pub fn last_request(&self) -> Option<RecordedRequest> {
+        self.requests.lock().unwrap().last().cloned()
+    }
+}
+
+impl Transport for TestClient {
+    fn execute(&self, request: Request) -> TransportFuture {
+        let path = request.url().path();
+        let path = path.strip_prefix("/v1/").unwrap_or(path).trim_matches('/').to_string();
+        let body = request.body()
+            .and_then(|b| b.as_bytes())
+            .and_then(|b| serde_json::from_slice(b).ok());
+        let recorded = RecordedRequest {
+            method: request.method().clone(),
+            path,
+            body,
+        };
+
+        let response = self.routes.lock().unwrap().iter()
+            .find(|(matcher, _)| matcher.matches(&recorded))
+            .map(|(_, response)| response.response());
+        let response = response.unwrap_or_else(|| {
+            let message = format!("no canned response for {} {}", recorded.method, recorded.path);
+            CannedResponse::error(StatusCode::NOT_FOUND, &message).response()
+        });
+        self.requests.lock().unwrap().push(recorded);
+        Box::pin(async move { Ok::<_, TransportError>(response) })
+    }
+}
+
+/// The environment variable that makes [`assert_snapshot`] write golden files instead of
+/// comparing against them
+pub const UPDATE_SNAPSHOTS: &str = "OAI_RS_UPDATE_SNAPSHOTS";
+
+/// Assert that `actual` serializes to the JSON in the golden file at `path`
+///
+/// Objects are compared regardless of key order. Run the tests with
+/// [`OAI_RS_UPDATE_SNAPSHOTS=1`](UPDATE_SNAPSHOTS) to write the golden files, e.g. for
+/// a new test or after an intended change, and review the differences before committing
+/// them. Relative paths are resolved against the working directory, which is the package
+/// root under `cargo test`.
+///
+/// # Panics
+///
+/// When the golden file is missing or unreadable, or holds different JSON than `actual`.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::testing::{assert_snapshot, TestClient};
+/// use oai_rs::{chat::{self, ChatMessage}, models};
+///
+/// async {
+///     let test = TestClient::new();
+///     let messages = vec![ChatMessage::user("Ice cream or cookies?")];
+///
+///     // Nothing answers the request, only its shape is checked
+///     let _ = chat::build(models::ChatModels::GPT_4O_MINI)
+///         .client(&test.client())
+///         .messages(&messages)
+///         .max_completion_tokens(64)
+///         .complete()
+///         .await;
+///
+///     assert_snapshot("tests/snapshots/dessert.json", &test.last_request());
+/// };
+/// ```
+#[track_caller]
+pub fn assert_snapshot<P: AsRef<Path>, T: Serialize>(path: P, actual: &T) {
+    let path = path.as_ref();
+    let actual = serde_json::to_value(actual).expect("snapshots serialize to JSON");
+
+    if std::env::var_os(UPDATE_SNAPSHOTS).is_some_and(|v| !v.is_empty() && v != "0") {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).expect("snapshot directory can be created");
+        }
+        let pretty = serde_json::to_string_pretty(&actual).expect("snapshots serialize to JSON");
+        std::fs::write(path, pretty + "\n").expect("snapshot can be written");
+        return;
+    }
+
+    let golden = match std::fs::read_to_string(path) {
+        Ok(golden) => golden,
+        Err(e) => panic!(
+            "can't read snapshot {}: {}, run with {}=1 to write it",
+            path.display(), e, UPDATE_SNAPSHOTS
+        ),
+    };
+    let expected: Value = serde_json::from_str(&golden)
+        .unwrap_or_else(|e| panic!("snapshot {} isn't valid JSON: {}", path.display(), e));
+    if actual != expected {
+        panic!(
+            "snapshot {} doesn't match, run with {}=1 to update it\n\nexpected:\n{}\n\nactual:\n{}",
+            path.display(),
+            UPDATE_SNAPSHOTS,
+            serde_json::to_string_pretty(&expected).unwrap(),
+            serde_json::to_string_pretty(&actual).unwrap(),
+        );
+    }
+}
+
+/// The golden file holding the snapshot `name` of this crate's own tests
+#[cfg(test)]
+pub(crate) fn golden(name: &str) -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("src/snapshots").join(name).with_extension("json")
+}
+
+/// A failure a [`FaultInjector`] can inject in place of a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fault {
+    /// A `429 Too Many Requests` response
+    RateLimited,
+    /// A `500 Internal Server Error` response
+    ServerError,
+    /// A timeout, as if no response arrived in time
+    Timeout,
+    /// A connection failure, as if the API couldn't be reached
+    Connect,
+    /// A `200 OK` response whose body is cut off halfway through the JSON
+    MalformedJson,
+}
+
+impl Fault {
+    fn inject(self) -> Result<Response, TransportError> {
+        let response = match self {
+            Fault::RateLimited => CannedResponse::error(StatusCode::TOO_MANY_REQUESTS, "Rate limit reached (injected)"),
+            Fault::ServerError => CannedResponse::error(StatusCode::INTERNAL_SERVER_ERROR, "The server had an error (injected)"),
+            Fault::Timeout => return Err(TransportError::Timeout),
+            Fault::Connect => return Err(TransportError::Connect),
+            Fault::MalformedJson => CannedResponse::raw(StatusCode::OK, "{\"id\": \"injected\", \"choices\": ["),
+        };
+        Ok(response.response())
+    }
+}
+
+/// Wraps a transport and injects failures into a share of its requests
+///
+/// Each request draws a fault from the configured rates, and is passed on to the inner
+/// transport if none is drawn. Draws come from a seeded generator, so the same seed fails
+/// the same requests every run.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::testing::{Fault, FaultInjector};
+/// use oai_rs::transport::HttpTransport;
+/// use oai_rs::{retry::RetryPolicy, Client};
+///
+/// let faults = FaultInjector::new(HttpTransport::new())
+///     .rate(Fault::RateLimited, 0.2)
+///     .rate(Fault::Timeout, 0.05)
+///     .seed(7);
+/// let client = Client::new(String::from("sk-..."))
+///     .retry_policy(RetryPolicy::default().max_retries(5))
+///     .transport(faults.clone());
+///
+/// // ...exercise the application, then check how often each fault was hit
+/// println!("{} rate limits injected", faults.injected(Fault::RateLimited));
+/// ```
+pub struct FaultInjector<T> {
+    inner: Arc<T>,
+    rates: Vec<(Fault, f64)>,
+    state: Arc<Mutex<FaultState>>,
+}
+
+struct FaultState {
+    seed: u64,
+    injected: HashMap<Fault, u64>,
+}
+
+impl FaultState {
+    /// The next draw in `[0, 1)`, from a xorshift generator
+    fn draw(&mut self) -> f64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        (self.seed >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl<T: Transport> FaultInjector<T> {
+    /// Wrap `inner` without injecting any faults yet
+    pub fn new(inner: T) -> Self {
+        FaultInjector {
+            inner: Arc::new(inner),
+            rates: Vec::new(),
+            state: Arc::new(Mutex::new(FaultState { seed: 0x9e37_79b9_7f4a_7c15, injected: HashMap::new() })),
+        }
+    }
+
+    /// Inject `fault` into `rate` of the requests, between 0 and 1
+    pub fn rate(mut self, fault: Fault, rate: f64) -> Self {
+        self.rates.retain(|(f, _)| *f != fault);
+        self.rates.push((fault, rate.clamp(0.0, 1.0)));
+        self
+    }
+
+    /// Seed the generator that picks the failing requests
+    pub fn seed(self, seed: u64) -> Self {
+        // Spread the bits of small seeds, xorshift draws stay close to 0 for a while otherwise
+        let mut mixed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        self.state.lock().unwrap().seed = (mixed ^ (mixed >> 31)).max(1);
+        self
+    }
+
+    /// How many times `fault` was injected so far
+    pub fn injected(&self, fault: Fault) -> u64 {
+        self.state.lock().unwrap().injected.get(&fault).copied().unwrap_or(0)
+    }
+}
+
+impl<T: Transport> Transport for FaultInjector<T> {
+    fn execute(&self, request: Request) -> TransportFuture {
+        let fault = {
+            let mut state = self.state.lock().unwrap();
+            let draw = state.draw();
+            let mut cumulative = 0.0;
+            let fault = self.rates.iter().find(|(_, rate)| {
+                cumulative += rate;
+                draw < cumulative
+            }).map(|(fault, _)| *fault);
+            if let Some(fault) = fault {
+                *state.injected.entry(fault).or_insert(0) += 1;
+            }
+            fault
+        };
+        match fault {
+            Some(fault) => Box::pin(async move { fault.inject() }),
+            None => self.inner.execute(request),
+        }
+    }
+}
+
+impl<T> Clone for FaultInjector<T> {
+    fn clone(&self) -> Self {
+        FaultInjector {
+            inner: self.inner.clone(),
+            rates: self.rates.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for FaultInjector<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FaultInjector")
+            .field("rates", &self.rates)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::models::{CompletionModels, EmbeddingModels};
+    use crate::{completions, embeddings};
+    use futures_util::StreamExt;
+
+    fn embeddings_answer(model: &str) -> CannedResponse {
+        CannedResponse::json(&json!({
+            "object": "list",
+            "data": [{ "object": "embedding", "embedding": [0.5], "index": 0 }],
+            "model": model,
+            "usage": null
+        }))
+    }
+
+    async fn embed(client: &Client, dimensions: Option<u32>) -> Result<embeddings::Embeddings, Error> {
+        let request = embeddings::build(EmbeddingModels::TEXT_EMBEDDING_3_SMALL)
+            .client(client)
+            .input("The food was delicious");
+        match dimensions {
+            Some(dimensions) => request.dimensions(dimensions).embed().await,
+            None => request.embed().await,
+        }
+    }
+
+    #[tokio::test]
+    async fn first_matching_route_answers() {
+        let test = TestClient::new()
+            .on(Matcher::get("models"), embeddings_answer("models"))
+            .on(Matcher::post("embeddings"), embeddings_answer("first"))
+            .on(Matcher::any(), embeddings_answer("any"));
+
+        assert_eq!(embed(&test.client(), None).await.unwrap().model, "first");
+        assert_eq!(embed(&test.client(), None).await.unwrap().model, "first");
+        assert_eq!(test.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn routes_on_body_pointers() {
+        let test = TestClient::new()
+            .on(Matcher::post("embeddings").body("/dimensions", json!(256)), embeddings_answer("small"))
+            .on(Matcher::post("embeddings").body("/model", json!("text-embedding-3-small")), embeddings_answer("full"));
+
+        assert_eq!(embed(&test.client(), Some(256)).await.unwrap().model, "small");
+        assert_eq!(embed(&test.client(), Some(512)).await.unwrap().model, "full");
+        assert_eq!(embed(&test.client(), None).await.unwrap().model, "full");
+    }
+
+    #[tokio::test]
+    async fn unmatched_requests_get_not_found() {
+        let test = TestClient::new().on(Matcher::post("chat/completions"), embeddings_answer("chat"));
+
+        match embed(&test.client(), None).await {
+            Err(Error::Api(e)) => {
+                assert_eq!(e.status, StatusCode::NOT_FOUND);
+                assert_eq!(e.message, "no canned response for POST embeddings");
+            }
+            other => panic!("expected a 404, got {:?}", other),
+        }
+        let recorded = test.last_request().unwrap();
+        assert_eq!((recorded.method, recorded.path.as_str()), (Method::POST, "embeddings"));
+    }
+
+    #[tokio::test]
+    async fn streams_canned_chunks() {
+        let chunk = |text: &str| json!({
+            "id": "cmpl-1",
+            "model": "text-davinci-003",
+            "choices": [{ "text": text, "index": 0, "logprobs": null, "finish_reason": null }],
+            "usage": null
+        });
+        let test = TestClient::new()
+            .on(Matcher::post("completions"), CannedResponse::stream(&[chunk("Cook"), chunk("ies")]));
+
+        let stream = completions::build(CompletionModels::TEXT_DAVINCI_003)
+            .client(&test.client())
+            .prompt("Ice cream or cookies?")
+            .stream()
+            .await
+            .unwrap();
+        let texts: Vec<String> = stream.map(|chunk| chunk.unwrap().choices[0].text.clone()).collect().await;
+
+        assert_eq!(texts, ["Cook", "ies"]);
+        assert_eq!(test.last_request().unwrap().body.unwrap()["stream"], json!(true));
+    }
+
+    fn faulty_client(faults: &FaultInjector<TestClient>) -> Client {
+        Client::new(String::from("sk-test"))
+            .base_url(String::from(TEST_BASE_URL))
+            .retry_policy(RetryPolicy::none())
+            .transport(faults.clone())
+    }
+
+    /// Which of `count` requests succeed through `faults`
+    async fn outcomes(faults: &FaultInjector<TestClient>, count: usize) -> Vec<bool> {
+        let client = faulty_client(faults);
+        let mut outcomes = Vec::new();
+        for _ in 0..count {
+            outcomes.push(embed(&client, None).await.is_ok());
+        }
+        outcomes
+    }
+
+    fn answering() -> TestClient {
+        TestClient::new().on(Matcher::post("embeddings"), embeddings_answer("ok"))
+    }
+
+    #[tokio::test]
+    async fn same_seed_fails_same_requests() {
+        let faults = |seed| FaultInjector::new(answering()).rate(Fault::ServerError, 0.5).seed(seed);
+
+        let first = outcomes(&faults(7), 40).await;
+        assert_eq!(outcomes(&faults(7), 40).await, first);
+        assert_ne!(outcomes(&faults(8), 40).await, first);
+        assert!(first.contains(&true) && first.contains(&false));
+    }
+
+    #[tokio::test]
+    async fn counts_injected_faults() {
+        let inner = answering();
+        let faults = FaultInjector::new(inner.clone())
+            .rate(Fault::RateLimited, 0.3)
+            .rate(Fault::Timeout, 0.2)
+            .seed(3);
+
+        let succeeded = outcomes(&faults, 50).await.into_iter().filter(|ok| *ok).count() as u64;
+
+        let (limited, timed_out) = (faults.injected(Fault::RateLimited), faults.injected(Fault::Timeout));
+        assert!(limited > 0 && timed_out > 0);
+        assert_eq!(limited + timed_out + succeeded, 50);
+        assert_eq!(faults.injected(Fault::ServerError), 0);
+        assert_eq!(inner.requests().len() as u64, succeeded);
+    }
+
+    #[tokio::test]
+    async fn injects_each_fault_as_its_error() {
+        for fault in [Fault::RateLimited, Fault::ServerError, Fault::Timeout, Fault::Connect, Fault::MalformedJson] {
+            let faults = FaultInjector::new(answering()).rate(fault, 1.0);
+            let error = embed(&faulty_client(&faults), None).await.unwrap_err();
+            let expected = match fault {
+                Fault::RateLimited => matches!(&error, Error::Api(e) if e.status == StatusCode::TOO_MANY_REQUESTS),
+                Fault::ServerError => matches!(&error, Error::Api(e) if e.status == StatusCode::INTERNAL_SERVER_ERROR),
+                Fault::Timeout => matches!(error, Error::Timeout),
+                Fault::Connect => matches!(error, Error::Connect),
+                Fault::MalformedJson => matches!(error, Error::Deserialize { .. }),
+            };
+            assert!(expected, "{:?} injected as {:?}", fault, error);
+            assert_eq!(faults.injected(fault), 1);
+        }
+    }
+}
@@ -0,0 +1,991 @@
+use crate::chat::Role;
+use crate::client::Client;
+use crate::error::Error;
+use crate::requester::{self, Query, RequestOptions};
+use crate::schema::{ResponseFormat, Schema};
+use crate::tools::RankingOptions;
+use crate::usage::Usage;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::ops::Range;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Thread {
+    pub id: String,
+    pub object: String,
+    pub created_at: u64,
+}
+
+/// A tool a message attachment is made available to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AttachmentTool {
+    FileSearch,
+    CodeInterpreter,
+}
+
+/// A file attached to a message, along with the tools it should be added to
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::threads::{Attachment, AttachmentTool};
+///
+/// let attachment = Attachment::new("file-abc123")
+///     .tool(AttachmentTool::FileSearch)
+///     .tool(AttachmentTool::CodeInterpreter);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    pub file_id: String,
+    pub tools: Vec<AttachmentTool>,
+}
+
+impl Attachment {
+    pub fn new<S: Into<String>>(file_id: S) -> Self {
+        Attachment {
+            file_id: file_id.into(),
+            tools: Vec::new(),
+        }
+    }
+
+    /// Add the file to a tool
+    pub fn tool(mut self, tool: AttachmentTool) -> Self {
+        if !self.tools.contains(&tool) {
+            self.tools.push(tool);
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileCitation {
+    pub file_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilePath {
+    pub file_id: String,
+}
+
+/// A citation in the text of a message, replacing the placeholder `text` at
+/// `start_index..end_index`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Annotation {
+    /// A quote from a file found by the `file_search` tool
+    FileCitation {
+        text: String,
+        start_index: usize,
+        end_index: usize,
+        file_citation: FileCitation,
+    },
+    /// A file generated by the `code_interpreter` tool
+    FilePath {
+        text: String,
+        start_index: usize,
+        end_index: usize,
+        file_path: FilePath,
+    },
+}
+
+impl Annotation {
+    /// The placeholder in the message text, e.g. `【4:0†source】`
+    pub fn text(&self) -> &str {
+        match self {
+            Annotation::FileCitation { text, .. } | Annotation::FilePath { text, .. } => text,
+        }
+    }
+
+    /// Where the placeholder is in the message text, in characters
+    pub fn range(&self) -> Range<usize> {
+        match self {
+            Annotation::FileCitation { start_index, end_index, .. } | Annotation::FilePath { start_index, end_index, .. } => *start_index..*end_index,
+        }
+    }
+
+    /// The file that is cited, download it with [`files::content`](crate::files::content)
+    pub fn file_id(&self) -> &str {
+        match self {
+            Annotation::FileCitation { file_citation, .. } => &file_citation.file_id,
+            Annotation::FilePath { file_path, .. } => &file_path.file_id,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Text {
+    pub value: String,
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+}
+
+impl Text {
+    /// The text with every annotation placeholder replaced by `cite(index, annotation)`,
+    /// e.g. to render footnotes
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::threads::Text;
+    ///
+    /// let text: Text = serde_json::from_value(serde_json::json!({
+    ///     "value": "Cookies are sweet【4:0†source】.",
+    ///     "annotations": [{
+    ///         "type": "file_citation",
+    ///         "text": "【4:0†source】",
+    ///         "start_index": 17,
+    ///         "end_index": 29,
+    ///         "file_citation": { "file_id": "file-abc123" }
+    ///     }]
+    /// })).unwrap();
+    ///
+    /// assert_eq!(text.cited(|i, _| format!("[{}]", i + 1)), "Cookies are sweet[1].");
+    /// ```
+    pub fn cited<F>(&self, mut cite: F) -> String
+    where
+        F: FnMut(usize, &Annotation) -> String
+    {
+        let mut annotations: Vec<(usize, &Annotation)> = self.annotations.iter().enumerate().collect();
+        annotations.sort_by_key(|(_, a)| a.range().start);
+
+        let mut cited = String::with_capacity(self.value.len());
+        let mut last = 0;
+        for (index, annotation) in annotations {
+            let placeholder = annotation.text();
+            let Some(offset) = self.value[last..].find(placeholder) else {
+                continue;
+            };
+            cited.push_str(&self.value[last..last + offset]);
+            cited.push_str(&cite(index, annotation));
+            last += offset + placeholder.len();
+        }
+        cited.push_str(&self.value[last..]);
+        cited
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageFile {
+    pub file_id: String,
+}
+
+/// A block of content in a thread message
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MessageContent {
+    Text { text: Text },
+    ImageFile { image_file: ImageFile },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ThreadMessage {
+    pub id: String,
+    pub object: String,
+    pub created_at: u64,
+    pub thread_id: String,
+    pub role: Role,
+    pub content: Vec<MessageContent>,
+    pub assistant_id: Option<String>,
+    pub run_id: Option<String>,
+    #[serde(default)]
+    pub attachments: Option<Vec<Attachment>>,
+}
+
+/// A part of an annotation received while streaming, fields are only set once they're known
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AnnotationDelta {
+    /// The position of the annotation in the text's annotations
+    pub index: usize,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub text: Option<String>,
+    pub start_index: Option<usize>,
+    pub end_index: Option<usize>,
+    pub file_citation: Option<FileCitation>,
+    pub file_path: Option<FilePath>,
+}
+
+impl AnnotationDelta {
+    /// Fill in the fields not known yet from a later part of the same annotation
+    fn merge(&mut self, part: AnnotationDelta) {
+        self.kind = self.kind.take().or(part.kind);
+        self.text = self.text.take().or(part.text);
+        self.start_index = self.start_index.or(part.start_index);
+        self.end_index = self.end_index.or(part.end_index);
+        self.file_citation = self.file_citation.take().or(part.file_citation);
+        self.file_path = self.file_path.take().or(part.file_path);
+    }
+
+    /// The complete annotation, once every field it needs was received
+    fn annotation(&self) -> Option<Annotation> {
+        let text = self.text.clone()?;
+        let start_index = self.start_index?;
+        let end_index = self.end_index?;
+        match self.kind.as_deref()? {
+            "file_citation" => Some(Annotation::FileCitation { text, start_index, end_index, file_citation: self.file_citation.clone()? }),
+            "file_path" => Some(Annotation::FilePath { text, start_index, end_index, file_path: self.file_path.clone()? }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TextDelta {
+    pub value: Option<String>,
+    #[serde(default)]
+    pub annotations: Vec<AnnotationDelta>,
+}
+
+/// A part of a message content block received while streaming
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MessageContentDelta {
+    /// The position of the block in the message's content
+    pub index: usize,
+    /// `text` or `image_file`
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub text: Option<TextDelta>,
+    pub image_file: Option<ImageFile>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MessageDeltaContent {
+    pub role: Option<Role>,
+    #[serde(default)]
+    pub content: Vec<MessageContentDelta>,
+}
+
+/// The data of a `thread.message.delta` event, received while streaming a run
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MessageDelta {
+    pub id: String,
+    #[serde(default)]
+    pub object: String,
+    pub delta: MessageDeltaContent,
+}
+
+/// Assembles streamed `thread.message.delta` events into complete messages
+///
+/// Pass the message from each `thread.message.created` event to
+/// [`created`](MessageAccumulator::created) and every delta to
+/// [`delta`](MessageAccumulator::delta). Deltas for messages that weren't created first
+/// start a new message with only the identifier and role set. Annotations are added to the
+/// text once every part of them was received, in the order of their positions.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::threads::{MessageAccumulator, MessageContent, MessageDelta};
+/// use serde_json::json;
+///
+/// let deltas = [
+///     json!({ "id": "msg_abc123", "delta": { "content": [{ "index": 0, "type": "text", "text": { "value": "Cookies are " } }] } }),
+///     json!({ "id": "msg_abc123", "delta": { "content": [{ "index": 0, "type": "text", "text": { "value": "sweet【4:0†source】" } }] } }),
+///     json!({ "id": "msg_abc123", "delta": { "content": [{ "index": 0, "type": "text", "text": { "annotations": [{
+///         "index": 0, "type": "file_citation", "text": "【4:0†source】", "start_index": 17, "end_index": 29,
+///         "file_citation": { "file_id": "file-abc123" }
+///     }] } }] } }),
+/// ];
+///
+/// let mut accumulator = MessageAccumulator::default();
+/// for delta in deltas {
+///     accumulator.delta(serde_json::from_value::<MessageDelta>(delta).unwrap());
+/// }
+///
+/// let message = accumulator.message("msg_abc123").unwrap();
+/// let MessageContent::Text { text } = &message.content[0] else { panic!() };
+/// assert_eq!(text.value, "Cookies are sweet【4:0†source】");
+/// assert_eq!(text.annotations[0].file_id(), "file-abc123");
+/// ```
+#[derive(Debug, Default)]
+pub struct MessageAccumulator {
+    messages: Vec<ThreadMessage>,
+    /// Annotations still missing fields, by message, content block and annotation position
+    partial: Vec<(String, usize, AnnotationDelta)>,
+    /// The position of each annotation added to a text, by message and content block, so
+    /// annotations completed out of order are kept in order
+    placed: Vec<(String, usize, Vec<usize>)>,
+}
+
+impl MessageAccumulator {
+    /// Start a message from a `thread.message.created` event
+    pub fn created(&mut self, message: ThreadMessage) {
+        self.placed.retain(|(id, _, _)| *id != message.id);
+        match self.messages.iter_mut().find(|m| m.id == message.id) {
+            Some(existing) => *existing = message,
+            None => self.messages.push(message),
+        }
+    }
+
+    /// Merge a `thread.message.delta` event into its message
+    pub fn delta(&mut self, delta: MessageDelta) {
+        let position = match self.messages.iter().position(|m| m.id == delta.id) {
+            Some(position) => position,
+            None => {
+                self.messages.push(ThreadMessage {
+                    id: delta.id.clone(),
+                    object: String::from("thread.message"),
+                    created_at: 0,
+                    thread_id: String::new(),
+                    role: delta.delta.role.unwrap_or(Role::Assistant),
+                    content: Vec::new(),
+                    assistant_id: None,
+                    run_id: None,
+                    attachments: None,
+                });
+                self.messages.len() - 1
+            }
+        };
+        if let Some(role) = delta.delta.role {
+            self.messages[position].role = role;
+        }
+
+        for part in delta.delta.content {
+            let content = &mut self.messages[position].content;
+            let index = part.index.min(content.len());
+            if index == content.len() {
+                match (&part.image_file, part.kind.as_str()) {
+                    (Some(image_file), _) => content.push(MessageContent::ImageFile { image_file: image_file.clone() }),
+                    (None, "text") => content.push(MessageContent::Text { text: Text { value: String::new(), annotations: Vec::new() } }),
+                    _ => continue,
+                }
+            }
+            let (MessageContent::Text { text }, Some(text_delta)) = (&mut content[index], part.text) else {
+                continue;
+            };
+            if let Some(value) = text_delta.value {
+                text.value.push_str(&value);
+            }
+            for annotation in text_delta.annotations {
+                let key = annotation.index;
+                let pending = self.partial.iter().position(|(id, i, a)| *id == delta.id && *i == index && a.index == key);
+                let merged = match pending {
+                    Some(p) => {
+                        self.partial[p].2.merge(annotation);
+                        p
+                    }
+                    None => {
+                        self.partial.push((delta.id.clone(), index, annotation));
+                        self.partial.len() - 1
+                    }
+                };
+                if let Some(complete) = self.partial[merged].2.annotation() {
+                    self.partial.remove(merged);
+                    let placed = match self.placed.iter().position(|(id, i, _)| *id == delta.id && *i == index) {
+                        Some(p) => &mut self.placed[p].2,
+                        None => {
+                            // Annotations the message was created with hold the first positions
+                            self.placed.push((delta.id.clone(), index, (0..text.annotations.len()).collect()));
+                            &mut self.placed.last_mut().unwrap().2
+                        }
+                    };
+                    let at = placed.partition_point(|i| *i < key);
+                    if placed.get(at) == Some(&key) {
+                        text.annotations[at] = complete;
+                    } else {
+                        placed.insert(at, key);
+                        text.annotations.insert(at, complete);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The message with the given identifier, as assembled so far
+    pub fn message(&self, id: &str) -> Option<&ThreadMessage> {
+        self.messages.iter().find(|m| m.id == id)
+    }
+
+    /// Every message, in the order they were started
+    pub fn messages(&self) -> &[ThreadMessage] {
+        &self.messages
+    }
+
+    pub fn into_messages(self) -> Vec<ThreadMessage> {
+        self.messages
+    }
+}
+
+/// How a thread is truncated to fit the context window before a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TruncationStrategy {
+    /// Messages in the middle of the thread are dropped to fit the model's context length
+    Auto,
+    /// Only the most recent `last_messages` messages are used
+    LastMessages { last_messages: u32 },
+}
+
+impl TruncationStrategy {
+    /// Only use the `count` most recent messages of the thread
+    pub fn last_messages(count: u32) -> Self {
+        TruncationStrategy::LastMessages { last_messages: count }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Run {
+    pub id: String,
+    pub object: String,
+    pub created_at: u64,
+    pub thread_id: String,
+    pub assistant_id: String,
+    /// e.g. `queued`, `in_progress`, `requires_action` or `completed`
+    pub status: String,
+    pub model: String,
+    #[serde(default)]
+    pub instructions: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    #[serde(default)]
+    pub max_prompt_tokens: Option<u32>,
+    #[serde(default)]
+    pub max_completion_tokens: Option<u32>,
+    #[serde(default)]
+    pub truncation_strategy: Option<TruncationStrategy>,
+    #[serde(default)]
+    pub response_format: Option<Value>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+/// The content of a chunk found by a file search
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileSearchResultContent {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub text: String,
+}
+
+/// A chunk found by a file search, with its relevance score
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileSearchResult {
+    pub file_id: String,
+    pub file_name: String,
+    pub score: f64,
+    /// Only included when listing steps with [`file_search_contents`](StepParameters::file_search_contents)
+    #[serde(default)]
+    pub content: Vec<FileSearchResultContent>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FileSearchCall {
+    #[serde(default)]
+    pub ranking_options: Option<RankingOptions>,
+    #[serde(default)]
+    pub results: Vec<FileSearchResult>,
+}
+
+/// A function called during a run, with the output submitted for it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StepFunctionCall {
+    pub name: String,
+    pub arguments: String,
+    #[serde(default)]
+    pub output: Option<String>,
+}
+
+/// Something the code interpreter produced while running code
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CodeInterpreterOutput {
+    /// Text printed by the code
+    Logs { logs: String },
+    /// An image created by the code, e.g. a plot, download it with [`files::content`](crate::files::content)
+    Image { image: ImageFile },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CodeInterpreterCall {
+    /// The code that was run
+    pub input: String,
+    #[serde(default)]
+    pub outputs: Vec<CodeInterpreterOutput>,
+}
+
+/// A tool called in a run step
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepToolCall {
+    FileSearch {
+        id: String,
+        #[serde(default)]
+        file_search: FileSearchCall,
+    },
+    CodeInterpreter {
+        id: String,
+        code_interpreter: CodeInterpreterCall,
+    },
+    Function {
+        id: String,
+        function: StepFunctionCall,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MessageCreation {
+    pub message_id: String,
+}
+
+/// What happened in a run step
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepDetails {
+    MessageCreation { message_creation: MessageCreation },
+    ToolCalls { tool_calls: Vec<StepToolCall> },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunStep {
+    pub id: String,
+    pub object: String,
+    pub created_at: u64,
+    pub run_id: String,
+    pub thread_id: String,
+    pub assistant_id: String,
+    /// e.g. `in_progress`, `completed` or `failed`
+    pub status: String,
+    pub step_details: StepDetails,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+    #[serde(default)]
+    pub last_error: Option<Value>,
+}
+
+impl RunStep {
+    /// The chunks found by every file search in the step, in the order they were ranked
+    pub fn file_search_results(&self) -> Vec<&FileSearchResult> {
+        match &self.step_details {
+            StepDetails::ToolCalls { tool_calls } => tool_calls.iter()
+                .flat_map(|call| match call {
+                    StepToolCall::FileSearch { file_search, .. } => file_search.results.iter(),
+                    _ => [].iter(),
+                })
+                .collect(),
+            StepDetails::MessageCreation { .. } => Vec::new(),
+        }
+    }
+
+    /// The outputs of every code interpreter call in the step
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::{files, threads::{self, CodeInterpreterOutput}};
+    ///
+    /// async {
+    ///     let steps = threads::build()
+    ///         .steps(String::from("thread_abc123"), String::from("run_abc123"))
+    ///         .list()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     for output in steps.iter().flat_map(|step| step.code_interpreter_outputs()) {
+    ///         match output {
+    ///             CodeInterpreterOutput::Logs { logs } => println!("{}", logs),
+    ///             CodeInterpreterOutput::Image { image } => {
+    ///                 let path = format!("{}.png", image.file_id);
+    ///                 files::download(&image.file_id, path).await.expect("Error Downloading Image");
+    ///             }
+    ///         }
+    ///     }
+    /// };
+    /// ```
+    pub fn code_interpreter_outputs(&self) -> Vec<&CodeInterpreterOutput> {
+        match &self.step_details {
+            StepDetails::ToolCalls { tool_calls } => tool_calls.iter()
+                .flat_map(|call| match call {
+                    StepToolCall::CodeInterpreter { code_interpreter, .. } => code_interpreter.outputs.iter(),
+                    _ => [].iter(),
+                })
+                .collect(),
+            StepDetails::MessageCreation { .. } => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RunStepList {
+    data: Vec<RunStep>,
+}
+
+/// Available parameters that can be sent with a thread request
+pub struct Parameters {
+    options: RequestOptions,
+}
+
+pub struct MessageParameters<'a> {
+    options: RequestOptions,
+    thread_id: String,
+    query: Query<'a>,
+}
+
+pub struct StepParameters {
+    options: RequestOptions,
+    thread_id: String,
+    run_id: String,
+    include: Vec<&'static str>,
+}
+
+pub struct RunParameters<'a> {
+    options: RequestOptions,
+    thread_id: String,
+    assistant_id: String,
+    query: Query<'a>,
+}
+
+/// Function to create a threads request
+///
+/// Call it using [`build`] and either `create()` a new thread, add a `message` to an
+/// existing thread, `run` an assistant on it or list the `steps` of a run, see Examples below.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::threads::{self, Attachment, AttachmentTool};
+///
+/// async {
+///     let thread = threads::build()
+///         .create()
+///         .await
+///         .expect("Error Getting Response");
+///
+///     let attachments = vec![Attachment::new("file-abc123").tool(AttachmentTool::FileSearch)];
+///     let message = threads::build()
+///         .message(thread.id)
+///         .content("What does the attached report conclude?")
+///         .attachments(&attachments)
+///         .create()
+///         .await;
+///
+///     println!("{:?}", message);
+/// };
+/// ```
+pub fn build() -> Parameters {
+    Parameters {
+        options: RequestOptions::default(),
+    }
+}
+
+impl Parameters {
+    /// Send the request using the given client instead of one loaded from the environment
+    pub fn client(mut self, client: &Client) -> Self {
+        self.options.client = Some(client.clone());
+        self
+    }
+
+    /// Creates an empty thread.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/threads/createThread)
+    pub async fn create(self) -> Result<Thread, Error> {
+        let response: Result<Thread, Error> = requester::threads(&self.options, &Query::new()).await;
+
+        match response {
+            Ok(t) => Ok(t),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Adds a message to the thread with the given identifier.
+    pub fn message<'a>(self, thread_id: String) -> MessageParameters<'a> {
+        MessageParameters {
+            options: self.options,
+            thread_id,
+            query: Query::new(),
+        }
+    }
+
+    /// Runs the assistant with the given identifier on the thread with the given identifier.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::threads::{self, TruncationStrategy};
+    ///
+    /// async {
+    ///     let truncation = TruncationStrategy::last_messages(10);
+    ///     let run = threads::build()
+    ///         .run(String::from("thread_abc123"), String::from("asst_abc123"))
+    ///         .truncation_strategy(&truncation)
+    ///         .create()
+    ///         .await;
+    ///
+    ///     println!("{:?}", run);
+    /// };
+    /// ```
+    pub fn run<'a>(self, thread_id: String, assistant_id: String) -> RunParameters<'a> {
+        RunParameters {
+            options: self.options,
+            thread_id,
+            assistant_id,
+            query: Query::new(),
+        }
+    }
+
+    /// Lists the steps of the run with the given identifier, most recent first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::threads;
+    ///
+    /// async {
+    ///     let steps = threads::build()
+    ///         .steps(String::from("thread_abc123"), String::from("run_abc123"))
+    ///         .file_search_contents()
+    ///         .list()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     for result in steps.iter().flat_map(|step| step.file_search_results()) {
+    ///         println!("{} {:.2} {:?}", result.file_name, result.score, result.content);
+    ///     }
+    /// };
+    /// ```
+    pub fn steps(self, thread_id: String, run_id: String) -> StepParameters {
+        StepParameters {
+            options: self.options,
+            thread_id,
+            run_id,
+            include: Vec::new(),
+        }
+    }
+}
+
+impl<'a> MessageParameters<'a> {
+    /// The text content of the message.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/messages/createMessage#messages-createmessage-content)
+    pub fn content(mut self, input: &'a str) -> Self {
+        self.query.push(("content", json!(input)));
+        self
+    }
+
+    /// The role of the entity creating the message, defaults to `user`.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/messages/createMessage#messages-createmessage-role)
+    pub fn role(mut self, input: Role) -> Self {
+        self.query.push(("role", json!(input)));
+        self
+    }
+
+    /// Files attached to the message and the tools they should be added to.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/messages/createMessage#messages-createmessage-attachments)
+    pub fn attachments(mut self, input: &'a [Attachment]) -> Self {
+        self.query.push(("attachments", json!(input)));
+        self
+    }
+
+    /// Mark the request as safe to retry even when the client's retry policy
+    /// is restricted to idempotent requests, see [`RetryScope::IdempotentOnly`](crate::retry::RetryScope::IdempotentOnly)
+    pub fn idempotent(mut self) -> Self {
+        self.options.idempotent = true;
+        self
+    }
+
+    /// Set a parameter this crate doesn't model yet, sent as is in the request body
+    ///
+    /// Replaces the value set for `key` by earlier setters, and is replaced by later ones.
+    pub fn extra_param(mut self, key: &'a str, value: Value) -> Self {
+        self.query.push((key, value));
+        self
+    }
+
+    /// Complete the request and send
+    pub async fn create(mut self) -> Result<ThreadMessage, Error> {
+        if self.query.get("role").is_none() {
+            self.query.push(("role", json!(Role::User)));
+        }
+
+        let response: Result<ThreadMessage, Error> = requester::thread_messages(&self.options, &self.thread_id, &self.query).await;
+
+        match response {
+            Ok(t) => Ok(t),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl StepParameters {
+    /// Include the content of the chunks found by file searches.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/run-steps/listRunSteps#run-steps-listrunsteps-include)
+    pub fn file_search_contents(mut self) -> Self {
+        self.include.push("step_details.tool_calls[*].file_search.results[*].content");
+        self
+    }
+
+    /// Complete the request and send
+    pub async fn list(self) -> Result<Vec<RunStep>, Error> {
+        let response: Result<RunStepList, Error> = requester::thread_run_steps(&self.options, &self.thread_id, &self.run_id, &self.include).await;
+
+        match response {
+            Ok(t) => Ok(t.data),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<'a> RunParameters<'a> {
+    /// What sampling temperature to use, between 0 and 2, overriding the assistant's.
+    ///
+    /// Higher values like 0.8 will make the output more random, while lower values
+    /// like 0.2 will make it more focused and deterministic.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/runs/createRun#runs-createrun-temperature)
+    pub fn temperature(mut self, input: &'a f32) -> Self {
+        self.query.push(("temperature", json!(input)));
+        self
+    }
+
+    /// An alternative to sampling with temperature, called nucleus sampling, overriding
+    /// the assistant's.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/runs/createRun#runs-createrun-top_p)
+    pub fn top_p(mut self, input: &'a f32) -> Self {
+        self.query.push(("top_p", json!(input)));
+        self
+    }
+
+    /// The maximum number of prompt tokens that may be used over the course of the run,
+    /// the run ends with status `incomplete` if it's exceeded.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/runs/createRun#runs-createrun-max_prompt_tokens)
+    pub fn max_prompt_tokens(mut self, input: &'a u32) -> Self {
+        self.query.push(("max_prompt_tokens", json!(input)));
+        self
+    }
+
+    /// The maximum number of completion tokens that may be used over the course of the run,
+    /// the run ends with status `incomplete` if it's exceeded.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/runs/createRun#runs-createrun-max_completion_tokens)
+    pub fn max_completion_tokens(mut self, input: &'a u32) -> Self {
+        self.query.push(("max_completion_tokens", json!(input)));
+        self
+    }
+
+    /// The format the assistant answers in, overriding the assistant's format, see
+    /// [`ResponseFormat`]
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/runs/createRun#runs-createrun-response_format)
+    pub fn response_format(mut self, input: ResponseFormat) -> Self {
+        self.query.push(("response_format", json!(input)));
+        self
+    }
+
+    /// Have the assistant answer with a JSON object, overriding the assistant's format.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/runs/createRun#runs-createrun-response_format)
+    pub fn json_object(self) -> Self {
+        self.response_format(ResponseFormat::JsonObject)
+    }
+
+    /// Have the assistant answer with JSON matching `schema`, overriding the assistant's format.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/runs/createRun#runs-createrun-response_format)
+    pub fn json_schema(self, name: &'a str, schema: &'a Schema) -> Self {
+        self.response_format(ResponseFormat::json_schema(name, schema.clone()))
+    }
+
+    /// How the thread is truncated before the run, defaults to `auto`.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/runs/createRun#runs-createrun-truncation_strategy)
+    pub fn truncation_strategy(mut self, input: &'a TruncationStrategy) -> Self {
+        self.query.push(("truncation_strategy", json!(input)));
+        self
+    }
+
+    /// Mark the request as safe to retry even when the client's retry policy
+    /// is restricted to idempotent requests, see [`RetryScope::IdempotentOnly`](crate::retry::RetryScope::IdempotentOnly)
+    pub fn idempotent(mut self) -> Self {
+        self.options.idempotent = true;
+        self
+    }
+
+    /// Set a parameter this crate doesn't model yet, sent as is in the request body
+    ///
+    /// Replaces the value set for `key` by earlier setters, and is replaced by later ones.
+    pub fn extra_param(mut self, key: &'a str, value: Value) -> Self {
+        self.query.push((key, value));
+        self
+    }
+
+    /// Complete the request and send
+    pub async fn create(mut self) -> Result<Run, Error> {
+        self.query.push(("assistant_id", json!(self.assistant_id)));
+
+        let response: Result<Run, Error> = requester::thread_runs(&self.options, &self.thread_id, &self.query).await;
+
+        match response {
+            Ok(t) => Ok(t),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MessageAccumulator, MessageContent, MessageDelta, Text};
+    use serde_json::{json, Value};
+
+    fn annotations(annotations: Value) -> MessageDelta {
+        serde_json::from_value(json!({
+            "id": "msg_abc123",
+            "delta": { "content": [{ "index": 0, "type": "text", "text": { "annotations": annotations } }] }
+        })).unwrap()
+    }
+
+    fn text(accumulator: &MessageAccumulator) -> &Text {
+        match &accumulator.message("msg_abc123").unwrap().content[0] {
+            MessageContent::Text { text } => text,
+            other => panic!("expected text, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn annotations_completed_out_of_order_keep_their_positions() {
+        let mut accumulator = MessageAccumulator::default();
+        accumulator.delta(serde_json::from_value(json!({
+            "id": "msg_abc123",
+            "delta": { "content": [{ "index": 0, "type": "text", "text": { "value": "Cookies【4:0†a】 and cream【4:1†b】." } }] }
+        })).unwrap());
+
+        // The first annotation is split across deltas, the second arrives whole in between
+        accumulator.delta(annotations(json!([
+            { "index": 0, "type": "file_citation", "text": "【4:0†a】" }
+        ])));
+        accumulator.delta(annotations(json!([{
+            "index": 1, "type": "file_citation", "text": "【4:1†b】", "start_index": 25, "end_index": 31,
+            "file_citation": { "file_id": "file-b" }
+        }])));
+        assert_eq!(text(&accumulator).annotations.len(), 1);
+        accumulator.delta(annotations(json!([{
+            "index": 0, "start_index": 7, "end_index": 13, "file_citation": { "file_id": "file-a" }
+        }])));
+
+        let files: Vec<&str> = text(&accumulator).annotations.iter().map(|a| a.file_id()).collect();
+        assert_eq!(files, ["file-a", "file-b"]);
+    }
+
+    #[test]
+    fn repeated_annotation_replaces_its_position() {
+        let mut accumulator = MessageAccumulator::default();
+        let citation = |index: usize, file_id: &str| json!({
+            "index": index, "type": "file_path", "text": "sandbox:/cookies.csv", "start_index": 0, "end_index": 20,
+            "file_path": { "file_id": file_id }
+        });
+        accumulator.delta(annotations(json!([citation(2, "file-c")])));
+        accumulator.delta(annotations(json!([citation(0, "file-a")])));
+        accumulator.delta(annotations(json!([citation(2, "file-d")])));
+
+        let files: Vec<&str> = text(&accumulator).annotations.iter().map(|a| a.file_id()).collect();
+        assert_eq!(files, ["file-a", "file-d"]);
+    }
+}
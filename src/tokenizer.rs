@@ -0,0 +1,61 @@
+use crate::error::Error;
+use std::collections::HashMap;
+use tiktoken_rs::CoreBPE;
+
+/// Splits text into tokens the same way a model does, available with the `tokenizer` feature
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::tokenizer::Tokenizer;
+///
+/// let tokenizer = Tokenizer::for_model("text-davinci-003").expect("Unknown model");
+/// assert_eq!(tokenizer.count("Ice cream or cookies?"), 5);
+/// ```
+#[derive(Clone, Copy)]
+pub struct Tokenizer {
+    bpe: &'static CoreBPE,
+}
+
+impl Tokenizer {
+    /// The tokenizer used by the given model, e.g. `text-davinci-003` or `gpt-4o`
+    pub fn for_model(model: &str) -> Result<Tokenizer, Error> {
+        let bpe = tiktoken_rs::bpe_for_model(model)
+            .map_err(|_| Error::UnknownTokenizer(model.to_string()))?;
+        Ok(Tokenizer { bpe })
+    }
+
+    /// Encode text as token IDs, special tokens are treated as plain text
+    pub fn encode(&self, text: &str) -> Vec<u32> {
+        self.bpe.encode_ordinary(text)
+    }
+
+    /// Decode token IDs back into text, invalid UTF-8 is replaced with `U+FFFD`
+    pub fn decode(&self, tokens: &[u32]) -> String {
+        String::from_utf8_lossy(&self.decode_bytes(tokens)).into_owned()
+    }
+
+    /// The number of tokens in the text
+    pub fn count(&self, text: &str) -> usize {
+        self.encode(text).len()
+    }
+
+    /// Expand a bias for each word into a bias for each of its tokens, for `logit_bias`
+    ///
+    /// Words are encoded both as given and with a leading space, as a word mid-sentence is
+    /// usually a different token than the same word at the start of the text.
+    pub fn logit_bias(&self, words: &[(&str, i32)]) -> HashMap<u32, i32> {
+        let mut bias = HashMap::new();
+        for (word, value) in words {
+            let spaced = format!(" {}", word.trim_start());
+            for token in self.encode(word).into_iter().chain(self.encode(&spaced)) {
+                bias.insert(token, *value);
+            }
+        }
+        bias
+    }
+
+    pub(crate) fn decode_bytes(&self, tokens: &[u32]) -> Vec<u8> {
+        self.bpe.decode_bytes(tokens).unwrap_or_default()
+    }
+}
@@ -0,0 +1,369 @@
+use crate::error::Error;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Derive [`Tool`] for a struct, using its name, doc comment and fields as the function
+/// definition, see [`Tool`] for an example
+#[cfg(feature = "derive")]
+pub use oai_rs_derive::OaiTool;
+
+/// Used by code generated by `#[derive(OaiTool)]`, not part of the public API
+#[doc(hidden)]
+pub mod __private {
+    pub use serde_json::{json, Map, Value};
+}
+
+/// The definition of a function the model may call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionDefinition {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub parameters: Value,
+}
+
+/// How file search results are ranked, and the lowest score a result may have
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RankingOptions {
+    /// `auto` or `default_2024_08_21`
+    pub ranker: String,
+    /// Between 0 and 1, results scored lower are left out
+    pub score_threshold: f32,
+}
+
+/// Configuration of the `file_search` tool
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::tools::{FileSearch, ToolDefinition};
+///
+/// let tool = ToolDefinition::file_search(FileSearch::default().max_num_results(5).score_threshold(0.5));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileSearch {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_num_results: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ranking_options: Option<RankingOptions>,
+}
+
+impl FileSearch {
+    /// The most results to return, between 1 and 50
+    pub fn max_num_results(mut self, max_num_results: u8) -> Self {
+        self.max_num_results = Some(max_num_results);
+        self
+    }
+
+    /// The ranker to use, `auto` unless set
+    pub fn ranker(mut self, ranker: &str) -> Self {
+        self.ranking_options_mut().ranker = String::from(ranker);
+        self
+    }
+
+    /// Leave out results scored lower than `score_threshold`, between 0 and 1
+    pub fn score_threshold(mut self, score_threshold: f32) -> Self {
+        self.ranking_options_mut().score_threshold = score_threshold;
+        self
+    }
+
+    fn ranking_options_mut(&mut self) -> &mut RankingOptions {
+        self.ranking_options.get_or_insert_with(|| RankingOptions {
+            ranker: String::from("auto"),
+            score_threshold: 0.0,
+        })
+    }
+}
+
+/// A tool the model may call, sent with a chat request
+///
+/// `file_search` and `code_interpreter` are only available to assistants.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolDefinition {
+    Function { function: FunctionDefinition },
+    FileSearch {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file_search: Option<FileSearch>,
+    },
+    CodeInterpreter,
+}
+
+impl ToolDefinition {
+    /// A function tool taking the arguments described by the `parameters` JSON schema,
+    /// see [`Schema`](crate::schema::Schema) to build one
+    pub fn function<S: Into<String>, P: Into<Value>>(name: S, description: Option<String>, parameters: P) -> Self {
+        ToolDefinition::Function {
+            function: FunctionDefinition {
+                name: name.into(),
+                description,
+                parameters: parameters.into(),
+            },
+        }
+    }
+
+    /// The function of a function tool, `None` for other tools
+    ///
+    /// Used to send tools to deployments only accepting the legacy `functions`.
+    pub fn function_definition(&self) -> Option<&FunctionDefinition> {
+        match self {
+            ToolDefinition::Function { function } => Some(function),
+            _ => None,
+        }
+    }
+
+    /// The `file_search` tool, searching the files in the assistant's and thread's vector stores
+    pub fn file_search(config: FileSearch) -> Self {
+        ToolDefinition::FileSearch { file_search: Some(config) }
+    }
+}
+
+impl From<FunctionDefinition> for ToolDefinition {
+    fn from(function: FunctionDefinition) -> Self {
+        ToolDefinition::Function { function }
+    }
+}
+
+/// Whether and which tool the model calls, see [`tool_choice`](crate::chat::Parameters::tool_choice)
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::tools::ToolChoice;
+///
+/// let choice = ToolChoice::function("get_weather");
+/// assert_eq!(
+///     serde_json::to_value(&choice).unwrap(),
+///     serde_json::json!({ "type": "function", "function": { "name": "get_weather" } }),
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolChoice {
+    /// The model chooses whether to call tools, the default when tools are given
+    Auto,
+    /// The model answers without calling tools
+    None,
+    /// The model has to call at least one tool
+    Required,
+    /// The model has to call the function with this name
+    Function(String),
+}
+
+impl ToolChoice {
+    /// Have the model call the function `name`
+    pub fn function<S: Into<String>>(name: S) -> Self {
+        ToolChoice::Function(name.into())
+    }
+
+    /// Have the model call the tool `T`
+    pub fn tool<T: Tool>() -> Self {
+        ToolChoice::Function(String::from(T::NAME))
+    }
+}
+
+impl Serialize for ToolChoice {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ToolChoice::Auto => serializer.serialize_str("auto"),
+            ToolChoice::None => serializer.serialize_str("none"),
+            ToolChoice::Required => serializer.serialize_str("required"),
+            ToolChoice::Function(name) => json!({ "type": "function", "function": { "name": name } })
+                .serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ToolChoice {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error as _;
+
+        let value = Value::deserialize(deserializer)?;
+        match value.as_str() {
+            Some("auto") => return Ok(ToolChoice::Auto),
+            Some("none") => return Ok(ToolChoice::None),
+            Some("required") => return Ok(ToolChoice::Required),
+            _ => {}
+        }
+        value.pointer("/function/name")
+            .and_then(Value::as_str)
+            .map(ToolChoice::function)
+            .ok_or_else(|| D::Error::custom(format!("invalid tool choice {}", value)))
+    }
+}
+
+/// The name and JSON encoded arguments of a function the model called
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionCall {
+    pub name: String,
+    pub arguments: String,
+}
+
+/// A tool call made by the model in an assistant message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    /// Some gateways leave this out, it's always `function` when they do
+    #[serde(rename = "type", default = "function_kind")]
+    pub kind: String,
+    pub function: FunctionCall,
+}
+
+fn function_kind() -> String {
+    String::from("function")
+}
+
+impl From<FunctionCall> for ToolCall {
+    /// A tool call made with a legacy `function_call`, given the ID `call_{name}` as legacy
+    /// calls don't have one
+    fn from(function: FunctionCall) -> Self {
+        ToolCall {
+            id: format!("call_{}", function.name),
+            kind: function_kind(),
+            function,
+        }
+    }
+}
+
+impl ToolCall {
+    /// Parse the call's arguments as the tool `T`
+    pub fn arguments<T: Tool>(&self) -> Result<T, Error> {
+        T::from_arguments(&self.function.arguments)
+    }
+}
+
+/// A function the model can call, with arguments deserialized into `Self`
+///
+/// Usually derived with `#[derive(OaiTool)]` (`derive` feature), which takes the function
+/// name from the struct name in snake case, the description from the struct's doc comment
+/// and builds the parameters schema from the fields and their doc comments.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "derive")] {
+/// use oai_rs::tools::{OaiTool, Tool};
+/// use serde::Deserialize;
+///
+/// /// Get the current weather in a city
+/// #[derive(OaiTool, Deserialize)]
+/// struct GetWeather {
+///     /// The city to get the weather for
+///     city: String,
+///     /// Temperature unit, celsius if not given
+///     unit: Option<String>,
+/// }
+///
+/// let definition = GetWeather::definition();
+/// assert_eq!(GetWeather::parameters()["required"], serde_json::json!(["city"]));
+/// let call: GetWeather = GetWeather::from_arguments(r#"{"city": "Paris"}"#).unwrap();
+/// assert_eq!(GetWeather::NAME, "get_weather");
+/// assert_eq!(call.city, "Paris");
+/// # }
+/// ```
+pub trait Tool: DeserializeOwned {
+    /// The function name the model calls the tool by
+    const NAME: &'static str;
+
+    /// What the function does, used by the model to choose when to call it
+    fn description() -> Option<&'static str>;
+
+    /// JSON schema of the function's arguments
+    fn parameters() -> Value;
+
+    /// The definition to send in a request's `tools`
+    fn definition() -> ToolDefinition {
+        ToolDefinition::function(Self::NAME, Self::description().map(String::from), Self::parameters())
+    }
+
+    /// Deserialize the JSON encoded arguments of a call to this tool
+    fn from_arguments(arguments: &str) -> Result<Self, Error> {
+        serde_json::from_str(arguments).map_err(|e| Error::ToolArguments(e.to_string()))
+    }
+}
+
+/// A type that can be used as a field of a derived [`Tool`]
+///
+/// Implemented for strings, numbers, booleans, and `Vec`s and `Option`s of those, implement
+/// it to use your own types as arguments.
+pub trait ToolParameter {
+    /// Whether the field has to be given by the model
+    const REQUIRED: bool = true;
+
+    /// JSON schema of the type
+    fn schema() -> Value;
+}
+
+macro_rules! tool_parameter {
+    ($kind:literal, $($t:ty),*) => {
+        $(
+            impl ToolParameter for $t {
+                fn schema() -> Value {
+                    json!({ "type": $kind })
+                }
+            }
+        )*
+    };
+}
+
+tool_parameter!("string", String, &str, char);
+tool_parameter!("integer", i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+tool_parameter!("number", f32, f64);
+tool_parameter!("boolean", bool);
+
+impl<T: ToolParameter> ToolParameter for Vec<T> {
+    fn schema() -> Value {
+        json!({ "type": "array", "items": T::schema() })
+    }
+}
+
+impl<T: ToolParameter> ToolParameter for Option<T> {
+    const REQUIRED: bool = false;
+
+    fn schema() -> Value {
+        T::schema()
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+
+    /// Book a table
+    #[derive(OaiTool, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    #[allow(dead_code)]
+    struct BookTable {
+        /// Name of the restaurant
+        restaurant_name: String,
+        #[serde(rename = "party")]
+        party_size: u8,
+        #[serde(skip)]
+        confirmed: bool,
+        #[serde(default)]
+        seating_area: Option<String>,
+    }
+
+    #[test]
+    fn derive_follows_serde_renames() {
+        assert_eq!(BookTable::NAME, "book_table");
+        assert_eq!(BookTable::description(), Some("Book a table"));
+        assert_eq!(
+            BookTable::parameters(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "restaurantName": { "type": "string", "description": "Name of the restaurant" },
+                    "party": { "type": "integer" },
+                    "seatingArea": { "type": "string" },
+                },
+                "required": ["restaurantName", "party"],
+            })
+        );
+
+        let call = BookTable::from_arguments(r#"{"restaurantName": "Chez Louis", "party": 4, "seatingArea": "terrace"}"#).unwrap();
+        assert_eq!(call.restaurant_name, "Chez Louis");
+        assert_eq!(call.party_size, 4);
+        assert_eq!(call.seating_area.as_deref(), Some("terrace"));
+    }
+}
@@ -0,0 +1,101 @@
+use crate::client;
+use reqwest::{Request, Response};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+/// The future returned by [`Transport::execute`]
+pub type TransportFuture = Pin<Box<dyn Future<Output = Result<Response, TransportError>> + Send>>;
+
+/// Sends requests built by the client and returns the responses
+///
+/// Requests are sent with the client's HTTP client unless a transport is set with
+/// [`Client::transport`](crate::Client::transport), e.g. to answer requests without a network
+/// in tests. Retries, failover and the circuit breaker all act on what the transport returns.
+pub trait Transport: Send + Sync {
+    /// Send a single attempt of a request
+    fn execute(&self, request: Request) -> TransportFuture;
+}
+
+/// Sends requests over the network with an HTTP client, what clients do without a transport
+///
+/// Useful as the inner transport of wrappers, e.g. the fault injector of the `test-util` feature.
+#[derive(Debug, Clone)]
+pub struct HttpTransport {
+    http: reqwest::Client,
+}
+
+impl HttpTransport {
+    /// A transport using the HTTP client shared by all clients
+    pub fn new() -> Self {
+        HttpTransport::with_http(client::shared_http().clone())
+    }
+
+    /// A transport using the given HTTP client
+    pub fn with_http(http: reqwest::Client) -> Self {
+        HttpTransport { http }
+    }
+}
+
+impl Default for HttpTransport {
+    fn default() -> Self {
+        HttpTransport::new()
+    }
+}
+
+impl Transport for HttpTransport {
+    fn execute(&self, request: Request) -> TransportFuture {
+        let http = self.http.clone();
+        Box::pin(async move { http.execute(request).await.map_err(TransportError::from) })
+    }
+}
+
+/// Why a request couldn't be sent
+#[derive(Debug)]
+pub enum TransportError {
+    /// The request timed out, retried like a timeout of the HTTP client
+    Timeout,
+    /// No connection could be made, retried and failed over like a connection error of the
+    /// HTTP client
+    Connect,
+    /// An error of the HTTP client
+    Http(reqwest::Error),
+}
+
+impl TransportError {
+    /// Whether the request timed out
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            TransportError::Timeout => true,
+            TransportError::Connect => false,
+            TransportError::Http(e) => e.is_timeout(),
+        }
+    }
+
+    /// Whether no connection could be made
+    pub fn is_connect(&self) -> bool {
+        match self {
+            TransportError::Timeout => false,
+            TransportError::Connect => true,
+            TransportError::Http(e) => e.is_connect(),
+        }
+    }
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransportError::Timeout => write!(f, "request timed out"),
+            TransportError::Connect => write!(f, "could not connect"),
+            TransportError::Http(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for TransportError {}
+
+impl From<reqwest::Error> for TransportError {
+    fn from(error: reqwest::Error) -> Self {
+        TransportError::Http(error)
+    }
+}
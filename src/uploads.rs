@@ -0,0 +1,534 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::files;
+use crate::requester::{self, Query, RequestOptions};
+use crate::retry;
+use bytes::Bytes;
+use futures_util::TryStreamExt;
+use reqwest::multipart::{Form, Part};
+use reqwest::Body;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// The largest part the API accepts, 64 MB
+pub const MAX_PART_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Upload {
+    pub id: String,
+    pub object: String,
+    pub bytes: u64,
+    pub created_at: u64,
+    pub filename: String,
+    pub purpose: String,
+    /// `pending`, `completed`, `cancelled` or `expired`
+    pub status: String,
+    pub expires_at: u64,
+    /// The file created once the upload is completed
+    #[serde(default)]
+    pub file: Option<Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UploadPart {
+    pub id: String,
+    pub object: String,
+    pub created_at: u64,
+    pub upload_id: String,
+}
+
+/// Available parameters that can be sent with an upload request
+pub struct Parameters {
+    options: RequestOptions,
+}
+
+pub struct CreateParameters<'a> {
+    options: RequestOptions,
+    sha256: Option<String>,
+    part_size: usize,
+    part_retries: u32,
+    query: Query<'a>,
+}
+
+/// Function to create an upload, for files larger than a single request allows
+///
+/// Call it using [`build`] and create an `upload`, then add the file in parts to the
+/// returned [`UploadSession`] and `complete()` it, see Examples below.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::uploads;
+///
+/// async {
+///     let data = bytes::Bytes::from_static(b"{\"prompt\": \"cookies\", \"completion\": \"yes\"}\n");
+///     let mut session = uploads::build()
+///         .upload(String::from("training.jsonl"), String::from("fine-tune"), data.len() as u64, String::from("text/jsonl"))
+///         .create()
+///         .await
+///         .expect("Error Getting Response");
+///
+///     session.add_part(data).await.expect("Error Uploading Part");
+///     let upload = session.complete().await;
+///
+///     println!("{:?}", upload);
+/// };
+/// ```
+pub fn build() -> Parameters {
+    Parameters {
+        options: RequestOptions::default(),
+    }
+}
+
+impl Parameters {
+    /// Send the request using the given client instead of one loaded from the environment
+    pub fn client(mut self, client: &Client) -> Self {
+        self.options.client = Some(client.clone());
+        self
+    }
+
+    /// Creates an upload of a file named `filename` of `bytes` bytes.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/uploads/create)
+    pub fn upload<'a>(self, filename: String, purpose: String, bytes: u64, mime_type: String) -> CreateParameters<'a> {
+        let mut query = Query::new();
+        query.push(("filename", json!(filename)));
+        query.push(("purpose", json!(purpose)));
+        query.push(("bytes", json!(bytes)));
+        query.push(("mime_type", json!(mime_type)));
+        CreateParameters {
+            options: self.options,
+            sha256: None,
+            part_size: MAX_PART_SIZE,
+            part_retries: 3,
+            query,
+        }
+    }
+}
+
+impl<'a> CreateParameters<'a> {
+    /// The hex encoded SHA-256 checksum of the whole file, the parts are checked against it
+    /// before the upload is completed
+    pub fn sha256(mut self, input: &str) -> Self {
+        self.sha256 = Some(input.to_ascii_lowercase());
+        self
+    }
+
+    /// The size of the parts [`UploadSession::upload_file`] splits the file into, at most
+    /// and by default [`MAX_PART_SIZE`]
+    pub fn part_size(mut self, input: usize) -> Self {
+        self.part_size = input.clamp(1, MAX_PART_SIZE);
+        self
+    }
+
+    /// How many times [`UploadSession::upload_file`] retries a part that failed to upload
+    /// before giving up, 3 by default
+    pub fn part_retries(mut self, input: u32) -> Self {
+        self.part_retries = input;
+        self
+    }
+
+    /// Mark the request as safe to retry even when the client's retry policy
+    /// is restricted to idempotent requests, see [`RetryScope::IdempotentOnly`](crate::retry::RetryScope::IdempotentOnly)
+    pub fn idempotent(mut self) -> Self {
+        self.options.idempotent = true;
+        self
+    }
+
+    /// Set a parameter this crate doesn't model yet, sent as is in the request body
+    ///
+    /// Replaces the value set for `key` by earlier setters, and is replaced by later ones.
+    pub fn extra_param(mut self, key: &'a str, value: Value) -> Self {
+        self.query.push((key, value));
+        self
+    }
+
+    /// Complete the request and send
+    pub async fn create(self) -> Result<UploadSession, Error> {
+        let response: Result<Upload, Error> = requester::uploads(&self.options, &self.query).await;
+
+        match response {
+            Ok(t) => Ok(UploadSession {
+                options: self.options,
+                upload: t,
+                expected_sha256: self.sha256,
+                part_size: self.part_size,
+                part_retries: self.part_retries,
+                hasher: Sha256::new(),
+                received: 0,
+                part_ids: Vec::new(),
+            }),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// An upload in progress, parts are added in order and checked before completing it
+///
+/// Every part is checked against the upload's size as it's added, and the parts together
+/// are checked against the size and checksum given when the upload was created before it's
+/// completed. A mismatch fails with [`Error::UploadMismatch`] without completing the
+/// upload, so a corrupted file is never handed on to e.g. fine-tuning.
+pub struct UploadSession {
+    options: RequestOptions,
+    upload: Upload,
+    expected_sha256: Option<String>,
+    part_size: usize,
+    part_retries: u32,
+    hasher: Sha256,
+    received: u64,
+    part_ids: Vec<String>,
+}
+
+impl UploadSession {
+    /// The upload as it was created
+    pub fn upload(&self) -> &Upload {
+        &self.upload
+    }
+
+    /// The number of bytes added so far
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// The hex encoded SHA-256 checksum of the parts added so far
+    pub fn sha256(&self) -> String {
+        let digest = self.hasher.clone().finalize();
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Upload the next part of the file, at most [`MAX_PART_SIZE`] bytes.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/uploads/add-part)
+    pub async fn add_part(&mut self, data: Bytes) -> Result<UploadPart, Error> {
+        if data.len() > MAX_PART_SIZE {
+            return Err(Error::InvalidParameter(format!("parts must be at most {} bytes, got {}", MAX_PART_SIZE, data.len())));
+        }
+        let received = self.received + data.len() as u64;
+        if received > self.upload.bytes {
+            return Err(Error::UploadMismatch(format!("parts add up to {} bytes, more than the {} bytes of the upload", received, self.upload.bytes)));
+        }
+
+        let form = || {
+            let part = Part::stream_with_length(Body::from(data.clone()), data.len() as u64);
+            Form::new().part("data", part)
+        };
+        let part: UploadPart = requester::upload_parts(&self.options, &self.upload.id, form).await?;
+
+        self.hasher.update(&data);
+        self.received = received;
+        self.part_ids.push(part.id.clone());
+        Ok(part)
+    }
+
+    /// Upload the file at `path` in parts, streaming each part from disk, calling
+    /// `on_progress` with the bytes uploaded so far and the upload's size after every part
+    ///
+    /// A part that fails to upload with a rate limit, server error, timeout or connection
+    /// failure is retried on its own, parts that were already uploaded are kept. If a part still fails after the retries set with
+    /// [`part_retries`](CreateParameters::part_retries) the error is returned, and calling
+    /// this again with the same file resumes from the first part that wasn't uploaded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use oai_rs::uploads;
+    ///
+    /// async {
+    ///     let size = tokio::fs::metadata("./batch.jsonl").await.unwrap().len();
+    ///     let mut session = uploads::build()
+    ///         .upload(String::from("batch.jsonl"), String::from("batch"), size, String::from("text/jsonl"))
+    ///         .part_size(8 * 1024 * 1024)
+    ///         .create()
+    ///         .await
+    ///         .expect("Error Getting Response");
+    ///
+    ///     while let Err(e) = session.upload_file("./batch.jsonl", |uploaded, total| println!("{}/{}", uploaded, total)).await {
+    ///         println!("upload interrupted, resuming: {}", e);
+    ///     }
+    ///     let upload = session.complete().await;
+    ///
+    ///     println!("{:?}", upload);
+    /// };
+    /// ```
+    pub async fn upload_file<P, F>(&mut self, path: P, mut on_progress: F) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+        F: FnMut(u64, u64)
+    {
+        let path = path.as_ref();
+        let size = tokio::fs::metadata(path).await.map_err(Error::Io)?.len();
+        if size != self.upload.bytes {
+            return Err(Error::UploadMismatch(format!("file has {} bytes, expected {}", size, self.upload.bytes)));
+        }
+
+        while self.received < size {
+            let offset = self.received;
+            let len = (size - offset).min(self.part_size as u64);
+            // The checksum is taken from the bytes as they are sent, every attempt starts over
+            // from the parts before this one
+            let sent = Arc::new(Mutex::new((self.hasher.clone(), 0u64)));
+            let form = || {
+                *sent.lock().unwrap() = (self.hasher.clone(), 0);
+                let sent = sent.clone();
+                let chunks = files::read_range(path.to_path_buf(), offset, len).inspect_ok(move |chunk| {
+                    let mut sent = sent.lock().unwrap();
+                    sent.0.update(chunk);
+                    sent.1 += chunk.len() as u64;
+                });
+                Form::new().part("data", Part::stream_with_length(Body::wrap_stream(chunks), len))
+            };
+
+            let mut attempt = 0;
+            let part: UploadPart = loop {
+                match requester::upload_parts(&self.options, &self.upload.id, form).await {
+                    Ok(part) => break part,
+                    Err(e) if attempt >= self.part_retries || retry::error_retry_reason(&e).is_none() => return Err(e),
+                    Err(_) => {
+                        let wait = self.options.client()?.retry_policy.delay(attempt);
+                        attempt += 1;
+                        tokio::time::sleep(wait).await;
+                    }
+                }
+            };
+
+            let (hasher, hashed) = sent.lock().unwrap().clone();
+            if hashed != len {
+                return Err(Error::UploadMismatch(format!("sent {} bytes of a {} byte part", hashed, len)));
+            }
+            self.hasher = hasher;
+            self.received += len;
+            self.part_ids.push(part.id);
+            on_progress(self.received, size);
+        }
+        Ok(())
+    }
+
+    /// Check the parts add up to the upload's size and checksum, and complete the upload.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/uploads/complete)
+    pub async fn complete(self) -> Result<Upload, Error> {
+        if self.received != self.upload.bytes {
+            return Err(Error::UploadMismatch(format!("parts add up to {} bytes, expected {}", self.received, self.upload.bytes)));
+        }
+        if let Some(expected) = &self.expected_sha256 {
+            let actual = self.sha256();
+            if actual != *expected {
+                return Err(Error::UploadMismatch(format!("parts have SHA-256 checksum {}, expected {}", actual, expected)));
+            }
+        }
+
+        let mut query = Query::new();
+        query.push(("part_ids", json!(self.part_ids)));
+        let response: Result<Upload, Error> = requester::upload_complete(&self.options, &self.upload.id, &query).await;
+
+        match response {
+            Ok(t) => Ok(t),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build, UploadSession};
+    use crate::client::Client;
+    use crate::error::Error;
+    use crate::retry::RetryPolicy;
+    use crate::testing::{CannedResponse, Matcher, TestClient};
+    use bytes::Bytes;
+    use serde_json::{json, Value};
+    use sha2::{Digest, Sha256};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn sha256(data: &[u8]) -> String {
+        Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn upload(status: &str) -> Value {
+        json!({
+            "id": "upload_1", "object": "upload", "bytes": 10, "created_at": 1719184911,
+            "filename": "training.jsonl", "purpose": "fine-tune", "status": status, "expires_at": 1719188511
+        })
+    }
+
+    fn part() -> Value {
+        json!({ "id": "part_1", "object": "upload.part", "created_at": 1719184911, "upload_id": "upload_1" })
+    }
+
+    fn uploads() -> TestClient {
+        TestClient::new()
+            .on(Matcher::post("uploads"), CannedResponse::json(&upload("pending")))
+            .on(Matcher::post("uploads/upload_1/parts"), CannedResponse::json(&part()))
+            .on(Matcher::post("uploads/upload_1/complete"), CannedResponse::json(&upload("completed")))
+    }
+
+    async fn session(client: &Client, sha256: Option<&str>) -> UploadSession {
+        let mut upload = build()
+            .client(client)
+            .upload(String::from("training.jsonl"), String::from("fine-tune"), 10, String::from("text/jsonl"));
+        if let Some(sha256) = sha256 {
+            upload = upload.sha256(sha256);
+        }
+        upload.create().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn parts_beyond_the_upload_size_are_rejected() {
+        let test = uploads();
+        let mut session = session(&test.client(), None).await;
+
+        session.add_part(Bytes::from_static(b"cookies")).await.unwrap();
+        let result = session.add_part(Bytes::from_static(b" & cream")).await;
+
+        assert!(matches!(result, Err(Error::UploadMismatch(_))), "{:?}", result);
+        assert_eq!(session.received(), 7);
+        assert_eq!(test.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn complete_checks_the_checksum_before_completing() {
+        let expected = sha256(b"cookies!!!");
+        let test = uploads();
+        let mut session = session(&test.client(), Some(&expected)).await;
+        session.add_part(Bytes::from_static(b"cookies???")).await.unwrap();
+
+        let result = session.complete().await;
+
+        assert!(matches!(&result, Err(Error::UploadMismatch(reason)) if reason.contains(&expected)), "{:?}", result);
+        assert!(test.requests().iter().all(|r| r.path != "uploads/upload_1/complete"));
+    }
+
+    #[tokio::test]
+    async fn complete_sends_the_parts_once_they_add_up() {
+        let test = uploads();
+        let mut session = session(&test.client(), Some(&sha256(b"cookies!!!"))).await;
+        session.add_part(Bytes::from_static(b"cookies")).await.unwrap();
+        session.add_part(Bytes::from_static(b"!!!")).await.unwrap();
+        assert_eq!(session.sha256(), sha256(b"cookies!!!"));
+
+        let upload = session.complete().await.unwrap();
+
+        assert_eq!(upload.status, "completed");
+        assert_eq!(test.last_request().unwrap().body, Some(json!({ "part_ids": ["part_1", "part_1"] })));
+    }
+
+    /// Answers uploads over HTTP on a local port, failing the part requests at the given
+    /// positions with a server error, and returns the client for it and the number of part
+    /// requests received
+    ///
+    /// Parts of a file are streamed from disk, so unlike with a [`TestClient`] every body is
+    /// read in full as the API would.
+    fn serve(failing: Vec<usize>) -> (Client, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let parts = Arc::new(AtomicUsize::new(0));
+        let received = parts.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().map_while(Result::ok) {
+                let (failing, received) = (failing.clone(), received.clone());
+                std::thread::spawn(move || answer(stream, &failing, &received));
+            }
+        });
+        let client = Client::new(String::from("sk-test"))
+            .base_url(base_url)
+            .retry_policy(RetryPolicy::none().backoff(Duration::ZERO, Duration::ZERO));
+        (client, parts)
+    }
+
+    /// Answers the requests on a connection until it's closed
+    fn answer(mut stream: TcpStream, failing: &[usize], parts: &AtomicUsize) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request_line = String::new();
+        while reader.read_line(&mut request_line).unwrap_or(0) > 0 {
+            let path = request_line.split_whitespace().nth(1).unwrap_or_default().to_string();
+            let mut length = 0;
+            let mut header = String::new();
+            while reader.read_line(&mut header).is_ok() && !header.trim_end().is_empty() {
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                header.clear();
+            }
+            reader.read_exact(&mut vec![0; length]).unwrap();
+
+            let (status, body) = match path.as_str() {
+                "/v1/uploads" => (200, upload("pending")),
+                "/v1/uploads/upload_1/complete" => (200, upload("completed")),
+                _ if failing.contains(&parts.fetch_add(1, Ordering::SeqCst)) => {
+                    (500, json!({ "error": { "message": "The server had an error", "type": "server_error" } }))
+                }
+                _ => (200, part()),
+            };
+            let body = body.to_string();
+            write!(stream, "HTTP/1.1 {} Status\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}", status, body.len(), body).unwrap();
+            request_line.clear();
+        }
+    }
+
+    fn scratch_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("oai-rs-{}-{}", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn failed_parts_are_retried_on_their_own() {
+        let path = scratch_file("retried-part", b"cookies!!!");
+        let (client, parts) = serve(vec![1]);
+        let mut session = build()
+            .client(&client)
+            .upload(String::from("training.jsonl"), String::from("fine-tune"), 10, String::from("text/jsonl"))
+            .sha256(&sha256(b"cookies!!!"))
+            .part_size(4)
+            .part_retries(1)
+            .create()
+            .await
+            .unwrap();
+
+        let mut progress = Vec::new();
+        session.upload_file(&path, |uploaded, total| progress.push((uploaded, total))).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(progress, [(4, 10), (8, 10), (10, 10)]);
+        assert_eq!(parts.load(Ordering::SeqCst), 4);
+        assert_eq!(session.complete().await.unwrap().status, "completed");
+    }
+
+    #[tokio::test]
+    async fn uploading_again_skips_the_uploaded_parts() {
+        let path = scratch_file("resumed-upload", b"cookies!!!");
+        let (client, parts) = serve(vec![1]);
+        let mut session = build()
+            .client(&client)
+            .upload(String::from("training.jsonl"), String::from("fine-tune"), 10, String::from("text/jsonl"))
+            .sha256(&sha256(b"cookies!!!"))
+            .part_size(4)
+            .part_retries(0)
+            .create()
+            .await
+            .unwrap();
+
+        let mut progress = Vec::new();
+        let failed = session.upload_file(&path, |uploaded, _| progress.push(uploaded)).await;
+        assert_eq!(failed.err().and_then(|e| e.status()).map(|s| s.as_u16()), Some(500));
+        assert_eq!(session.received(), 4);
+
+        session.upload_file(&path, |uploaded, _| progress.push(uploaded)).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(progress, [4, 8, 10]);
+        assert_eq!(parts.load(Ordering::SeqCst), 4);
+        assert_eq!(session.complete().await.unwrap().status, "completed");
+    }
+}
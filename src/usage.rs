@@ -1,8 +1,348 @@
 use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Prompt, cached prompt and completion prices in dollars per million tokens, by model
+/// identifier, fine-tuned models under their base model with an `ft:` prefix
+const PRICES: [(&str, f64, f64, f64); 27] = [
+    ("gpt-4.1", 2.00, 0.50, 8.00),
+    ("gpt-4.1-mini", 0.40, 0.10, 1.60),
+    ("gpt-4.1-nano", 0.10, 0.025, 0.40),
+    ("gpt-4o", 2.50, 1.25, 10.00),
+    ("gpt-4o-mini", 0.15, 0.075, 0.60),
+    ("gpt-4-turbo", 10.00, 10.00, 30.00),
+    ("gpt-4", 30.00, 30.00, 60.00),
+    ("gpt-4-32k", 60.00, 60.00, 120.00),
+    ("gpt-3.5-turbo", 0.50, 0.50, 1.50),
+    ("o1", 15.00, 7.50, 60.00),
+    ("o1-mini", 1.10, 0.55, 4.40),
+    ("o1-pro", 150.00, 150.00, 600.00),
+    ("o3", 2.00, 0.50, 8.00),
+    ("o3-mini", 1.10, 0.55, 4.40),
+    ("o3-pro", 20.00, 20.00, 80.00),
+    ("o4-mini", 1.10, 0.275, 4.40),
+    ("ft:gpt-4.1", 3.00, 0.75, 12.00),
+    ("ft:gpt-4.1-mini", 0.80, 0.20, 3.20),
+    ("ft:gpt-4.1-nano", 0.20, 0.05, 0.80),
+    ("ft:gpt-4o", 3.75, 1.875, 15.00),
+    ("ft:gpt-4o-mini", 0.30, 0.15, 1.20),
+    ("ft:gpt-3.5-turbo", 3.00, 3.00, 6.00),
+    ("text-embedding-3-small", 0.02, 0.02, 0.0),
+    ("text-embedding-3-large", 0.13, 0.13, 0.0),
+    ("text-embedding-ada-002", 0.10, 0.10, 0.0),
+    ("omni-moderation-latest", 0.0, 0.0, 0.0),
+    ("text-moderation-latest", 0.0, 0.0, 0.0),
+];
+
+/// The list prices of `model`, matching dated snapshots like `gpt-4o-2024-08-06` and
+/// fine-tuned models like `ft:gpt-4o-mini-2024-07-18:acme::abc123` but not variants like
+/// `gpt-4o-audio-preview`, which are priced differently
+fn list_price(model: &str) -> Option<(f64, f64, f64)> {
+    let (kind, model) = match model.strip_prefix("ft:") {
+        Some(fine_tuned) => ("ft:", fine_tuned.split(':').next().unwrap_or_default()),
+        None => ("", model),
+    };
+    let is_snapshot = |suffix: &str| {
+        suffix.is_empty() || suffix.strip_prefix('-').is_some_and(|date| !date.is_empty() && date.bytes().all(|b| b.is_ascii_digit() || b == b'-'))
+    };
+    PRICES.iter()
+        .find(|(id, ..)| id.strip_prefix(kind).and_then(|id| model.strip_prefix(id)).is_some_and(is_snapshot))
+        .map(|&(_, prompt, cached, completion)| (prompt, cached, completion))
+}
+
+/// Breakdown of the tokens in the prompt
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct PromptTokensDetails {
+    /// Tokens read from the prompt cache
+    #[serde(default)]
+    pub cached_tokens: i32,
+    #[serde(default)]
+    pub audio_tokens: i32
+}
+
+/// Breakdown of the tokens in the completion
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct CompletionTokensDetails {
+    /// Tokens used for reasoning by o-series models, not visible in the output
+    #[serde(default)]
+    pub reasoning_tokens: i32,
+    /// Tokens of a predicted output that appeared in the completion
+    #[serde(default)]
+    pub accepted_prediction_tokens: i32,
+    /// Tokens of a predicted output that didn't appear in the completion, still billed
+    #[serde(default)]
+    pub rejected_prediction_tokens: i32,
+    #[serde(default)]
+    pub audio_tokens: i32
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Usage {
+    /// Sent as `input_tokens` by the Responses API
+    #[serde(default, alias = "input_tokens")]
     pub prompt_tokens: i32,
+    /// Sent as `output_tokens` by the Responses API
+    #[serde(default, alias = "output_tokens")]
     pub completion_tokens: i32,
-    pub total_tokens: i32
-}
\ No newline at end of file
+    #[serde(default)]
+    pub total_tokens: i32,
+    #[serde(default, alias = "input_tokens_details", alias = "input_token_details", skip_serializing_if = "Option::is_none")]
+    pub prompt_tokens_details: Option<PromptTokensDetails>,
+    #[serde(default, alias = "output_tokens_details", alias = "output_token_details", skip_serializing_if = "Option::is_none")]
+    pub completion_tokens_details: Option<CompletionTokensDetails>
+}
+
+impl Usage {
+    /// Prompt tokens read from the prompt cache, `0` if not reported
+    pub fn cached_tokens(&self) -> i32 {
+        self.prompt_tokens_details.as_ref().map_or(0, |d| d.cached_tokens)
+    }
+
+    /// Completion tokens used for reasoning, `0` if not reported
+    pub fn reasoning_tokens(&self) -> i32 {
+        self.completion_tokens_details.as_ref().map_or(0, |d| d.reasoning_tokens)
+    }
+
+    /// Tokens of a predicted output that appeared in the completion, `0` if not reported
+    pub fn accepted_prediction_tokens(&self) -> i32 {
+        self.completion_tokens_details.as_ref().map_or(0, |d| d.accepted_prediction_tokens)
+    }
+
+    /// Tokens of a predicted output that didn't appear in the completion, `0` if not reported
+    pub fn rejected_prediction_tokens(&self) -> i32 {
+        self.completion_tokens_details.as_ref().map_or(0, |d| d.rejected_prediction_tokens)
+    }
+
+    /// The estimated cost in dollars of a request to `model` with this usage, `None` for
+    /// models without a known price
+    ///
+    /// Uses list prices per million tokens, with the discount for cached prompt tokens but
+    /// not for batches. Audio, realtime and preview models, and models newer than this
+    /// release, have no known price.
+    pub fn estimated_cost(&self, model: &str) -> Option<f64> {
+        let (prompt, cached, completion) = list_price(model)?;
+        let prompt_tokens = self.prompt_tokens.max(0);
+        let cached_tokens = self.cached_tokens().clamp(0, prompt_tokens);
+        let dollars = (prompt_tokens - cached_tokens) as f64 * prompt
+            + cached_tokens as f64 * cached
+            + self.completion_tokens.max(0) as f64 * completion;
+        Some(dollars / 1_000_000.0)
+    }
+
+    /// Add the usage of another request to this one, e.g. to total up continuation rounds
+    pub fn add(&mut self, other: &Usage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+        if let Some(other) = &other.prompt_tokens_details {
+            let details = self.prompt_tokens_details.get_or_insert_with(PromptTokensDetails::default);
+            details.cached_tokens += other.cached_tokens;
+            details.audio_tokens += other.audio_tokens;
+        }
+        if let Some(other) = &other.completion_tokens_details {
+            let details = self.completion_tokens_details.get_or_insert_with(CompletionTokensDetails::default);
+            details.reasoning_tokens += other.reasoning_tokens;
+            details.accepted_prediction_tokens += other.accepted_prediction_tokens;
+            details.rejected_prediction_tokens += other.rejected_prediction_tokens;
+            details.audio_tokens += other.audio_tokens;
+        }
+    }
+}
+
+/// Usage added up over a number of requests
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    /// Prompt tokens read from the prompt cache
+    #[serde(default)]
+    pub cached_tokens: u64
+}
+
+impl UsageTotals {
+    fn add(&mut self, usage: &Usage) {
+        self.requests += 1;
+        self.prompt_tokens += usage.prompt_tokens.max(0) as u64;
+        self.completion_tokens += usage.completion_tokens.max(0) as u64;
+        self.total_tokens += usage.total_tokens.max(0) as u64;
+        self.cached_tokens += usage.cached_tokens().max(0) as u64;
+    }
+
+    fn merge(&mut self, other: &UsageTotals) {
+        self.requests += other.requests;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+        self.cached_tokens += other.cached_tokens;
+    }
+
+    /// The share of prompt tokens read from the prompt cache, between 0 and 1, `None` if
+    /// there were no prompt tokens
+    ///
+    /// A low ratio for repeated requests with long prompts means their prompts don't share a
+    /// long enough identical prefix, e.g. because something that changes between requests
+    /// comes before the static instructions.
+    pub fn cache_hit_ratio(&self) -> Option<f64> {
+        if self.prompt_tokens == 0 {
+            return None;
+        }
+        Some(self.cached_tokens as f64 / self.prompt_tokens as f64)
+    }
+}
+
+/// Usage recorded by a [`UsageTracker`] at the time of the snapshot
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageSnapshot {
+    /// Usage of requests sent with a labelled client, by label
+    pub labels: HashMap<String, UsageTotals>,
+    /// Usage of requests sent without a label
+    pub unlabelled: UsageTotals,
+    /// Usage of every request, labelled or not, by the model that answered it
+    #[serde(default)]
+    pub models: HashMap<String, UsageTotals>
+}
+
+impl UsageSnapshot {
+    /// Usage of every request, labelled or not
+    pub fn total(&self) -> UsageTotals {
+        self.labels.values().fold(self.unlabelled, |mut total, usage| {
+            total.merge(usage);
+            total
+        })
+    }
+}
+
+/// A total number of completion tokens shared out over continuation rounds
+pub(crate) struct OutputBudget {
+    per_round: Option<u64>,
+    remaining: u64,
+    last_round: u64,
+}
+
+impl OutputBudget {
+    /// A budget of `total` tokens, spending at most `per_round` tokens on each round if set
+    pub(crate) fn new(total: u32, per_round: Option<u64>) -> Self {
+        OutputBudget { per_round, remaining: total as u64, last_round: 0 }
+    }
+
+    /// Whether any tokens are left for another round
+    pub(crate) fn has_remaining(&self) -> bool {
+        self.remaining > 0
+    }
+
+    /// The `max_tokens` of the next round
+    pub(crate) fn next_round(&mut self) -> u64 {
+        self.last_round = self.per_round.map_or(self.remaining, |limit| limit.min(self.remaining));
+        self.last_round
+    }
+
+    /// Take the tokens a round generated off the budget, its whole limit if usage wasn't reported
+    pub(crate) fn spend(&mut self, usage: Option<&Usage>) {
+        let spent = usage.map_or(self.last_round, |u| u.completion_tokens.max(0) as u64);
+        self.remaining = self.remaining.saturating_sub(spent.max(1));
+    }
+}
+
+/// Adds up the usage reported by responses, per label set with [`Client::with_label`](crate::Client::with_label)
+/// and per model
+///
+/// The tracker is shared between all clones of the [`Client`](crate::Client) it is set on.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::{models, usage::UsageTracker, Client};
+///
+/// async {
+///     let tracker = UsageTracker::new();
+///     let client = Client::from_env().expect("Missing configuration").usage_tracker(tracker.clone());
+///
+///     let completions = client.with_label("tenant-42")
+///         .completions(models::CompletionModels::TEXT_DAVINCI_003)
+///         .prompt("Ice cream or cookies?")
+///         .complete()
+///         .await;
+///
+///     let snapshot = tracker.snapshot();
+///     println!("{:?}", snapshot.labels.get("tenant-42"));
+///     for (model, usage) in snapshot.models.iter() {
+///         println!("{}: {:?} of prompt tokens cached", model, usage.cache_hit_ratio());
+///     }
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct UsageTracker {
+    usage: Arc<Mutex<UsageSnapshot>>
+}
+
+impl UsageTracker {
+    pub fn new() -> Self {
+        UsageTracker::default()
+    }
+
+    /// The usage recorded so far
+    pub fn snapshot(&self) -> UsageSnapshot {
+        self.usage.lock().unwrap().clone()
+    }
+
+    /// Clear the recorded usage, returning what was recorded until now
+    pub fn reset(&self) -> UsageSnapshot {
+        std::mem::take(&mut *self.usage.lock().unwrap())
+    }
+
+    pub(crate) fn record(&self, label: Option<&str>, model: Option<&str>, usage: &Usage) {
+        let mut snapshot = self.usage.lock().unwrap();
+        if let Some(model) = model {
+            snapshot.models.entry(model.to_string()).or_default().add(usage);
+        }
+        match label {
+            Some(label) => snapshot.labels.entry(label.to_string()).or_default().add(usage),
+            None => snapshot.unlabelled.add(usage),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PromptTokensDetails, Usage};
+
+    fn usage(prompt: i32, cached: i32, completion: i32) -> Usage {
+        Usage {
+            prompt_tokens: prompt,
+            completion_tokens: completion,
+            total_tokens: prompt + completion,
+            prompt_tokens_details: Some(PromptTokensDetails { cached_tokens: cached, audio_tokens: 0 }),
+            completion_tokens_details: None,
+        }
+    }
+
+    #[test]
+    fn estimated_cost_matches_snapshots_and_fine_tuned_models() {
+        let usage = usage(1_000_000, 0, 1_000_000);
+        let costs = [
+            ("gpt-4o", Some(12.50)),
+            ("gpt-4o-2024-08-06", Some(12.50)),
+            ("gpt-4o-mini-2024-07-18", Some(0.75)),
+            ("gpt-4.1-nano", Some(0.50)),
+            ("gpt-4-0613", Some(90.00)),
+            ("o3-mini-2025-01-31", Some(5.50)),
+            ("ft:gpt-4o-mini-2024-07-18:acme::abc123", Some(1.50)),
+            ("gpt-4o-audio-preview", None),
+            ("gpt-4o-realtime-preview-2024-12-17", None),
+            ("gpt-4.5-preview", None),
+            ("ft:davinci-002:acme::abc123", None),
+        ];
+        for (model, cost) in costs {
+            assert_eq!(usage.estimated_cost(model), cost, "{}", model);
+        }
+    }
+
+    #[test]
+    fn estimated_cost_discounts_cached_prompt_tokens() {
+        assert_eq!(usage(1_000_000, 400_000, 0).estimated_cost("gpt-4o"), Some(2.00));
+        // More cached than prompt tokens is taken as all of them
+        assert_eq!(usage(1_000_000, 2_000_000, 0).estimated_cost("gpt-4o"), Some(1.25));
+    }
+}
@@ -0,0 +1,287 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::requester::{self, Query, RequestOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// When a vector store expires, counted from the time it was last used
+///
+/// Expired vector stores are deleted along with their files' chunks, so they stop counting
+/// towards storage costs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpiresAfter {
+    /// The timestamp the expiry is counted from, always `last_active_at`
+    pub anchor: String,
+    pub days: u32,
+}
+
+impl ExpiresAfter {
+    /// Expire the vector store after it wasn't used for `days` days, between 1 and 365
+    pub fn last_active(days: u32) -> Self {
+        ExpiresAfter {
+            anchor: String::from("last_active_at"),
+            days,
+        }
+    }
+}
+
+/// The chunk size and overlap of a static chunking strategy, in tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StaticChunking {
+    /// Between 100 and 4096
+    pub max_chunk_size_tokens: u32,
+    /// At most half of `max_chunk_size_tokens`
+    pub chunk_overlap_tokens: u32,
+}
+
+/// How files are split into chunks before they're embedded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChunkingStrategy {
+    /// Chunks of 800 tokens overlapping by 400 tokens
+    Auto,
+    Static {
+        #[serde(rename = "static")]
+        config: StaticChunking,
+    },
+    /// Files chunked before chunking strategies were introduced
+    #[serde(other)]
+    Other,
+}
+
+impl ChunkingStrategy {
+    /// Chunks of at most `max_chunk_size_tokens`, overlapping by `chunk_overlap_tokens`
+    pub fn fixed_size(max_chunk_size_tokens: u32, chunk_overlap_tokens: u32) -> Self {
+        ChunkingStrategy::Static {
+            config: StaticChunking {
+                max_chunk_size_tokens,
+                chunk_overlap_tokens,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FileCounts {
+    pub in_progress: u32,
+    pub completed: u32,
+    pub failed: u32,
+    pub cancelled: u32,
+    pub total: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VectorStore {
+    pub id: String,
+    pub object: String,
+    pub created_at: u64,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// `expired`, `in_progress` or `completed`
+    pub status: String,
+    #[serde(default)]
+    pub usage_bytes: u64,
+    #[serde(default)]
+    pub file_counts: FileCounts,
+    #[serde(default)]
+    pub expires_after: Option<ExpiresAfter>,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub last_active_at: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VectorStoreFile {
+    pub id: String,
+    pub object: String,
+    pub created_at: u64,
+    pub vector_store_id: String,
+    /// `in_progress`, `completed`, `cancelled` or `failed`
+    pub status: String,
+    #[serde(default)]
+    pub usage_bytes: u64,
+    #[serde(default)]
+    pub chunking_strategy: Option<ChunkingStrategy>,
+    #[serde(default)]
+    pub last_error: Option<Value>,
+}
+
+/// Available parameters that can be sent with a vector store request
+pub struct Parameters {
+    options: RequestOptions,
+}
+
+pub struct StoreParameters<'a> {
+    options: RequestOptions,
+    query: Query<'a>,
+}
+
+pub struct FileParameters<'a> {
+    options: RequestOptions,
+    vector_store_id: String,
+    file_id: String,
+    query: Query<'a>,
+}
+
+/// Function to create a vector stores request
+///
+/// Call it using [`build`] and either create a new vector `store` or add a `file` to an
+/// existing vector store, see Examples below.
+///
+/// # Examples
+///
+/// ```rust
+/// use oai_rs::vector_stores::{self, ChunkingStrategy, ExpiresAfter};
+///
+/// async {
+///     let expires_after = ExpiresAfter::last_active(7);
+///     let store = vector_stores::build()
+///         .store()
+///         .name("Support articles")
+///         .expires_after(&expires_after)
+///         .create()
+///         .await
+///         .expect("Error Getting Response");
+///
+///     let chunking = ChunkingStrategy::fixed_size(400, 100);
+///     let file = vector_stores::build()
+///         .file(store.id, String::from("file-abc123"))
+///         .chunking_strategy(&chunking)
+///         .create()
+///         .await;
+///
+///     println!("{:?}", file);
+/// };
+/// ```
+pub fn build() -> Parameters {
+    Parameters {
+        options: RequestOptions::default(),
+    }
+}
+
+impl Parameters {
+    /// Send the request using the given client instead of one loaded from the environment
+    pub fn client(mut self, client: &Client) -> Self {
+        self.options.client = Some(client.clone());
+        self
+    }
+
+    /// Creates a vector store.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/vector-stores/create)
+    pub fn store<'a>(self) -> StoreParameters<'a> {
+        StoreParameters {
+            options: self.options,
+            query: Query::new(),
+        }
+    }
+
+    /// Adds the file with the given identifier to the vector store with the given identifier.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/vector-stores-files/createFile)
+    pub fn file<'a>(self, vector_store_id: String, file_id: String) -> FileParameters<'a> {
+        FileParameters {
+            options: self.options,
+            vector_store_id,
+            file_id,
+            query: Query::new(),
+        }
+    }
+}
+
+impl<'a> StoreParameters<'a> {
+    /// The name of the vector store.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/vector-stores/create#vector-stores-create-name)
+    pub fn name(mut self, input: &'a str) -> Self {
+        self.query.push(("name", json!(input)));
+        self
+    }
+
+    /// Files to add to the vector store.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/vector-stores/create#vector-stores-create-file_ids)
+    pub fn file_ids(mut self, input: &'a [&str]) -> Self {
+        self.query.push(("file_ids", json!(input)));
+        self
+    }
+
+    /// When the vector store expires, vector stores don't expire by default.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/vector-stores/create#vector-stores-create-expires_after)
+    pub fn expires_after(mut self, input: &'a ExpiresAfter) -> Self {
+        self.query.push(("expires_after", json!(input)));
+        self
+    }
+
+    /// How the files added with [`file_ids`](StoreParameters::file_ids) are chunked, defaults to `auto`.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/vector-stores/create#vector-stores-create-chunking_strategy)
+    pub fn chunking_strategy(mut self, input: &'a ChunkingStrategy) -> Self {
+        self.query.push(("chunking_strategy", json!(input)));
+        self
+    }
+
+    /// Mark the request as safe to retry even when the client's retry policy
+    /// is restricted to idempotent requests, see [`RetryScope::IdempotentOnly`](crate::retry::RetryScope::IdempotentOnly)
+    pub fn idempotent(mut self) -> Self {
+        self.options.idempotent = true;
+        self
+    }
+
+    /// Set a parameter this crate doesn't model yet, sent as is in the request body
+    ///
+    /// Replaces the value set for `key` by earlier setters, and is replaced by later ones.
+    pub fn extra_param(mut self, key: &'a str, value: Value) -> Self {
+        self.query.push((key, value));
+        self
+    }
+
+    /// Complete the request and send
+    pub async fn create(self) -> Result<VectorStore, Error> {
+        let response: Result<VectorStore, Error> = requester::vector_stores(&self.options, &self.query).await;
+
+        match response {
+            Ok(t) => Ok(t),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<'a> FileParameters<'a> {
+    /// How the file is chunked, defaults to `auto`.
+    ///
+    /// [OpenAI Reference](https://platform.openai.com/docs/api-reference/vector-stores-files/createFile#vector-stores-files-createfile-chunking_strategy)
+    pub fn chunking_strategy(mut self, input: &'a ChunkingStrategy) -> Self {
+        self.query.push(("chunking_strategy", json!(input)));
+        self
+    }
+
+    /// Mark the request as safe to retry even when the client's retry policy
+    /// is restricted to idempotent requests, see [`RetryScope::IdempotentOnly`](crate::retry::RetryScope::IdempotentOnly)
+    pub fn idempotent(mut self) -> Self {
+        self.options.idempotent = true;
+        self
+    }
+
+    /// Set a parameter this crate doesn't model yet, sent as is in the request body
+    ///
+    /// Replaces the value set for `key` by earlier setters, and is replaced by later ones.
+    pub fn extra_param(mut self, key: &'a str, value: Value) -> Self {
+        self.query.push((key, value));
+        self
+    }
+
+    /// Complete the request and send
+    pub async fn create(mut self) -> Result<VectorStoreFile, Error> {
+        self.query.push(("file_id", json!(self.file_id)));
+
+        let response: Result<VectorStoreFile, Error> = requester::vector_store_files(&self.options, &self.vector_store_id, &self.query).await;
+
+        match response {
+            Ok(t) => Ok(t),
+            Err(e) => Err(e),
+        }
+    }
+}